@@ -1,46 +1,107 @@
 use crate::ast::*;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ParamDecl {
     pub name: String,
     pub param_type: TypeName,
+    /// Value used when a call leaves the argument out.
+    pub default: Option<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FunDef {
     pub name: String,
     pub params: Vec<ParamDecl>,
     pub return_type: TypeName,
-    pub statements: Option<Stmt>,
+    pub statements: Vec<Stmt>,
+    pub span: Span,
+    /// Comment written directly above the declaration, verbatim, when the
+    /// source was lexed with comments kept.
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SuperCall {
+    pub args: Vec<Expr>,
+    pub span: Span,
+    /// Number of constructor statements written before the call. JS only
+    /// allows this when none of them touch `this`, so anything but `0` is
+    /// reported.
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Constructor {
     pub params: Vec<ParamDecl>,
-    pub super_call: Option<Vec<Expr>>,
-    pub statements: Option<Stmt>,
+    pub super_call: Option<SuperCall>,
+    pub statements: Vec<Stmt>,
+}
+
+impl Constructor {
+    /// The body statements before and after the `super(...)` call.
+    pub fn split_at_super(&self) -> (&[Stmt], &[Stmt]) {
+        let position = self
+            .super_call
+            .as_ref()
+            .map_or(0, |super_call| super_call.position.min(self.statements.len()));
+        self.statements.split_at(position)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MethDef {
     pub name: String,
     pub params: Vec<ParamDecl>,
     pub return_type: TypeName,
-    pub statements: Option<Stmt>,
+    pub statements: Vec<Stmt>,
+    /// Comment written directly above the declaration, verbatim, when the
+    /// source was lexed with comments kept.
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ClassDef {
     pub name: String,
     pub extends: Option<String>,
     pub vars: Vec<VarDeclStmt>,
     pub constructor: Constructor,
     pub methods: Vec<MethDef>,
+    pub span: Span,
+    /// Comment written directly above the declaration, verbatim, when the
+    /// source was lexed with comments kept.
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Entry {
     pub statements: Vec<Stmt>,
     pub class_defs: Vec<ClassDef>,
     pub fun_defs: Vec<FunDef>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only compiles while declarations hold the lexer's `TypeName`, the one
+    /// the parser produces, rather than a copy of their own.
+    fn takes_lexer_type(type_name: crate::lexer::TypeName) -> crate::lexer::TypeName {
+        type_name
+    }
+
+    #[test]
+    fn test_declarations_use_lexer_type_name() {
+        let param = ParamDecl::default();
+        let fun = FunDef { return_type: crate::lexer::TypeName::Int, ..Default::default() };
+        assert_eq!(takes_lexer_type(param.param_type), crate::lexer::TypeName::Void);
+        assert_eq!(takes_lexer_type(fun.return_type), crate::lexer::TypeName::Int);
+    }
+}
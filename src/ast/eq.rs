@@ -0,0 +1,161 @@
+use crate::ast::*;
+
+/// Compares two expressions while ignoring every `Span`, so the same code
+/// parsed at different offsets is considered equal. Explicit parentheses
+/// (`Grouped`) still count as structure.
+pub fn structurally_eq(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::IntegerLiteral(a), Expr::IntegerLiteral(b)) => a.value == b.value,
+        (Expr::StringLiteral(a), Expr::StringLiteral(b)) => a.value == b.value,
+        (Expr::CharLiteral(a), Expr::CharLiteral(b)) => a.value == b.value,
+        (Expr::BooleanLiteral(a), Expr::BooleanLiteral(b)) => a.value == b.value,
+        (Expr::Nil(_), Expr::Nil(_)) => true,
+        (Expr::Variable(a), Expr::Variable(b)) => a.name == b.name,
+        (Expr::Binary(a), Expr::Binary(b)) => {
+            a.operator == b.operator
+                && structurally_eq(&a.left, &b.left)
+                && structurally_eq(&a.right, &b.right)
+        }
+        (Expr::Unary(a), Expr::Unary(b)) => a.operator == b.operator && structurally_eq(&a.expr, &b.expr),
+        (Expr::FunCall(a), Expr::FunCall(b)) => a.callee == b.callee && exprs_eq(&a.args, &b.args),
+        (Expr::MethCall(a), Expr::MethCall(b)) => {
+            a.meth == b.meth && structurally_eq(&a.object, &b.object) && exprs_eq(&a.args, &b.args)
+        }
+        (Expr::Field(a), Expr::Field(b)) => a.field == b.field && structurally_eq(&a.object, &b.object),
+        (Expr::New(a), Expr::New(b)) => a.class_name == b.class_name && exprs_eq(&a.args, &b.args),
+        (Expr::This(_), Expr::This(_)) | (Expr::Super(_), Expr::Super(_)) => true,
+        (Expr::Println(a), Expr::Println(b)) => exprs_eq(&a.args, &b.args),
+        (Expr::Print(a), Expr::Print(b)) => exprs_eq(&a.args, &b.args),
+        (Expr::Len(a), Expr::Len(b)) => structurally_eq(&a.arg, &b.arg),
+        (Expr::TypeNameOf(a), Expr::TypeNameOf(b)) => structurally_eq(&a.arg, &b.arg),
+        (Expr::Lambda(a), Expr::Lambda(b)) => {
+            a.return_type == b.return_type
+                && a.params.len() == b.params.len()
+                && a.params.iter().zip(&b.params).all(|(a, b)| {
+                    a.name == b.name
+                        && a.param_type == b.param_type
+                        && match (&a.default, &b.default) {
+                            (Some(a), Some(b)) => structurally_eq(a, b),
+                            (None, None) => true,
+                            _ => false,
+                        }
+                })
+                && a.body.len() == b.body.len()
+                && a.body.iter().zip(&b.body).all(|(a, b)| structurally_eq_stmt(a, b))
+        }
+        (Expr::Assert(a), Expr::Assert(b)) => {
+            structurally_eq(&a.cond, &b.cond)
+                && match (&a.message, &b.message) {
+                    (Some(a), Some(b)) => structurally_eq(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        }
+        (Expr::Grouped(a, _), Expr::Grouped(b, _)) => structurally_eq(a, b),
+        (Expr::Empty, Expr::Empty) => true,
+        _ => false,
+    }
+}
+
+/// [`structurally_eq`] for statements.
+pub fn structurally_eq_stmt(a: &Stmt, b: &Stmt) -> bool {
+    match (a, b) {
+        (Stmt::Expr(a), Stmt::Expr(b)) => structurally_eq(&a.expr, &b.expr),
+        (Stmt::VarDecl(a), Stmt::VarDecl(b)) => {
+            a.name == b.name && a.var_type == b.var_type && a.mutable == b.mutable
+        }
+        (Stmt::Assign(a), Stmt::Assign(b)) => a.name == b.name && structurally_eq(&a.expr, &b.expr),
+        (Stmt::VarDeclWithAssign(a), Stmt::VarDeclWithAssign(b)) => {
+            a.name == b.name
+                && a.var_type == b.var_type
+                && a.mutable == b.mutable
+                && structurally_eq(&a.expr, &b.expr)
+        }
+        (Stmt::MultiVarDecl(a), Stmt::MultiVarDecl(b)) => {
+            a.mutable == b.mutable
+                && a.decls.len() == b.decls.len()
+                && a.decls.iter().zip(&b.decls).all(|(a, b)| structurally_eq_stmt(a, b))
+        }
+        (Stmt::While(a), Stmt::While(b)) => {
+            structurally_eq(&a.condition, &b.condition) && structurally_eq_stmt(&a.body, &b.body)
+        }
+        (Stmt::DoWhile(a), Stmt::DoWhile(b)) => {
+            structurally_eq_stmt(&a.body, &b.body) && structurally_eq(&a.condition, &b.condition)
+        }
+        (Stmt::ForRange(a), Stmt::ForRange(b)) => {
+            a.var == b.var
+                && a.inclusive == b.inclusive
+                && structurally_eq(&a.start, &b.start)
+                && structurally_eq(&a.end, &b.end)
+                && structurally_eq_stmt(&a.body, &b.body)
+        }
+        (Stmt::If(a), Stmt::If(b)) => {
+            structurally_eq(&a.condition, &b.condition)
+                && structurally_eq_stmt(&a.then_branch, &b.then_branch)
+                && match (&a.else_branch, &b.else_branch) {
+                    (Some(a), Some(b)) => structurally_eq_stmt(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        }
+        (Stmt::Match(a), Stmt::Match(b)) => {
+            structurally_eq(&a.scrutinee, &b.scrutinee)
+                && a.arms.len() == b.arms.len()
+                && a.arms.iter().zip(&b.arms).all(|(a, b)| {
+                    structurally_eq(&a.pattern, &b.pattern) && structurally_eq_stmt(&a.body, &b.body)
+                })
+                && match (&a.default, &b.default) {
+                    (Some(a), Some(b)) => structurally_eq_stmt(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        }
+        (Stmt::Break(_), Stmt::Break(_)) | (Stmt::Continue(_), Stmt::Continue(_)) => true,
+        (Stmt::Return(a), Stmt::Return(b)) => match (&a.value, &b.value) {
+            (Some(a), Some(b)) => structurally_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        },
+        (Stmt::Block(a), Stmt::Block(b)) => stmts_eq(&a.statements, &b.statements),
+        (Stmt::Empty, Stmt::Empty) => true,
+        _ => false,
+    }
+}
+
+fn exprs_eq(a: &[Expr], b: &[Expr]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| structurally_eq(a, b))
+}
+
+fn stmts_eq(a: &[Stmt], b: &[Stmt]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| structurally_eq_stmt(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn statements(input: &str) -> Vec<Stmt> {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        Parser::new(tokens).parse().unwrap().statements
+    }
+
+    #[test]
+    fn test_structural_equality_ignores_spans() {
+        let stmts = statements("1 + 1;\n   1 + 1;");
+        let (Stmt::Expr(a), Stmt::Expr(b)) = (&stmts[0], &stmts[1]) else {
+            panic!("expected expression statements");
+        };
+        assert_ne!(a.expr, b.expr);
+        assert!(structurally_eq(&a.expr, &b.expr));
+        assert!(structurally_eq_stmt(&stmts[0], &stmts[1]));
+    }
+
+    #[test]
+    fn test_structural_inequality() {
+        let stmts = statements("1 + 1; 1 - 1; (1 + 1); if (x) { y = 1; } if (x) { y = 1; } else {}");
+        assert!(!structurally_eq_stmt(&stmts[0], &stmts[1]));
+        assert!(!structurally_eq_stmt(&stmts[0], &stmts[2]));
+        assert!(!structurally_eq_stmt(&stmts[3], &stmts[4]));
+    }
+}
@@ -2,10 +2,14 @@ use crate::ast::*;
 use std::fmt;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum Expr {
     IntegerLiteral(IntegerLiteral),
     StringLiteral(StringLiteral),
+    CharLiteral(CharLiteral),
     BooleanLiteral(BooleanLiteral),
+    Nil(NilExpr),
     Variable(Variable),
     Binary(BinaryExpr),
     Unary(UnaryExpr),
@@ -14,38 +18,63 @@ pub enum Expr {
     Field(Field),
     New(NewExpr),
     This(ThisExpr),
+    Super(SuperExpr),
     Println(PrintlnExpr),
     Print(PrintExpr),
+    Assert(AssertExpr),
+    Len(LenExpr),
+    TypeNameOf(TypeNameOfExpr),
+    Lambda(LambdaExpr),
     Grouped(Box<Expr>, Span),
     #[default]
     Empty,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IntegerLiteral {
     pub value: i64,
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StringLiteral {
     pub value: String,
     pub span: Span,
 }
 
+/// A single-quoted character, emitted as a one-character JS string.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CharLiteral {
+    pub value: char,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BooleanLiteral {
     pub value: bool,
     pub span: Span,
 }
 
+/// The `nil` literal, emitted as JS `null`.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NilExpr {
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Variable {
     pub name: String,
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BinaryExpr {
     pub left: Box<Expr>,
     pub operator: BinaryOp,
@@ -54,6 +83,7 @@ pub struct BinaryExpr {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct UnaryExpr {
     pub operator: UnaryOp,
     pub expr: Box<Expr>,
@@ -61,11 +91,13 @@ pub struct UnaryExpr {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BinaryOp {
     Add,
     Subtract,
     Multiply,
     Divide,
+    Power,
     #[default]
     Equal,
     NotEqual,
@@ -75,6 +107,11 @@ pub enum BinaryOp {
     LessEqual,
     Or,
     And,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 impl fmt::Display for BinaryOp {
@@ -84,6 +121,7 @@ impl fmt::Display for BinaryOp {
             BinaryOp::Subtract => write!(f, "-"),
             BinaryOp::Multiply => write!(f, "*"),
             BinaryOp::Divide => write!(f, "/"),
+            BinaryOp::Power => write!(f, "**"),
             BinaryOp::Equal => write!(f, "=="),
             BinaryOp::NotEqual => write!(f, "!="),
             BinaryOp::Greater => write!(f, ">"),
@@ -92,16 +130,64 @@ impl fmt::Display for BinaryOp {
             BinaryOp::LessEqual => write!(f, "<="),
             BinaryOp::Or => write!(f, "||"),
             BinaryOp::And => write!(f, "&&"),
+            BinaryOp::BitAnd => write!(f, "&"),
+            BinaryOp::BitOr => write!(f, "|"),
+            BinaryOp::BitXor => write!(f, "^"),
+            BinaryOp::ShiftLeft => write!(f, "<<"),
+            BinaryOp::ShiftRight => write!(f, ">>"),
         }
     }
 }
 
+/// How tightly `op` binds; higher binds tighter.
+///
+/// | Level | Operators         |
+/// |-------|-------------------|
+/// | 1     | `\|\|`            |
+/// | 2     | `&&`              |
+/// | 3     | `\|`              |
+/// | 4     | `^`               |
+/// | 5     | `&`               |
+/// | 6     | `==` `!=`         |
+/// | 7     | `<` `>` `<=` `>=` |
+/// | 8     | `<<` `>>`         |
+/// | 9     | `+` `-`           |
+/// | 10    | `*` `/`           |
+/// | 11    | `**`              |
+///
+/// The levels match JavaScript's. The parser doesn't let comparisons chain,
+/// so splitting equality from the relational operators only matters when
+/// deciding which parentheses generated code needs.
+pub fn precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Or => 1,
+        BinaryOp::And => 2,
+        BinaryOp::BitOr => 3,
+        BinaryOp::BitXor => 4,
+        BinaryOp::BitAnd => 5,
+        BinaryOp::Equal | BinaryOp::NotEqual => 6,
+        BinaryOp::Greater | BinaryOp::Less | BinaryOp::GreaterEqual | BinaryOp::LessEqual => 7,
+        BinaryOp::ShiftLeft | BinaryOp::ShiftRight => 8,
+        BinaryOp::Add | BinaryOp::Subtract => 9,
+        BinaryOp::Multiply | BinaryOp::Divide => 10,
+        BinaryOp::Power => 11,
+    }
+}
+
+/// Whether `a op b op c` groups as `a op (b op c)`. Only `**` does; every
+/// other operator groups to the left.
+pub fn is_right_associative(op: &BinaryOp) -> bool {
+    *op == BinaryOp::Power
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UnaryOp {
     #[default]
     Not,
     Negate,
     Plus,
+    BitNot,
 }
 
 impl fmt::Display for UnaryOp {
@@ -110,11 +196,13 @@ impl fmt::Display for UnaryOp {
             UnaryOp::Not => write!(f, "!"),
             UnaryOp::Negate => write!(f, "-"),
             UnaryOp::Plus => write!(f, "+"),
+            UnaryOp::BitNot => write!(f, "~"),
         }
     }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FunCall {
     pub callee: String,
     pub args: Vec<Expr>,
@@ -122,6 +210,7 @@ pub struct FunCall {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MethCall {
     pub object: Box<Expr>,
     pub meth: String,
@@ -130,6 +219,7 @@ pub struct MethCall {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Field {
     pub object: Box<Expr>,
     pub field: String,
@@ -137,6 +227,7 @@ pub struct Field {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NewExpr {
     pub class_name: String,
     pub args: Vec<Expr>,
@@ -144,18 +235,81 @@ pub struct NewExpr {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ThisExpr {
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SuperExpr {
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PrintExpr {
-    pub arg: Box<Expr>,
+    pub args: Vec<Expr>,
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PrintlnExpr {
+    pub args: Vec<Expr>,
+    pub span: Span,
+}
+
+/// `assert(cond)` or `assert(cond, message)`, which throws an `Error` at
+/// runtime when the condition is falsy.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AssertExpr {
+    pub cond: Box<Expr>,
+    pub message: Option<Box<Expr>>,
+    pub span: Span,
+}
+
+/// `len(s)`, the length of a string as JS counts it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LenExpr {
     pub arg: Box<Expr>,
     pub span: Span,
 }
+
+/// `typeName(x)`, the name of `x`'s static type as a string. It is worked
+/// out by semantic analysis, so `x` itself is never evaluated.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeNameOfExpr {
+    pub arg: Box<Expr>,
+    pub span: Span,
+}
+
+/// `fun (x: Int) -> Int { ... }`, an anonymous function used as a value.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LambdaExpr {
+    pub params: Vec<ParamDecl>,
+    pub return_type: TypeName,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence_order() {
+        let levels = [BinaryOp::Multiply, BinaryOp::Add, BinaryOp::Equal, BinaryOp::And, BinaryOp::Or]
+            .map(|op| precedence(&op));
+        assert!(levels.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", levels);
+
+        assert!(precedence(&BinaryOp::Power) > precedence(&BinaryOp::Multiply));
+        assert_eq!(precedence(&BinaryOp::Subtract), precedence(&BinaryOp::Add));
+        assert!(is_right_associative(&BinaryOp::Power));
+        assert!(!is_right_associative(&BinaryOp::Subtract));
+    }
+}
@@ -1,4 +1,5 @@
 mod decl;
+mod eq;
 mod expr;
 mod printer;
 mod stmt;
@@ -7,6 +8,8 @@ mod visitor;
 use crate::lexer::Span;
 use crate::lexer::TypeName;
 pub use decl::*;
+pub use eq::*;
 pub use expr::*;
 pub use printer::*;
 pub use stmt::*;
+pub use visitor::*;
@@ -84,7 +84,9 @@ impl PrettyPrint for Expr {
         match self {
             Expr::IntegerLiteral(lit) => write!(f, "{}", lit.value.to_string().cyan()),
             Expr::StringLiteral(lit) => write!(f, "\"{}\"", lit.value.green()),
+            Expr::CharLiteral(lit) => write!(f, "'{}'", lit.value.to_string().green()),
             Expr::BooleanLiteral(lit) => write!(f, "{}", lit.value.to_string().yellow()),
+            Expr::Nil(_) => write!(f, "{}", "nil".yellow()),
             Expr::Variable(var) => write!(f, "{}", var.name.cyan().italic()),
             Expr::Binary(bin_expr) => {
                 write!(f, "(")?;
@@ -134,16 +136,45 @@ impl PrettyPrint for Expr {
                 write!(f, ")")
             }
             Expr::This(_) => write!(f, "{}", "this".yellow().bold()),
+            Expr::Super(_) => write!(f, "{}", "super".yellow().bold()),
             Expr::Println(expr) => {
                 write!(f, "{}(", "println".blue().bold())?;
-                expr.arg.pretty_print(f, indent)?;
+                print_list(f, &expr.args, indent, ", ")?;
                 write!(f, ")")
             }
             Expr::Print(expr) => {
                 write!(f, "{}(", "print".blue().bold())?;
+                print_list(f, &expr.args, indent, ", ")?;
+                write!(f, ")")
+            }
+            Expr::Len(expr) => {
+                write!(f, "{}(", "len".blue().bold())?;
+                expr.arg.pretty_print(f, indent)?;
+                write!(f, ")")
+            }
+            Expr::TypeNameOf(expr) => {
+                write!(f, "{}(", "typeName".blue().bold())?;
                 expr.arg.pretty_print(f, indent)?;
                 write!(f, ")")
             }
+            Expr::Lambda(lambda) => {
+                write!(f, "{} (", "fun".magenta().bold())?;
+                print_list(f, &lambda.params, indent, ", ")?;
+                writeln!(f, ") -> {} {{", lambda.return_type.to_string().blue().bold())?;
+                for stmt in &lambda.body {
+                    stmt.pretty_print(f, indent + 1)?;
+                }
+                write!(f, "{}}}", indent_str(indent))
+            }
+            Expr::Assert(expr) => {
+                write!(f, "{}(", "assert".blue().bold())?;
+                expr.cond.pretty_print(f, indent)?;
+                if let Some(message) = &expr.message {
+                    write!(f, ", ")?;
+                    message.pretty_print(f, indent)?;
+                }
+                write!(f, ")")
+            }
             Expr::Grouped(expr, _) => {
                 write!(f, "(")?;
                 expr.pretty_print(f, indent)?;
@@ -172,7 +203,7 @@ impl PrettyPrint for Stmt {
                     f,
                     "{}{} {}: {};",
                     ind,
-                    "let".magenta(),
+                    var_decl.keyword().magenta(),
                     var_decl.name.cyan(),
                     var_decl.var_type.to_string().blue().bold()
                 )
@@ -183,17 +214,16 @@ impl PrettyPrint for Stmt {
                 writeln!(f, ";")
             }
             Stmt::VarDeclWithAssign(var_decl) => {
-                write!(
-                    f,
-                    "{}{} {}: {} = ",
-                    ind,
-                    "let".magenta(),
-                    var_decl.name.cyan(),
-                    var_decl.var_type.to_string().blue().bold()
-                )?;
+                write!(f, "{}{} {}", ind, var_decl.keyword().magenta(), var_decl.name.cyan())?;
+                if let Some(var_type) = &var_decl.var_type {
+                    write!(f, ": {}", var_type.to_string().blue().bold())?;
+                }
+                write!(f, " = ")?;
                 var_decl.expr.pretty_print(f, indent)?;
                 writeln!(f, ";")
             }
+            // one line per binding, as if each had its own keyword
+            Stmt::MultiVarDecl(multi) => multi.decls.iter().try_for_each(|decl| decl.pretty_print(f, indent)),
             Stmt::While(while_stmt) => {
                 write!(f, "{}{} (", ind, "while".magenta().bold())?;
                 while_stmt.condition.pretty_print(f, indent)?;
@@ -201,6 +231,22 @@ impl PrettyPrint for Stmt {
                 while_stmt.body.pretty_print(f, indent + 1)?;
                 writeln!(f, "{}}}", ind)
             }
+            Stmt::ForRange(for_range) => {
+                write!(f, "{}{} ({} {} ", ind, "for".magenta().bold(), for_range.var, "in".magenta().bold())?;
+                for_range.start.pretty_print(f, indent)?;
+                write!(f, "{}", if for_range.inclusive { "..=" } else { ".." })?;
+                for_range.end.pretty_print(f, indent)?;
+                writeln!(f, ") {{")?;
+                for_range.body.pretty_print(f, indent + 1)?;
+                writeln!(f, "{}}}", ind)
+            }
+            Stmt::DoWhile(do_while) => {
+                writeln!(f, "{}{} {{", ind, "do".magenta().bold())?;
+                do_while.body.pretty_print(f, indent + 1)?;
+                write!(f, "{}}} {} (", ind, "while".magenta().bold())?;
+                do_while.condition.pretty_print(f, indent)?;
+                writeln!(f, ");")
+            }
             Stmt::If(if_stmt) => {
                 write!(f, "{}{} (", ind, "if".magenta().bold())?;
                 if_stmt.condition.pretty_print(f, indent)?;
@@ -216,7 +262,27 @@ impl PrettyPrint for Stmt {
                     Ok(())
                 }
             }
+            Stmt::Match(match_stmt) => {
+                write!(f, "{}{} (", ind, "match".magenta().bold())?;
+                match_stmt.scrutinee.pretty_print(f, indent)?;
+                writeln!(f, ") {{")?;
+                let inner = indent_str(indent + 1);
+                for arm in &match_stmt.arms {
+                    write!(f, "{}", inner)?;
+                    arm.pattern.pretty_print(f, indent + 1)?;
+                    writeln!(f, " => {{")?;
+                    arm.body.pretty_print(f, indent + 2)?;
+                    writeln!(f, "{}}}", inner)?;
+                }
+                if let Some(default) = &match_stmt.default {
+                    writeln!(f, "{}_ => {{", inner)?;
+                    default.pretty_print(f, indent + 2)?;
+                    writeln!(f, "{}}}", inner)?;
+                }
+                writeln!(f, "{}}}", ind)
+            }
             Stmt::Break(_) => writeln!(f, "{}{};", ind, "break".red().bold()),
+            Stmt::Continue(_) => writeln!(f, "{}{};", ind, "continue".red().bold()),
             Stmt::Return(ret) => {
                 write!(f, "{}{}", ind, "return".red().bold())?;
                 if let Some(value) = &ret.value {
@@ -237,13 +303,18 @@ impl PrettyPrint for Stmt {
 }
 
 impl PrettyPrint for ParamDecl {
-    fn pretty_print(&self, f: &mut fmt::Formatter<'_>, _indent: usize) -> fmt::Result {
+    fn pretty_print(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
         write!(
             f,
             "{}: {}",
             self.name.cyan(),
             self.param_type.to_string().blue().bold()
-        )
+        )?;
+        if let Some(default) = &self.default {
+            write!(f, " = ")?;
+            default.pretty_print(f, indent)?;
+        }
+        Ok(())
     }
 }
 
@@ -268,7 +339,7 @@ impl PrettyPrint for FunDef {
 
         writeln!(f, ") -> {} {{", self.return_type.to_string().blue().bold())?;
 
-        if let Some(stmt) = &self.statements {
+        for stmt in &self.statements {
             stmt.pretty_print(f, indent + 1)?;
         }
 
@@ -291,7 +362,13 @@ impl PrettyPrint for Constructor {
 
         writeln!(f, ") {{")?;
 
-        if let Some(args) = &self.super_call {
+        let (before_super, after_super) = self.split_at_super();
+        for stmt in before_super {
+            stmt.pretty_print(f, indent + 1)?;
+        }
+
+        if let Some(super_call) = &self.super_call {
+            let args = &super_call.args;
             write!(f, "{}  {}(", ind, "super".yellow().bold())?;
             for (i, arg) in args.iter().enumerate() {
                 arg.pretty_print(f, indent + 1)?;
@@ -302,7 +379,7 @@ impl PrettyPrint for Constructor {
             writeln!(f, ");")?;
         }
 
-        if let Some(stmt) = &self.statements {
+        for stmt in after_super {
             stmt.pretty_print(f, indent + 1)?;
         }
 
@@ -331,7 +408,7 @@ impl PrettyPrint for MethDef {
 
         writeln!(f, ") -> {} {{", self.return_type.to_string().blue().bold())?;
 
-        if let Some(stmt) = &self.statements {
+        for stmt in &self.statements {
             stmt.pretty_print(f, indent + 1)?;
         }
 
@@ -403,7 +480,7 @@ impl PrettyPrint for VarDeclStmt {
             f,
             "{}{} {}: {};",
             ind,
-            "let".magenta(),
+            self.keyword().magenta(),
             self.name.cyan(),
             self.var_type.to_string().blue().bold()
         )
@@ -2,34 +2,75 @@ use crate::ast::*;
 use crate::lexer::Span;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum Stmt {
     Expr(ExprStmt),
     VarDecl(VarDeclStmt),
     Assign(AssignStmt),
     VarDeclWithAssign(VarDeclWithAssign),
+    MultiVarDecl(MultiVarDeclStmt),
     While(WhileStmt),
+    DoWhile(DoWhileStmt),
+    ForRange(ForRangeStmt),
     If(IfStmt),
+    Match(MatchStmt),
     Break(BreakStmt),
+    Continue(ContinueStmt),
     Return(ReturnStmt),
     Block(BlockStmt),
     #[default]
     Empty,
 }
 
+impl Stmt {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Stmt::Expr(s) => Some(s.span),
+            Stmt::VarDecl(s) => Some(s.span),
+            Stmt::Assign(s) => Some(s.span),
+            Stmt::VarDeclWithAssign(s) => Some(s.span),
+            Stmt::MultiVarDecl(s) => Some(s.span),
+            Stmt::While(s) => Some(s.span),
+            Stmt::DoWhile(s) => Some(s.span),
+            Stmt::ForRange(s) => Some(s.span),
+            Stmt::If(s) => Some(s.span),
+            Stmt::Match(s) => Some(s.span),
+            Stmt::Break(s) => Some(s.span),
+            Stmt::Continue(s) => Some(s.span),
+            Stmt::Return(s) => Some(s.span),
+            Stmt::Block(s) => Some(s.span),
+            Stmt::Empty => None,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExprStmt {
     pub expr: Box<Expr>,
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VarDeclStmt {
     pub name: String,
     pub var_type: TypeName,
+    /// `false` for bindings declared with `const`.
+    pub mutable: bool,
     pub span: Span,
 }
 
+impl VarDeclStmt {
+    /// The keyword the binding was declared with.
+    pub fn keyword(&self) -> &'static str {
+        if self.mutable { "let" } else { "const" }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AssignStmt {
     pub name: String,
     pub expr: Box<Expr>,
@@ -37,21 +78,74 @@ pub struct AssignStmt {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VarDeclWithAssign {
     pub name: String,
-    pub var_type: TypeName,
+    pub var_type: Option<TypeName>,
     pub expr: Box<Expr>,
+    /// `false` for bindings declared with `const`.
+    pub mutable: bool,
     pub span: Span,
 }
 
+impl VarDeclWithAssign {
+    /// The keyword the binding was declared with.
+    pub fn keyword(&self) -> &'static str {
+        if self.mutable { "let" } else { "const" }
+    }
+}
+
+/// `let a: Int, b: Int = 0;`, several bindings under one keyword. Each name
+/// has its own type and optional initializer, so `decls` holds one
+/// [`Stmt::VarDecl`] or [`Stmt::VarDeclWithAssign`] per name, in order.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MultiVarDeclStmt {
+    pub decls: Vec<Stmt>,
+    /// `false` for bindings declared with `const`.
+    pub mutable: bool,
+    pub span: Span,
+}
+
+impl MultiVarDeclStmt {
+    /// The keyword the bindings were declared with.
+    pub fn keyword(&self) -> &'static str {
+        if self.mutable { "let" } else { "const" }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct WhileStmt {
     pub condition: Box<Expr>,
     pub body: Box<Stmt>,
     pub span: Span,
 }
 
+/// `do { ... } while (condition);`, whose body runs before the first check.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DoWhileStmt {
+    pub body: Box<Stmt>,
+    pub condition: Box<Expr>,
+    pub span: Span,
+}
+
+/// `for (var in start..end) body`, counting `var` up from `start`.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ForRangeStmt {
+    pub var: String,
+    pub start: Box<Expr>,
+    pub end: Box<Expr>,
+    /// `true` for `..=`, which includes `end` itself.
+    pub inclusive: bool,
+    pub body: Box<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IfStmt {
     pub condition: Box<Expr>,
     pub then_branch: Box<Stmt>,
@@ -59,18 +153,49 @@ pub struct IfStmt {
     pub span: Span,
 }
 
+/// `match (scrutinee) { 1 => ... _ => ... }`, emitted as a JS `switch` whose
+/// cases never fall through.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MatchStmt {
+    pub scrutinee: Box<Expr>,
+    pub arms: Vec<MatchArm>,
+    /// The body of the `_` arm, if there is one.
+    pub default: Option<Box<Stmt>>,
+    pub span: Span,
+}
+
+/// One `pattern => body` arm; the pattern is an integer, string or boolean
+/// literal.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MatchArm {
+    pub pattern: Expr,
+    pub body: Box<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BlockStmt {
     pub statements: Vec<Stmt>,
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BreakStmt {
     pub span: Span,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ContinueStmt {
+    pub span: Span,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ReturnStmt {
     pub value: Option<Box<Expr>>,
     pub span: Span,
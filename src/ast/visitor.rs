@@ -1,13 +1,371 @@
 use crate::ast::*;
+
+/// Read-only traversal over the AST. Every method defaults to the matching
+/// `walk_*` function, which visits the node's children, so an implementation
+/// only overrides the nodes it cares about and calls `walk_*` itself when it
+/// still wants to descend.
 pub trait Visitor {
-    fn visit_integer_literal(&self, integer_literal: &IntegerLiteral) -> Result<(), String>;
-    fn visit_string_literal(&self, string_literal: &StringLiteral) -> Result<(), String>;
-    fn visit_boolean_literal(&self, boolean_literal: &BooleanLiteral) -> Result<(), String>;
-    fn visit_variable(&self, variable: &Variable) -> Result<(), String>;
-    fn visit_binary_expr(&self, binary_expr: &BinaryExpr) -> Result<(), String>;
-    fn visit_fun_call(&self, fun_call: &FunCall) -> Result<(), String>;
-    fn visit_meth_call(&self, meth_call: &MethCall) -> Result<(), String>;
-    fn visit_new(&self, new: &NewExpr) -> Result<(), String>;
-    fn visit_this(&self, this: &ThisExpr) -> Result<(), String>;
-    fn visit_println(&self, println: &PrintlnExpr) -> Result<(), String>;
+    fn visit_entry(&mut self, entry: &Entry) {
+        walk_entry(self, entry);
+    }
+
+    fn visit_fun_def(&mut self, fun_def: &FunDef) {
+        walk_fun_def(self, fun_def);
+    }
+
+    fn visit_class_def(&mut self, class_def: &ClassDef) {
+        walk_class_def(self, class_def);
+    }
+
+    fn visit_constructor(&mut self, constructor: &Constructor) {
+        walk_constructor(self, constructor);
+    }
+
+    fn visit_super_call(&mut self, super_call: &SuperCall) {
+        walk_super_call(self, super_call);
+    }
+
+    fn visit_meth_def(&mut self, meth_def: &MethDef) {
+        walk_meth_def(self, meth_def);
+    }
+
+    fn visit_param_decl(&mut self, param_decl: &ParamDecl) {
+        if let Some(default) = &param_decl.default {
+            self.visit_expr(default);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr_stmt(&mut self, expr_stmt: &ExprStmt) {
+        self.visit_expr(&expr_stmt.expr);
+    }
+
+    fn visit_var_decl(&mut self, _var_decl: &VarDeclStmt) {}
+
+    fn visit_assign(&mut self, assign: &AssignStmt) {
+        self.visit_expr(&assign.expr);
+    }
+
+    fn visit_var_decl_with_assign(&mut self, var_decl: &VarDeclWithAssign) {
+        self.visit_expr(&var_decl.expr);
+    }
+
+    fn visit_multi_var_decl(&mut self, multi: &MultiVarDeclStmt) {
+        for decl in &multi.decls {
+            self.visit_stmt(decl);
+        }
+    }
+
+    fn visit_while(&mut self, while_stmt: &WhileStmt) {
+        walk_while(self, while_stmt);
+    }
+
+    fn visit_do_while(&mut self, do_while: &DoWhileStmt) {
+        walk_do_while(self, do_while);
+    }
+
+    fn visit_for_range(&mut self, for_range: &ForRangeStmt) {
+        walk_for_range(self, for_range);
+    }
+
+    fn visit_if(&mut self, if_stmt: &IfStmt) {
+        walk_if(self, if_stmt);
+    }
+
+    fn visit_match(&mut self, match_stmt: &MatchStmt) {
+        walk_match(self, match_stmt);
+    }
+
+    fn visit_break(&mut self, _break_stmt: &BreakStmt) {}
+
+    fn visit_continue(&mut self, _continue_stmt: &ContinueStmt) {}
+
+    fn visit_return(&mut self, return_stmt: &ReturnStmt) {
+        if let Some(value) = &return_stmt.value {
+            self.visit_expr(value);
+        }
+    }
+
+    fn visit_block(&mut self, block: &BlockStmt) {
+        walk_stmts(self, &block.statements);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_integer_literal(&mut self, _integer_literal: &IntegerLiteral) {}
+
+    fn visit_string_literal(&mut self, _string_literal: &StringLiteral) {}
+
+    fn visit_char_literal(&mut self, _char_literal: &CharLiteral) {}
+
+    fn visit_boolean_literal(&mut self, _boolean_literal: &BooleanLiteral) {}
+
+    fn visit_nil(&mut self, _nil: &NilExpr) {}
+
+    fn visit_variable(&mut self, _variable: &Variable) {}
+
+    fn visit_binary_expr(&mut self, binary_expr: &BinaryExpr) {
+        self.visit_expr(&binary_expr.left);
+        self.visit_expr(&binary_expr.right);
+    }
+
+    fn visit_unary_expr(&mut self, unary_expr: &UnaryExpr) {
+        self.visit_expr(&unary_expr.expr);
+    }
+
+    fn visit_fun_call(&mut self, fun_call: &FunCall) {
+        walk_exprs(self, &fun_call.args);
+    }
+
+    fn visit_meth_call(&mut self, meth_call: &MethCall) {
+        self.visit_expr(&meth_call.object);
+        walk_exprs(self, &meth_call.args);
+    }
+
+    fn visit_field(&mut self, field: &Field) {
+        self.visit_expr(&field.object);
+    }
+
+    fn visit_new(&mut self, new: &NewExpr) {
+        walk_exprs(self, &new.args);
+    }
+
+    fn visit_this(&mut self, _this: &ThisExpr) {}
+
+    fn visit_super(&mut self, _super_expr: &SuperExpr) {}
+
+    fn visit_println(&mut self, println: &PrintlnExpr) {
+        walk_exprs(self, &println.args);
+    }
+
+    fn visit_print(&mut self, print: &PrintExpr) {
+        walk_exprs(self, &print.args);
+    }
+
+    fn visit_len(&mut self, len: &LenExpr) {
+        self.visit_expr(&len.arg);
+    }
+
+    fn visit_type_name_of(&mut self, type_name_of: &TypeNameOfExpr) {
+        self.visit_expr(&type_name_of.arg);
+    }
+
+    fn visit_lambda(&mut self, lambda: &LambdaExpr) {
+        walk_lambda(self, lambda);
+    }
+
+    fn visit_assert(&mut self, assert: &AssertExpr) {
+        self.visit_expr(&assert.cond);
+        if let Some(message) = &assert.message {
+            self.visit_expr(message);
+        }
+    }
+
+    fn visit_grouped(&mut self, inner: &Expr, _span: &Span) {
+        self.visit_expr(inner);
+    }
+}
+
+pub fn walk_entry<V: Visitor + ?Sized>(visitor: &mut V, entry: &Entry) {
+    walk_stmts(visitor, &entry.statements);
+    for fun_def in &entry.fun_defs {
+        visitor.visit_fun_def(fun_def);
+    }
+    for class_def in &entry.class_defs {
+        visitor.visit_class_def(class_def);
+    }
+}
+
+pub fn walk_fun_def<V: Visitor + ?Sized>(visitor: &mut V, fun_def: &FunDef) {
+    for param in &fun_def.params {
+        visitor.visit_param_decl(param);
+    }
+    walk_stmts(visitor, &fun_def.statements);
+}
+
+pub fn walk_class_def<V: Visitor + ?Sized>(visitor: &mut V, class_def: &ClassDef) {
+    for var in &class_def.vars {
+        visitor.visit_var_decl(var);
+    }
+    visitor.visit_constructor(&class_def.constructor);
+    for method in &class_def.methods {
+        visitor.visit_meth_def(method);
+    }
+}
+
+pub fn walk_constructor<V: Visitor + ?Sized>(visitor: &mut V, constructor: &Constructor) {
+    for param in &constructor.params {
+        visitor.visit_param_decl(param);
+    }
+    if let Some(super_call) = &constructor.super_call {
+        visitor.visit_super_call(super_call);
+    }
+    walk_stmts(visitor, &constructor.statements);
+}
+
+pub fn walk_super_call<V: Visitor + ?Sized>(visitor: &mut V, super_call: &SuperCall) {
+    walk_exprs(visitor, &super_call.args);
+}
+
+pub fn walk_meth_def<V: Visitor + ?Sized>(visitor: &mut V, meth_def: &MethDef) {
+    for param in &meth_def.params {
+        visitor.visit_param_decl(param);
+    }
+    walk_stmts(visitor, &meth_def.statements);
+}
+
+pub fn walk_lambda<V: Visitor + ?Sized>(visitor: &mut V, lambda: &LambdaExpr) {
+    for param in &lambda.params {
+        visitor.visit_param_decl(param);
+    }
+    walk_stmts(visitor, &lambda.body);
+}
+
+pub fn walk_stmts<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Stmt]) {
+    for stmt in statements {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Expr(s) => visitor.visit_expr_stmt(s),
+        Stmt::VarDecl(s) => visitor.visit_var_decl(s),
+        Stmt::Assign(s) => visitor.visit_assign(s),
+        Stmt::VarDeclWithAssign(s) => visitor.visit_var_decl_with_assign(s),
+        Stmt::MultiVarDecl(s) => visitor.visit_multi_var_decl(s),
+        Stmt::While(s) => visitor.visit_while(s),
+        Stmt::DoWhile(s) => visitor.visit_do_while(s),
+        Stmt::ForRange(s) => visitor.visit_for_range(s),
+        Stmt::If(s) => visitor.visit_if(s),
+        Stmt::Match(s) => visitor.visit_match(s),
+        Stmt::Break(s) => visitor.visit_break(s),
+        Stmt::Continue(s) => visitor.visit_continue(s),
+        Stmt::Return(s) => visitor.visit_return(s),
+        Stmt::Block(s) => visitor.visit_block(s),
+        Stmt::Empty => {}
+    }
+}
+
+pub fn walk_while<V: Visitor + ?Sized>(visitor: &mut V, while_stmt: &WhileStmt) {
+    visitor.visit_expr(&while_stmt.condition);
+    visitor.visit_stmt(&while_stmt.body);
+}
+
+pub fn walk_do_while<V: Visitor + ?Sized>(visitor: &mut V, do_while: &DoWhileStmt) {
+    visitor.visit_stmt(&do_while.body);
+    visitor.visit_expr(&do_while.condition);
+}
+
+pub fn walk_for_range<V: Visitor + ?Sized>(visitor: &mut V, for_range: &ForRangeStmt) {
+    visitor.visit_expr(&for_range.start);
+    visitor.visit_expr(&for_range.end);
+    visitor.visit_stmt(&for_range.body);
+}
+
+pub fn walk_if<V: Visitor + ?Sized>(visitor: &mut V, if_stmt: &IfStmt) {
+    visitor.visit_expr(&if_stmt.condition);
+    visitor.visit_stmt(&if_stmt.then_branch);
+    if let Some(else_branch) = &if_stmt.else_branch {
+        visitor.visit_stmt(else_branch);
+    }
+}
+
+pub fn walk_match<V: Visitor + ?Sized>(visitor: &mut V, match_stmt: &MatchStmt) {
+    visitor.visit_expr(&match_stmt.scrutinee);
+    for arm in &match_stmt.arms {
+        visitor.visit_expr(&arm.pattern);
+        visitor.visit_stmt(&arm.body);
+    }
+    if let Some(default) = &match_stmt.default {
+        visitor.visit_stmt(default);
+    }
+}
+
+pub fn walk_exprs<V: Visitor + ?Sized>(visitor: &mut V, exprs: &[Expr]) {
+    for expr in exprs {
+        visitor.visit_expr(expr);
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::IntegerLiteral(e) => visitor.visit_integer_literal(e),
+        Expr::StringLiteral(e) => visitor.visit_string_literal(e),
+        Expr::CharLiteral(e) => visitor.visit_char_literal(e),
+        Expr::BooleanLiteral(e) => visitor.visit_boolean_literal(e),
+        Expr::Nil(e) => visitor.visit_nil(e),
+        Expr::Variable(e) => visitor.visit_variable(e),
+        Expr::Binary(e) => visitor.visit_binary_expr(e),
+        Expr::Unary(e) => visitor.visit_unary_expr(e),
+        Expr::FunCall(e) => visitor.visit_fun_call(e),
+        Expr::MethCall(e) => visitor.visit_meth_call(e),
+        Expr::Field(e) => visitor.visit_field(e),
+        Expr::New(e) => visitor.visit_new(e),
+        Expr::This(e) => visitor.visit_this(e),
+        Expr::Super(e) => visitor.visit_super(e),
+        Expr::Println(e) => visitor.visit_println(e),
+        Expr::Print(e) => visitor.visit_print(e),
+        Expr::Assert(e) => visitor.visit_assert(e),
+        Expr::Len(e) => visitor.visit_len(e),
+        Expr::TypeNameOf(e) => visitor.visit_type_name_of(e),
+        Expr::Lambda(e) => visitor.visit_lambda(e),
+        Expr::Grouped(inner, span) => visitor.visit_grouped(inner, span),
+        Expr::Empty => {}
+    }
+}
+
+/// Tallies the expression nodes in a tree, e.g. `println(1 + x)` counts four:
+/// the call, the addition and both operands.
+#[derive(Debug, Default)]
+pub struct NodeCounter {
+    pub exprs: usize,
+}
+
+impl NodeCounter {
+    pub fn count(entry: &Entry) -> usize {
+        let mut counter = Self::default();
+        counter.visit_entry(entry);
+        counter.exprs
+    }
+}
+
+impl Visitor for NodeCounter {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if !matches!(expr, Expr::Empty) {
+            self.exprs += 1;
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn count(input: &str) -> usize {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        NodeCounter::count(&ast)
+    }
+
+    #[test]
+    fn test_node_counter() {
+        assert_eq!(count("println(1 + x);"), 4);
+        assert_eq!(
+            count(
+                "fun add(a: Int, b: Int) -> Int { return a + b; }
+                 class Box { let v: Int; init(v: Int) { this.v = v; } meth get() -> Int { return this.v; } }
+                 let b: Box = new Box(add(1, -2));"
+            ),
+            // a + b: 3; v: 1; this.v: 2; new Box(add(1, -2)): 4, since -2 is one literal
+            10
+        );
+    }
 }
@@ -1,70 +1,144 @@
-use crate::ast::{ClassDef, Constructor, MethDef, VarDeclStmt};
+use std::fmt::{self, Write};
+
+use std::collections::HashSet;
+
+use crate::ast::{ClassDef, Constructor, MethDef};
+use crate::lexer::TypeName;
 
 use super::*;
 
 pub trait ClassGenerator {
-    fn generate_methods(&self, methods: Vec<MethDef>) -> String;
-    fn generate_classes(&self, classes: Vec<ClassDef>) -> String;
-    fn convert_class(&self, class: ClassDef) -> String;
-    fn convert_fields(&self, vars: Vec<VarDeclStmt>) -> String;
-    fn convert_constructor(&self, constructor: Constructor) -> String;
-    fn convert_method(&self, method: MethDef) -> String;
+    fn generate_methods(&self, out: &mut String, methods: &[MethDef]) -> fmt::Result;
+    fn generate_classes(&self, out: &mut String, classes: &[ClassDef]) -> fmt::Result;
+    fn convert_class(&self, out: &mut String, class: &ClassDef) -> fmt::Result;
+    fn convert_fields(&self, out: &mut String, class: &ClassDef) -> fmt::Result;
+    fn convert_constructor(&self, out: &mut String, constructor: &Constructor) -> fmt::Result;
+    fn convert_method(&self, out: &mut String, method: &MethDef) -> fmt::Result;
 }
 
 impl ClassGenerator for CodeGenerator {
-    fn generate_classes(&self, classes: Vec<ClassDef>) -> String {
-        let class_collection: Vec<_> = classes.iter().map(|c| self.convert_class(c.clone())).collect();
-        class_collection.join("\n")
-    }
-    
-    fn convert_class(&self, class: ClassDef) -> String {
-        let name = class.name;
-        let extends = match class.extends {
-            Some(string) => [" extends".to_string(),string].join(" "),
-            None => "".to_string(),
-        };
-        let fields = self.convert_fields(class.vars);
-        let constructor = self.convert_constructor(class.constructor);
-        let methods = self.generate_methods(class.methods);
-        
-
-        ["class ".to_string(), name, extends, "{\n".to_string(), fields, constructor, methods, "\n}".to_string()].join("")
-    }
-
-    fn convert_fields(&self, vars: Vec<VarDeclStmt>) -> String {
-        let field_collection: Vec<_> = vars.iter().map(|n| n.clone().name).collect();
-        field_collection.join(";\n") + if !field_collection.is_empty() {";\n"} else {""}
-    }
-
-    fn convert_constructor(&self, constructor: Constructor) -> String{
-        let params = self.convert_params(constructor.params);
-        let super_call = match &constructor.super_call {
-            Some(_) => ["super(".to_string(), self.generate_expressions(constructor.super_call.unwrap(), ","),");".to_string()].join(""),
-            None => "".to_string()
-        };
-        let statements = match &constructor.statements {
-            Some(_) => self.convert_statement(constructor.statements.unwrap()),
-            None => "".to_string()
-        };
-        
-        ["constructor(".to_string(), params, ") {".to_string(), super_call, statements,"}\n".to_string()].join("")
-    }
-    
-    fn generate_methods(&self, methods: Vec<MethDef>) -> String {
-        let method_collection: Vec<String> = methods.iter().map(|m| self.convert_method(m.clone())).collect();
-        method_collection.join("").trim().to_string()
-    }
-    
-    fn convert_method(&self, method: MethDef) -> String {
-        let name = method.name;
-        let params = self.convert_params(method.params);
-        let statements = match method.statements {
-            Some(_) =>self.convert_statement(method.statements.unwrap()),
-            None => "".to_string()
-        };
-        [name, "(".to_string(), params, ")".to_string(),statements,"\n".to_string()].join("")
-    }
-    
+    fn generate_classes(&self, out: &mut String, classes: &[ClassDef]) -> fmt::Result {
+        for (i, class) in classes.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            self.write_doc(out, &class.doc, 0)?;
+            self.write_export(out);
+            self.mark(out, class.span);
+            self.convert_class(out, class)?;
+        }
+        Ok(())
+    }
+
+    fn convert_class(&self, out: &mut String, class: &ClassDef) -> fmt::Result {
+        write!(out, "class {}", class.name)?;
+        if let Some(parent) = &class.extends {
+            write!(out, " extends {}", parent)?;
+        }
+        out.push_str(" {\n");
+        self.convert_fields(out, class)?;
+        // JS gives a subclass without a constructor one that passes its
+        // arguments to `super`; an empty one would throw on `new` instead
+        let constructor = &class.constructor;
+        let implicit = class.extends.is_some() && constructor.super_call.is_none() && constructor.statements.is_empty();
+        if !implicit {
+            self.convert_constructor(out, constructor)?;
+        }
+        self.generate_methods(out, &class.methods)?;
+        out.push('}');
+        Ok(())
+    }
+
+    fn convert_fields(&self, out: &mut String, class: &ClassDef) -> fmt::Result {
+        // Emitted as public class fields; the constructor assigns them via `this.<name> = ...`.
+        // A field is only declared once per hierarchy: redeclaring a parent's
+        // field would reset it to `undefined` after `super(...)` had set it.
+        let mut declared = self.inherited_fields(class);
+        for var in &class.vars {
+            if !declared.insert(var.name.as_str()) {
+                continue;
+            }
+            self.write_indent(out, 1)?;
+            out.push_str(&var.name);
+            self.write_type_annotation(out, &var.var_type)?;
+            out.push_str(";\n");
+        }
+        Ok(())
+    }
+
+    fn convert_constructor(&self, out: &mut String, constructor: &Constructor) -> fmt::Result {
+        self.write_jsdoc(out, &constructor.params, &TypeName::Void, 1)?;
+        self.write_indent(out, 1)?;
+        out.push_str("constructor(");
+        self.convert_params(out, &constructor.params)?;
+        out.push(')');
+
+        if constructor.super_call.is_none() && constructor.statements.is_empty() {
+            out.push_str(" {}\n");
+            return Ok(());
+        }
+
+        out.push_str(" {\n");
+        let (before_super, after_super) = constructor.split_at_super();
+        if !before_super.is_empty() {
+            self.generate_statements(out, before_super, 2)?;
+            out.push('\n');
+        }
+        if let Some(super_call) = &constructor.super_call {
+            self.write_indent(out, 2)?;
+            out.push_str("super(");
+            self.generate_expressions(out, &super_call.args, ",")?;
+            out.push_str(");\n");
+        }
+        if !after_super.is_empty() {
+            self.generate_statements(out, after_super, 2)?;
+            out.push('\n');
+        }
+        self.write_indent(out, 1)?;
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    fn generate_methods(&self, out: &mut String, methods: &[MethDef]) -> fmt::Result {
+        for method in methods {
+            self.convert_method(out, method)?;
+        }
+        Ok(())
+    }
+
+    fn convert_method(&self, out: &mut String, method: &MethDef) -> fmt::Result {
+        self.write_doc(out, &method.doc, 1)?;
+        self.write_jsdoc(out, &method.params, &method.return_type, 1)?;
+        self.write_indent(out, 1)?;
+        write!(out, "{}(", method.name)?;
+        self.convert_params(out, &method.params)?;
+        out.push(')');
+        self.write_type_annotation(out, &method.return_type)?;
+        self.convert_body(out, &method.statements, 1)?;
+        out.push('\n');
+        Ok(())
+    }
+}
+
+impl CodeGenerator {
+    /// Names of the fields declared by `class`'s ancestors in this program.
+    fn inherited_fields(&self, class: &ClassDef) -> HashSet<&str> {
+        let mut fields = HashSet::new();
+        let mut visited = HashSet::from([class.name.as_str()]);
+        let mut parent = class.extends.as_deref();
+        while let Some(name) = parent {
+            // stop at classes defined elsewhere and at inheritance cycles
+            let Some(ancestor) = self.classes.iter().find(|c| c.name == name) else {
+                break;
+            };
+            if !visited.insert(name) {
+                break;
+            }
+            fields.extend(ancestor.vars.iter().map(|var| var.name.as_str()));
+            parent = ancestor.extends.as_deref();
+        }
+        fields
+    }
 }
 
 #[cfg(test)]
@@ -78,7 +152,8 @@ mod tests {
         let ast = parser.parse().unwrap();
         println!("{:?}",ast);
         let generator = CodeGenerator::new(ast);
-        let classes = generator.generate_classes(generator.classes.clone());
+        let mut classes = String::new();
+        generator.generate_classes(&mut classes, &generator.classes).unwrap();
         println!("{}",classes);
         classes
     }
@@ -86,19 +161,19 @@ mod tests {
     #[test]
     fn test_generate_minimal_class() {
         let classes = gen_class("class Animal { init() {} }");
-        assert_eq!(classes, "class Animal{\nconstructor() {}\n\n}")
+        assert_eq!(classes, "class Animal {\n  constructor() {}\n}")
     }
 
     #[test]
     fn test_generate_class_with_field() {
         let class = gen_class("class Animal { let voice: Str; init(voice: Str) {{this.voice = voice;}} }");
-        assert_eq!(class, "class Animal{\nvoice;\nconstructor(voice) {{ { this.voice = voice } }}\n\n}")
+        assert_eq!(class, "class Animal {\n  voice;\n  constructor(voice) {\n    {\n      this.voice = voice;\n    }\n  }\n}")
     }
 
     #[test]
     fn test_generate_class_with_fields() {
         let class = gen_class("class Animal { let voice: Str; let limbnum: Int; init(voice: Str, limbnum: Int) {{this.voice = voice; this.limbnum = limbnum}} }");
-        assert_eq!(class, "class Animal{\nvoice;\nlimbnum;\nconstructor(voice,limbnum) {{ { this.voice = voice; \nthis.limbnum = limbnum } }}\n\n}")
+        assert_eq!(class, "class Animal {\n  voice;\n  limbnum;\n  constructor(voice,limbnum) {\n    {\n      this.voice = voice;\n      this.limbnum = limbnum;\n    }\n  }\n}")
     }
 
     #[test]
@@ -106,12 +181,71 @@ mod tests {
         let class = gen_class("class Animal { init() {} 
         meth speak() -> Void { println(\"animal noise\"); }
         meth age() -> Int {return 0;}}");
-        assert_eq!(class, "class Animal{\nconstructor() {}\nspeak(){ console.log(\"animal noise\") }\nage(){ return 0 }\n}".trim())
+        assert_eq!(class, "class Animal {\n  constructor() {}\n  speak(){\n    console.log(\"animal noise\");\n  }\n  age(){\n    return 0;\n  }\n}".trim())
+    }
+
+    #[test]
+    fn test_generate_method_jsdoc() {
+        let tokens = Lexer::new("class Counter { init(start: Int) {} meth add(n: Int) -> Boolean { return true; } }")
+            .tokenize()
+            .unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let options = CodeGenOptions { jsdoc: true, ..CodeGenOptions::default() };
+        let generator = CodeGenerator::with_options(ast, options);
+        let mut classes = String::new();
+        generator.generate_classes(&mut classes, &generator.classes).unwrap();
+        assert!(classes.contains("  /**\n   * @param {number} start\n   */\n  constructor(start) {}"));
+        assert!(classes.contains("  /**\n   * @param {number} n\n   * @returns {boolean}\n   */\n  add(n){"));
+    }
+
+    #[test]
+    fn test_generate_constructor_with_statements() {
+        let class = gen_class("class Animal { let voice: Str; let limbnum: Int; init(voice: Str, limbnum: Int) {this.voice = voice; this.limbnum = limbnum;} }");
+        assert_eq!(class, "class Animal {\n  voice;\n  limbnum;\n  constructor(voice,limbnum) {\n    this.voice = voice;\n    this.limbnum = limbnum;\n  }\n}")
+    }
+
+    #[test]
+    fn test_generate_fields_once_per_hierarchy() {
+        let classes = gen_class(
+            "class Animal { let name: Str; init(name: Str) { this.name = name; } }
+             class Cat extends Animal { let name: Str; let lives: Int; let lives: Int; init() { super(\"cat\"); this.lives = 9; } }",
+        );
+        assert!(classes.starts_with("class Animal {\n  name;\n  constructor(name) {"));
+        assert!(classes.contains("class Cat extends Animal {\n  lives;\n  constructor() {\n    super(\"cat\");\n    this.lives = 9;\n  }\n}"));
     }
 
     #[test]
     fn test_generate_inherited_class() {
         let class = gen_class("class Cat extends Animal { init() {super(\"meow\");} }");
-        assert_eq!(class, "class Cat extends Animal{\nconstructor() {super(\"meow\")}\n\n}")
+        assert_eq!(class, "class Cat extends Animal {\n  constructor() {\n    super(\"meow\");\n  }\n}")
+    }
+
+    #[test]
+    fn test_empty_subclass_constructor_is_left_out() {
+        let class = gen_class("class Cat extends Animal { let lives: Int; init() {} }");
+        assert_eq!(class, "class Cat extends Animal {\n  lives;\n}")
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generate_super_after_statements() {
+        let class = gen_class("class Cat extends Animal { init() { let legs: Int = 4; super(legs); println(legs); } }");
+        assert_eq!(class, "class Cat extends Animal {\n  constructor() {\n    let legs = 4;\n    super(legs);\n    console.log(legs);\n  }\n}")
+    }
+
+    #[test]
+    fn test_generate_super_method_call() {
+        let class = gen_class("class Cat extends Animal { init() {super();} meth speak() -> Int { return super.speak(); } }");
+        assert!(class.contains("speak(){\n    return super.speak();\n  }"))
+    }
+
+    #[test]
+    fn test_generate_typescript_class() {
+        let tokens = Lexer::new("class Animal { let voice: Str; init(voice: Str) {this.voice = voice;} meth speak() -> Str { return this.voice; } }").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let options = CodeGenOptions { target: Target::TypeScript, ..CodeGenOptions::default() };
+        let generator = CodeGenerator::with_options(ast, options);
+        let mut class = String::new();
+        generator.generate_classes(&mut class, &generator.classes).unwrap();
+        assert_eq!(class, "class Animal {\n  voice: string;\n  constructor(voice: string) {\n    this.voice = voice;\n  }\n  speak(): string{\n    return this.voice;\n  }\n}")
+    }
+}
@@ -1,28 +1,259 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
 use super::*;
 use crate::ast::*;
+use crate::lexer::{Span, TypeName};
+
+/// Output language produced by the code generator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    #[default]
+    JavaScript,
+    /// JavaScript plus the type annotations from the source.
+    TypeScript,
+}
+
+/// How the generated program is meant to be loaded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    /// A plain script; top-level declarations stay global.
+    #[default]
+    Script,
+    /// An ES module that exports its top-level functions and classes.
+    EsModule,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeGenOptions {
+    /// Number of spaces emitted per nesting level.
+    pub indent_width: usize,
+    pub target: Target,
+    pub module: ModuleKind,
+    /// Start the output with a `"use strict";` prologue.
+    pub strict: bool,
+    /// Start the output with a `#!/usr/bin/env node` line so it can be run
+    /// directly as a script.
+    pub shebang: bool,
+    /// End the output with a `main();` call when the program defines `main`.
+    /// Analyze with [`AnalyzerOptions::auto_run_main`](crate::sema::AnalyzerOptions::auto_run_main)
+    /// set as well, so a `main` that needs arguments is rejected.
+    pub auto_run_main: bool,
+    /// Source file named in the `sources` of a generated source map.
+    pub source_name: Option<String>,
+    /// Put a JSDoc block with `@param` and `@returns` types above each
+    /// function, method and constructor, for editors reading the plain JS.
+    pub jsdoc: bool,
+    /// Write integer literals of five or more digits with `_` between
+    /// each group of three, as in `1_000_000`.
+    pub numeric_separators: bool,
+}
+
+impl Default for CodeGenOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 2,
+            target: Target::default(),
+            module: ModuleKind::default(),
+            strict: false,
+            shebang: false,
+            auto_run_main: false,
+            source_name: None,
+            jsdoc: false,
+            numeric_separators: false,
+        }
+    }
+}
+
+/// Maps a LavaScript type to its TypeScript spelling.
+/// Function types name their parameters `arg0`, `arg1`, ... since TypeScript
+/// requires a name for each one.
+pub fn ts_type(type_name: &TypeName) -> String {
+    match type_name {
+        TypeName::Int => "number".to_string(),
+        TypeName::Str => "string".to_string(),
+        TypeName::Boolean => "boolean".to_string(),
+        TypeName::Void => "void".to_string(),
+        TypeName::Class(name) => name.clone(),
+        TypeName::Function { params, ret } => {
+            let params: Vec<String> = params
+                .iter()
+                .enumerate()
+                .map(|(i, param)| format!("arg{}: {}", i, ts_type(param)))
+                .collect();
+            format!("({}) => {}", params.join(", "), ts_type(ret))
+        }
+    }
+}
 
 pub struct CodeGenerator {
     pub statements: Vec<Stmt>,
     pub classes: Vec<ClassDef>,
-    pub functions: Vec<FunDef>
+    pub functions: Vec<FunDef>,
+    pub options: CodeGenOptions,
+    /// Output offsets paired with the source span written there, collected
+    /// only while generating a source map.
+    marks: RefCell<Option<Vec<(usize, Span)>>>,
+    /// Types semantic analysis found for `typeName(...)` calls, by span.
+    type_names: HashMap<Span, TypeName>,
 }
 
 impl CodeGenerator {
     pub fn new(ast: Entry) -> Self {
+        Self::with_options(ast, CodeGenOptions::default())
+    }
+
+    pub fn with_options(ast: Entry, options: CodeGenOptions) -> Self {
         Self {
             statements: ast.statements,
             classes: ast.class_defs,
-            functions: ast.fun_defs
+            functions: ast.fun_defs,
+            options,
+            marks: RefCell::new(None),
+            type_names: HashMap::new(),
         }
     }
 
+    /// Supplies the types [`Analyzer::get_type_names`] inferred, which
+    /// `typeName(...)` calls compile to. Without them every such call
+    /// becomes `"unknown"`.
+    ///
+    /// [`Analyzer::get_type_names`]: crate::sema::Analyzer::get_type_names
+    pub fn with_type_names(mut self, type_names: HashMap<Span, TypeName>) -> Self {
+        self.type_names = type_names;
+        self
+    }
+
+    /// The static type name `typeName(...)` at `span` resolved to.
+    pub fn type_name_at(&self, span: Span) -> String {
+        self.type_names.get(&span).map_or_else(|| "unknown".to_string(), TypeName::to_string)
+    }
+
     pub fn generate(&self) -> String {
-        let statements = self.generate_statements(self.statements.clone());
-        let classes = self.generate_classes(self.classes.clone());
-        let functions = self.generate_functions(self.functions.clone());
-        
-        let program: String = [classes, functions, statements].join("\n");
-        program
+        let mut out = String::new();
+        self.write_program(&mut out)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Like [`generate`](Self::generate), but also returns a version 3 source
+    /// map (as JSON) tying each emitted statement, function and class back to
+    /// its line and column in the source.
+    pub fn generate_with_sourcemap(&self) -> (String, String) {
+        self.marks.replace(Some(Vec::new()));
+        let code = self.generate();
+        let marks = self.marks.take().unwrap_or_default();
+        let source_name = self.options.source_name.as_deref().unwrap_or_default();
+        let map = build_source_map(&code, &marks, source_name);
+        (code, map)
+    }
+
+    /// Records that the code about to be written to `out` came from `span`,
+    /// when a source map is being generated.
+    pub fn mark(&self, out: &str, span: Span) {
+        if let Some(marks) = self.marks.borrow_mut().as_mut() {
+            marks.push((out.len(), span));
+        }
+    }
+
+    fn write_program(&self, out: &mut String) -> fmt::Result {
+        if self.options.shebang {
+            out.push_str("#!/usr/bin/env node\n");
+        }
+        if self.options.strict {
+            out.push_str("\"use strict\";\n");
+        }
+        self.generate_classes(out, &self.classes)?;
+        out.push('\n');
+        self.generate_functions(out, &self.functions)?;
+        out.push('\n');
+        self.generate_statements(out, &self.statements, 0)?;
+        if self.options.auto_run_main && self.functions.iter().any(|function| function.name == "main") {
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str("main();");
+        }
+        Ok(())
+    }
+
+    /// Writes `export ` before a top-level declaration when emitting a module.
+    pub fn write_export(&self, out: &mut String) {
+        if self.options.module == ModuleKind::EsModule {
+            out.push_str("export ");
+        }
+    }
+
+    /// Writes a declaration's doc comment on the lines above it, re-indented
+    /// to `level`. Continuation lines of a `/* */` comment keep their `*`
+    /// lined up under the opening one.
+    pub fn write_doc(&self, out: &mut String, doc: &Option<String>, level: usize) -> fmt::Result {
+        let Some(doc) = doc else {
+            return Ok(());
+        };
+        for line in doc.lines() {
+            let line = line.trim();
+            self.write_indent(out, level)?;
+            if line.starts_with('*') {
+                out.push(' ');
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        Ok(())
+    }
+
+    /// Writes a JSDoc block typing `params` and `return_type` when the
+    /// `jsdoc` option is on. Defaulted parameters are marked optional, and a
+    /// `Void` return gets no `@returns` tag.
+    pub fn write_jsdoc(
+        &self,
+        out: &mut String,
+        params: &[ParamDecl],
+        return_type: &TypeName,
+        level: usize,
+    ) -> fmt::Result {
+        if !self.options.jsdoc || (params.is_empty() && *return_type == TypeName::Void) {
+            return Ok(());
+        }
+        self.write_indent(out, level)?;
+        out.push_str("/**\n");
+        for param in params {
+            self.write_indent(out, level)?;
+            match param.default {
+                Some(_) => writeln!(out, " * @param {{{}}} [{}]", ts_type(&param.param_type), param.name)?,
+                None => writeln!(out, " * @param {{{}}} {}", ts_type(&param.param_type), param.name)?,
+            }
+        }
+        if *return_type != TypeName::Void {
+            self.write_indent(out, level)?;
+            writeln!(out, " * @returns {{{}}}", ts_type(return_type))?;
+        }
+        self.write_indent(out, level)?;
+        out.push_str(" */\n");
+        Ok(())
+    }
+
+    /// The nesting level of the line currently being written, judged by its
+    /// indentation. Expressions don't carry a level of their own, so a lambda
+    /// body uses this to line up with the statement around it.
+    pub fn current_level(&self, out: &str) -> usize {
+        let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        indent / self.options.indent_width.max(1)
+    }
+
+    pub fn write_indent(&self, out: &mut String, level: usize) -> fmt::Result {
+        write!(out, "{:width$}", "", width = level * self.options.indent_width)
+    }
+
+    /// Writes `: <type>` when targeting TypeScript, and nothing for plain JavaScript.
+    pub fn write_type_annotation(&self, out: &mut String, type_name: &TypeName) -> fmt::Result {
+        match self.options.target {
+            Target::JavaScript => Ok(()),
+            Target::TypeScript => write!(out, ": {}", ts_type(type_name)),
+        }
     }
 }
 
@@ -78,10 +309,10 @@ mod tests {
         r"class Rectangle {
             let width: Int;
             let height: Int;
-            init(width: Int, height: Int) {{
+            init(width: Int, height: Int) {
                 this.width = width;
                 this.height = height;
-            }}
+            }
             meth area() -> Int {
                 return this.width * this.height;
             }
@@ -103,6 +334,37 @@ mod tests {
         println!("{}", program)
     }
 
+    #[test]
+    fn test_rectangle_fields() {
+        let program = gen_program(RECTANGLE_CODE);
+        assert!(program.starts_with(
+            "class Rectangle {\n  width;\n  height;\n  constructor(width,height) {\n    this.width = width;\n    this.height = height;\n  }\n"
+        ));
+    }
+
+    #[test]
+    fn test_rectangle_class() {
+        let program = gen_program(RECTANGLE_CODE);
+        let class = &program[..program.find("\n\n").unwrap()];
+        assert_eq!(
+            class,
+            "class Rectangle {
+  width;
+  height;
+  constructor(width,height) {
+    this.width = width;
+    this.height = height;
+  }
+  area(){
+    return this.width * this.height;
+  }
+  perimeter(){
+    return 2 * this.width + 2 * this.height;
+  }
+}"
+        );
+    }
+
     #[test]
     fn test_empty_program() {
         let program = gen_program("");
@@ -157,7 +419,7 @@ mod tests {
 
     #[test]
     fn test_class_without_methods() {
-        let code = r"class Empty {}";
+        let code = r"class Empty { init() {} }";
         let program = gen_program(code);
         assert!(program.contains("class Empty"));
     }
@@ -168,11 +430,101 @@ mod tests {
             statements: vec![],
             classes: vec![],
             functions: vec![],
+            options: CodeGenOptions::default(),
+            marks: RefCell::new(None),
+            type_names: HashMap::new(),
         };
         let output = gen.generate();
         assert_eq!(output.trim(), "");
     }
 
+    #[test]
+    fn test_custom_indent_width() {
+        let mut lexer = Lexer::new("fun f(x: Int) -> Int { if (x > 0) { return x; } return 0; }");
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let generator = CodeGenerator::with_options(ast, CodeGenOptions { indent_width: 4, ..CodeGenOptions::default() });
+        assert_eq!(
+            generator.generate().trim(),
+            "function f(x){\n    if (x > 0) {\n        return x;\n    }\n    return 0;\n}"
+        );
+    }
+
+    #[test]
+    fn test_es_module_exports() {
+        let tokens = Lexer::new("class A { init() {} } fun f() -> Void { println(1); } f();").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let options = CodeGenOptions { module: ModuleKind::EsModule, ..CodeGenOptions::default() };
+        let program = CodeGenerator::with_options(ast.clone(), options).generate();
+        assert!(program.contains("export class A {"));
+        assert!(program.contains("export function f(){"));
+        assert!(program.trim_end().ends_with("\nf();"));
+
+        let program = CodeGenerator::new(ast).generate();
+        assert!(!program.contains("export"));
+    }
+
+    #[test]
+    fn test_strict_prologue() {
+        let ast = Parser::new(Lexer::new("fun f() -> Void {} f();").tokenize().unwrap()).parse().unwrap();
+        let options = CodeGenOptions { strict: true, ..CodeGenOptions::default() };
+        let program = CodeGenerator::with_options(ast.clone(), options).generate();
+        assert_eq!(program.lines().next(), Some("\"use strict\";"));
+
+        let program = CodeGenerator::new(ast).generate();
+        assert!(!program.contains("use strict"));
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let source = "
+            /** A 2D point. */
+            class Point {
+                init() {}
+                // distance from the origin
+                meth norm() -> Int { return 0; }
+            }
+
+            // adds two numbers
+            // and nothing else
+            fun add(a: Int, b: Int) -> Int { return a + b; } // trailing
+            fun sub(a: Int, b: Int) -> Int { return a - b; }
+        ";
+        let ast = Parser::new(Lexer::with_comments(source).tokenize().unwrap()).parse().unwrap();
+        let program = CodeGenerator::new(ast).generate();
+        assert!(program.starts_with("/** A 2D point. */\nclass Point {"));
+        assert!(program.contains("  // distance from the origin\n  norm(){"));
+        assert!(program.contains("// adds two numbers\n// and nothing else\nfunction add(a,b){"));
+        assert!(!program.contains("trailing"));
+
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        assert!(!CodeGenerator::new(ast).generate().contains("//"));
+    }
+
+    #[test]
+    fn test_auto_run_main() {
+        let options = CodeGenOptions { auto_run_main: true, ..CodeGenOptions::default() };
+
+        let ast = Parser::new(Lexer::new("fun main() -> Void { println(1); } let x: Int = 1;").tokenize().unwrap()).parse().unwrap();
+        let program = CodeGenerator::with_options(ast.clone(), options.clone()).generate();
+        assert!(program.ends_with("let x = 1;\nmain();"));
+        assert!(!CodeGenerator::new(ast).generate().contains("main();"));
+
+        let ast = Parser::new(Lexer::new("fun helper() -> Void {}").tokenize().unwrap()).parse().unwrap();
+        let program = CodeGenerator::with_options(ast, options).generate();
+        assert!(!program.contains("main();"));
+    }
+
+    #[test]
+    fn test_shebang_option() {
+        let options = CodeGenOptions { shebang: true, strict: true, ..CodeGenOptions::default() };
+        let ast = Parser::new(Lexer::new("#!/usr/bin/env lavascript\nprintln(1);").tokenize().unwrap()).parse().unwrap();
+        let program = CodeGenerator::with_options(ast.clone(), options).generate();
+        assert!(program.starts_with("#!/usr/bin/env node\n\"use strict\";\n"));
+        assert!(!CodeGenerator::new(ast).generate().contains("#!"));
+    }
+
     #[test]
     fn test_void_like_function() {
         let code = r"
@@ -202,7 +554,9 @@ mod tests {
             println(test(15));
         ";
         let program = gen_program(code);
-        println!("{}", program);
+        assert!(program.contains(
+            "function test(n){\n  if (n > 10) {\n    if (n > 20) {\n      return 2;\n    } else {\n      return 1;\n    }\n  } else {\n    return 0;\n  }\n}"
+        ), "{}", program);
     }
 
     #[test]
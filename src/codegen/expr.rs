@@ -1,78 +1,429 @@
-use crate::ast::Expr;
+use std::fmt::{self, Write};
+
+use crate::ast::{is_right_associative, precedence, AssertExpr, BinaryOp, Expr, UnaryOp};
 use super::*;
 
+/// Re-escapes characters the lexer un-escaped so the value can sit inside a double-quoted JS string.
+pub fn escape_js_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            // `\0` would read as an octal escape if a digit followed it
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Spells `value` with a `_` between every three digits, counting from the
+/// right. Numbers under five digits are left alone; `1_000` reads no better.
+fn group_digits(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    if digits.len() < 5 {
+        return value.to_string();
+    }
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+    if value < 0 {
+        grouped.push('-');
+    }
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('_');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Looks through the parentheses the source wrote; codegen decides on its own
+/// which ones the output needs.
+fn ungrouped(mut expr: &Expr) -> &Expr {
+    while let Expr::Grouped(inner, _) = expr {
+        expr = inner;
+    }
+    expr
+}
+
+/// A negative literal prints with a leading `-`, so it needs the same care as
+/// a unary minus.
+fn is_negative_literal(expr: &Expr) -> bool {
+    matches!(ungrouped(expr), Expr::IntegerLiteral(literal) if literal.value < 0)
+}
+
+/// Whether `operand` must be parenthesized to stay an operand of `operator`.
+fn needs_parens(operator: &BinaryOp, operand: &Expr, is_right: bool) -> bool {
+    match ungrouped(operand) {
+        Expr::Binary(inner) => {
+            let (outer, inner) = (precedence(operator), precedence(&inner.operator));
+            let against_associativity = if is_right_associative(operator) { !is_right } else { is_right };
+            inner < outer || (inner == outer && against_associativity)
+        }
+        // JS rejects a unary operator directly on the left of `**`
+        Expr::Unary(_) => *operator == BinaryOp::Power && !is_right,
+        operand if is_negative_literal(operand) => *operator == BinaryOp::Power && !is_right,
+        _ => false,
+    }
+}
+
 pub trait ExpressionGenerator {
-    fn generate_expressions(&self, expressions: Vec<Expr>, seperator: &str) -> String;
-    fn convert_expression(&self, expression: Expr) -> String;
+    fn generate_expressions(&self, out: &mut String, expressions: &[Expr], seperator: &str) -> fmt::Result;
+    fn convert_expression(&self, out: &mut String, expression: &Expr) -> fmt::Result;
+    fn convert_operand(&self, out: &mut String, operand: &Expr, parenthesize: bool) -> fmt::Result;
+    fn convert_assert_throw(&self, out: &mut String, assert: &AssertExpr) -> fmt::Result;
 }
 
 impl ExpressionGenerator for CodeGenerator {
-    fn generate_expressions(&self, expressions: Vec<Expr>, seperator: &str) -> String {
-        let expression_collection: Vec<_> = expressions.iter().map(|e| self.convert_expression(e.clone())).collect();
-        expression_collection.join(seperator)
+    fn generate_expressions(&self, out: &mut String, expressions: &[Expr], seperator: &str) -> fmt::Result {
+        for (i, expression) in expressions.iter().enumerate() {
+            if i > 0 {
+                out.push_str(seperator);
+            }
+            self.convert_expression(out, expression)?;
+        }
+        Ok(())
     }
 
-    fn convert_expression(&self, expression: Expr) -> String {
+    fn convert_expression(&self, out: &mut String, expression: &Expr) -> fmt::Result {
         match expression {
-            Expr::IntegerLiteral(integer_literal) => integer_literal.value.to_string(),
-            Expr::StringLiteral(string_literal) => ["\"".to_string(), string_literal.value, "\"".to_string()].join(""),
-            Expr::BooleanLiteral(boolean_literal) => boolean_literal.value.to_string(),
-            Expr::Variable(variable) => variable.name,
+            Expr::IntegerLiteral(integer_literal) if self.options.numeric_separators => {
+                out.push_str(&group_digits(integer_literal.value))
+            }
+            Expr::IntegerLiteral(integer_literal) => write!(out, "{}", integer_literal.value)?,
+            Expr::StringLiteral(string_literal) => write!(out, "\"{}\"", escape_js_string(&string_literal.value))?,
+            Expr::CharLiteral(char_literal) => write!(out, "\"{}\"", escape_js_string(&char_literal.value.to_string()))?,
+            Expr::BooleanLiteral(boolean_literal) => write!(out, "{}", boolean_literal.value)?,
+            Expr::Nil(_) => out.push_str("null"),
+            Expr::Variable(variable) => out.push_str(&variable.name),
             Expr::Binary(binary_expr) => {
-                let left = self.convert_expression(*binary_expr.left);
                 let operation = match binary_expr.operator {
-                    crate::ast::BinaryOp::Add => "+".to_string(),
-                    crate::ast::BinaryOp::Subtract => "-".to_string(),
-                    crate::ast::BinaryOp::Multiply => "*".to_string(),
-                    crate::ast::BinaryOp::Divide => "/".to_string(),
-                    crate::ast::BinaryOp::Equal => "==".to_string(),
-                    crate::ast::BinaryOp::NotEqual => "!=".to_string(),
-                    crate::ast::BinaryOp::Greater => ">".to_string(),
-                    crate::ast::BinaryOp::Less => "<".to_string(),
-                    crate::ast::BinaryOp::GreaterEqual => ">=".to_string(),
-                    crate::ast::BinaryOp::LessEqual => "<=".to_string(),
-                    crate::ast::BinaryOp::Or => "||".to_string(),
-                    crate::ast::BinaryOp::And => "&&".to_string(),
+                    BinaryOp::Add => "+",
+                    BinaryOp::Subtract => "-",
+                    BinaryOp::Multiply => "*",
+                    BinaryOp::Divide => "/",
+                    BinaryOp::Power => "**",
+                    BinaryOp::Equal => "==",
+                    BinaryOp::NotEqual => "!=",
+                    BinaryOp::Greater => ">",
+                    BinaryOp::Less => "<",
+                    BinaryOp::GreaterEqual => ">=",
+                    BinaryOp::LessEqual => "<=",
+                    BinaryOp::Or => "||",
+                    BinaryOp::And => "&&",
+                    BinaryOp::BitAnd => "&",
+                    BinaryOp::BitOr => "|",
+                    BinaryOp::BitXor => "^",
+                    BinaryOp::ShiftLeft => "<<",
+                    BinaryOp::ShiftRight => ">>",
                 };
-                let right = self.convert_expression(*binary_expr.right);
-                [left, operation,right].join(" ")
-
+                let operator = &binary_expr.operator;
+                self.convert_operand(out, &binary_expr.left, needs_parens(operator, &binary_expr.left, false))?;
+                write!(out, " {} ", operation)?;
+                self.convert_operand(out, &binary_expr.right, needs_parens(operator, &binary_expr.right, true))?;
             }
             Expr::Unary(unary_expr) => {
                 let operation = match unary_expr.operator {
-                    crate::ast::UnaryOp::Not => "!".to_string(),
-                    crate::ast::UnaryOp::Negate => "-".to_string(),
-                    crate::ast::UnaryOp::Plus => "+".to_string(),
+                    UnaryOp::Not => "!",
+                    UnaryOp::Negate => "-",
+                    UnaryOp::Plus => "+",
+                    UnaryOp::BitNot => "~",
+                };
+                out.push_str(operation);
+                // a nested `-`/`+` is wrapped too, so `- -x` doesn't become `--x`
+                let parenthesize = match ungrouped(&unary_expr.expr) {
+                    Expr::Binary(_) => true,
+                    Expr::Unary(inner) => inner.operator == unary_expr.operator,
+                    operand => is_negative_literal(operand) && unary_expr.operator == UnaryOp::Negate,
                 };
-                let value = self.convert_expression(*unary_expr.expr);
-                [operation, value].join("")
-            },
+                self.convert_operand(out, &unary_expr.expr, parenthesize)?;
+            }
             Expr::FunCall(fun_call) => {
-                let function = fun_call.callee;
-                let args = self.generate_expressions(fun_call.args, ",");
-                [function, "(".to_string(), args, ")".to_string()].join("") 
+                write!(out, "{}(", fun_call.callee)?;
+                self.generate_expressions(out, &fun_call.args, ",")?;
+                out.push(')');
             }
             Expr::MethCall(meth_call) => {
-                let object = self.convert_expression(*meth_call.object);
-                let method = meth_call.meth;
-                let args = self.generate_expressions(meth_call.args, ",");
-                [object,".".to_string(),method,"(".to_string(),args,")".to_string()].join("")
+                let parenthesize = matches!(ungrouped(&meth_call.object), Expr::Binary(_) | Expr::Unary(_))
+                    || is_negative_literal(&meth_call.object);
+                self.convert_operand(out, &meth_call.object, parenthesize)?;
+                write!(out, ".{}(", meth_call.meth)?;
+                self.generate_expressions(out, &meth_call.args, ",")?;
+                out.push(')');
             }
             Expr::Field(field_call) => {
-                let object = self.convert_expression(*field_call.object);
-                let field = field_call.field;
-                [object,".".to_string(),field.to_string()].join("")
+                let parenthesize = matches!(ungrouped(&field_call.object), Expr::Binary(_) | Expr::Unary(_))
+                    || is_negative_literal(&field_call.object);
+                self.convert_operand(out, &field_call.object, parenthesize)?;
+                write!(out, ".{}", field_call.field)?;
             }
             Expr::New(new_expr) => {
-                let name = new_expr.class_name;
-                let args = self.generate_expressions(new_expr.args, ",");
-                ["new".to_string(), [name,"(".to_string(),args,")".to_string()].join("")].join(" ")
-            },
-            Expr::This(_) => "this".to_string(),
-            Expr::Println(println_expr) => ["console.log(".to_string(),self.convert_expression(*println_expr.arg),")".to_string()].join(""),
-            Expr::Print(print_expr) => ["console.log(".to_string(),self.convert_expression(*print_expr.arg),")".to_string()].join(""),
-            Expr::Grouped(expr, _span) => self.convert_expression(*expr),
-            Expr::Empty => "".to_string(),
+                write!(out, "new {}(", new_expr.class_name)?;
+                self.generate_expressions(out, &new_expr.args, ",")?;
+                out.push(')');
+            }
+            Expr::This(_) => out.push_str("this"),
+            Expr::Super(_) => out.push_str("super"),
+            Expr::Println(println_expr) => {
+                out.push_str("console.log(");
+                self.generate_expressions(out, &println_expr.args, ", ")?;
+                out.push(')');
+            }
+            Expr::Print(print_expr) => {
+                // join like console.log does, just without the newline
+                out.push_str("process.stdout.write(");
+                match print_expr.args.as_slice() {
+                    [] => out.push_str("\"\""),
+                    [arg] => {
+                        out.push_str("String(");
+                        self.convert_expression(out, arg)?;
+                        out.push(')');
+                    }
+                    args => {
+                        out.push('[');
+                        self.generate_expressions(out, args, ", ")?;
+                        out.push_str("].join(\" \")");
+                    }
+                }
+                out.push(')');
+            }
+            Expr::Len(len) => {
+                self.convert_operand(out, &len.arg, true)?;
+                out.push_str(".length");
+            }
+            Expr::TypeNameOf(type_name_of) => {
+                write!(out, "\"{}\"", escape_js_string(&self.type_name_at(type_name_of.span)))?
+            }
+            Expr::Lambda(lambda) => {
+                out.push('(');
+                self.convert_params(out, &lambda.params)?;
+                out.push(')');
+                self.write_type_annotation(out, &lambda.return_type)?;
+                out.push_str(" => ");
+                self.convert_body(out, &lambda.body, self.current_level(out))?;
+            }
+            Expr::Assert(assert) => {
+                // statements get a plain `if`; anywhere else `throw` needs a function around it
+                out.push_str("((");
+                self.convert_expression(out, ungrouped(&assert.cond))?;
+                out.push_str(") || (() => { ");
+                self.convert_assert_throw(out, assert)?;
+                out.push_str("; })())");
+            }
+            Expr::Grouped(expr, _span) => self.convert_expression(out, expr)?,
+            Expr::Empty => {}
+        }
+        Ok(())
+    }
+
+    fn convert_operand(&self, out: &mut String, operand: &Expr, parenthesize: bool) -> fmt::Result {
+        if !parenthesize {
+            return self.convert_expression(out, operand);
+        }
+        out.push('(');
+        self.convert_expression(out, ungrouped(operand))?;
+        out.push(')');
+        Ok(())
+    }
+
+    /// Writes the `throw` for a failed assertion, without the trailing `;`.
+    fn convert_assert_throw(&self, out: &mut String, assert: &AssertExpr) -> fmt::Result {
+        out.push_str("throw new Error(");
+        match &assert.message {
+            Some(message) => self.convert_expression(out, message)?,
+            None => out.push_str("\"Assertion failed\""),
         }
+        out.push(')');
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_js_string, group_digits};
+    use crate::{codegen::*, lexer::*, parser::*};
+
+    fn gen_program(input: &str) -> String {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        CodeGenerator::new(ast).generate()
+    }
+
+    #[test]
+    fn test_escape_js_string() {
+        assert_eq!(escape_js_string("plain"), "plain");
+        assert_eq!(escape_js_string("a\nb\tc\rd"), "a\\nb\\tc\\rd");
+        assert_eq!(escape_js_string("say \"hi\" \\ bye"), "say \\\"hi\\\" \\\\ bye");
+        assert_eq!(escape_js_string("\x001\u{7}"), "\\u00001\\u0007");
+    }
+
+    #[test]
+    fn test_nil_becomes_null() {
+        let program = gen_program("class Node { init() {} } let next: Node = nil; println(nil);");
+        assert!(program.contains("let next = null;"));
+        assert!(program.contains("console.log(null);"));
+    }
+
+    #[test]
+    fn test_char_literal_becomes_string() {
+        let program = gen_program(r#"let c: Str = 'a'; let q: Str = '\''; println(c + "b");"#);
+        assert!(program.contains(r#"let c = "a";"#));
+        assert!(program.contains(r#"let q = "'";"#));
+    }
+
+    #[test]
+    fn test_string_literal_round_trips_escapes() {
+        let program = gen_program(r#"println("line1\nline2");"#);
+        assert!(program.contains(r#"console.log("line1\nline2");"#));
+    }
+
+    #[test]
+    fn test_print_and_println_differ() {
+        assert_eq!(gen_program("println(1);").trim(), "console.log(1);");
+        assert_eq!(gen_program("print(1);").trim(), "process.stdout.write(String(1));");
+    }
+
+    #[test]
+    fn test_print_argument_counts() {
+        assert_eq!(gen_program("println();").trim(), "console.log();");
+        assert_eq!(gen_program("println(1, \"a\");").trim(), "console.log(1, \"a\");");
+        assert_eq!(gen_program("print();").trim(), "process.stdout.write(\"\");");
+        assert_eq!(
+            gen_program("print(1, \"a\");").trim(),
+            "process.stdout.write([1, \"a\"].join(\" \"));"
+        );
+    }
+
+    #[test]
+    fn test_assert_becomes_runtime_check() {
+        assert_eq!(
+            gen_program("assert(x > 0);").trim(),
+            "if (!(x > 0)) throw new Error(\"Assertion failed\");"
+        );
+        assert_eq!(
+            gen_program("assert(ok, \"not ok\");").trim(),
+            "if (!(ok)) throw new Error(\"not ok\");"
+        );
+        assert!(gen_program("let b: Boolean = assert(ok);")
+            .contains("let b = ((ok) || (() => { throw new Error(\"Assertion failed\"); })());"));
+    }
+
+    #[test]
+    fn test_len_becomes_length() {
+        assert_eq!(gen_program("len(s);").trim(), "(s).length;");
+        assert_eq!(gen_program("let n: Int = len(a + b) * 2;").trim(), "let n = (a + b).length * 2;");
+        assert_eq!(gen_program("len(\"abc\");").trim(), "(\"abc\").length;");
+    }
+
+    #[test]
+    fn test_numeric_separators() {
+        let gen_with = |input: &str, numeric_separators: bool| {
+            let tokens = Lexer::new(input).tokenize().unwrap();
+            let ast = Parser::new(tokens).parse().unwrap();
+            let options = CodeGenOptions { numeric_separators, ..CodeGenOptions::default() };
+            CodeGenerator::with_options(ast, options).generate().trim().to_string()
+        };
+        assert_eq!(gen_with("let n: Int = 1000000;", true), "let n = 1_000_000;");
+        assert_eq!(gen_with("let n: Int = 1000000;", false), "let n = 1000000;");
+        assert_eq!(gen_with("let n: Int = 12345 + 1000 - 123456;", true), "let n = 12_345 + 1000 - 123_456;");
+
+        assert_eq!(group_digits(-9223372036854775808), "-9_223_372_036_854_775_808");
+        assert_eq!(group_digits(99999), "99_999");
+        assert_eq!(group_digits(-1234), "-1234");
+    }
+
+    #[test]
+    fn test_lambda_becomes_arrow_function() {
+        assert_eq!(
+            gen_program("let inc: (Int) -> Int = fun (x: Int) -> Int { return x + 1; };").trim(),
+            "let inc = (x) => {\n  return x + 1;\n};"
+        );
+        assert_eq!(
+            gen_program("apply(fun (a: Int, b: Int) -> Int { return a * b; }, 2);").trim(),
+            "apply((a,b) => {\n  return a * b;\n},2);"
+        );
+        assert_eq!(gen_program("run(fun () -> Void {});").trim(), "run(() => {});");
+    }
+
+    #[test]
+    fn test_lambda_body_follows_enclosing_indentation() {
+        let source = "fun outer() -> Void {
+            let f: (Int) -> Int = fun (x: Int) -> Int {
+                if (x > 0) { return 1; }
+                let y: Int = x;
+                while (y < 0) { y = y + 1; }
+                return y;
+            };
+        }";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let generator = CodeGenerator::new(Parser::new(tokens).parse().unwrap());
+        let mut out = String::new();
+        generator.generate_functions(&mut out, &generator.functions).unwrap();
+        assert_eq!(
+            out,
+            "function outer(){\n  let f = (x) => {\n    if (x > 0) {\n      return 1;\n    }\n    let y = x;\n    while (y < 0) {\n      y = y + 1;\n    }\n    return y;\n  };\n}"
+        );
+    }
+
+    #[test]
+    fn test_type_name_without_analysis() {
+        assert_eq!(gen_program("typeName(5);").trim(), "\"unknown\";");
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        assert_eq!(
+            gen_program("a & b; a | b; a ^ b; ~a; a << 1; a >> 1;").trim(),
+            "a & b;\na | b;\na ^ b;\n~a;\na << 1;\na >> 1;"
+        );
+    }
+
+    #[test]
+    fn test_boolean_logic_keeps_short_circuit_grouping() {
+        let cases = [
+            ("a || b && c;", "a || b && c;"),
+            ("(a || b) && c;", "(a || b) && c;"),
+            ("a && (b || c);", "a && (b || c);"),
+            ("(a && b) || c;", "a && b || c;"),
+            ("true || false && x;", "true || false && x;"),
+            ("(true || false) && x;", "(true || false) && x;"),
+            ("!(a && b) || !c;", "!(a && b) || !c;"),
+            ("a == 1 || b == 2 && c;", "a == 1 || b == 2 && c;"),
+            ("(a || b) == c;", "(a || b) == c;"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(gen_program(source).trim(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        let cases = [
+            ("(2 + 3) * 4;", "(2 + 3) * 4;"),
+            ("((2 + 3)) * 4;", "(2 + 3) * 4;"),
+            ("4 * (2 + 3);", "4 * (2 + 3);"),
+            ("2 + 3 * 4;", "2 + 3 * 4;"),
+            ("((2 + 3));", "2 + 3;"),
+            ("a - (b - c);", "a - (b - c);"),
+            ("(a - b) - c;", "a - b - c;"),
+            ("2 ** 3 ** 2;", "2 ** 3 ** 2;"),
+            ("(2 ** 3) ** 2;", "(2 ** 3) ** 2;"),
+            ("(-2) ** 2;", "(-2) ** 2;"),
+            ("(-a) ** 2;", "(-a) ** 2;"),
+            ("-(a + b);", "-(a + b);"),
+            ("-(-a);", "-(-a);"),
+            ("-(-5);", "-(-5);"),
+            ("a - -5;", "a - -5;"),
+            ("(a + b).m();", "(a + b).m();"),
+            ("(a || b) && c;", "(a || b) && c;"),
+            ("a == (b < c);", "a == b < c;"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(gen_program(source).trim(), expected, "{}", source);
+        }
+    }
+}
@@ -1,33 +1,50 @@
+use std::fmt::{self, Write};
+
 use crate::ast::{FunDef, ParamDecl};
 use super::*;
 
 pub trait FunctionGenerator {
-    fn generate_functions(&self, functions: Vec<FunDef>) -> String;
-    fn convert_function(&self, function: FunDef) -> String;
-    fn convert_params(&self, params: Vec<ParamDecl>) -> String;
-
+    fn generate_functions(&self, out: &mut String, functions: &[FunDef]) -> fmt::Result;
+    fn convert_function(&self, out: &mut String, function: &FunDef) -> fmt::Result;
+    fn convert_params(&self, out: &mut String, params: &[ParamDecl]) -> fmt::Result;
 }
 
 impl FunctionGenerator for CodeGenerator {
-    fn generate_functions(&self, functions: Vec<FunDef>) -> String {
-        let fun_collection: Vec<String> = functions.iter().map(|f| self.convert_function(f.clone())).collect();
-        fun_collection.join("\n").trim().to_string()
+    fn generate_functions(&self, out: &mut String, functions: &[FunDef]) -> fmt::Result {
+        for (i, function) in functions.iter().enumerate() {
+            if i > 0 {
+                out.push_str("\n\n");
+            }
+            self.write_doc(out, &function.doc, 0)?;
+            self.write_jsdoc(out, &function.params, &function.return_type, 0)?;
+            self.write_export(out);
+            self.mark(out, function.span);
+            self.convert_function(out, function)?;
+        }
+        Ok(())
     }
-    
-    fn convert_function(&self, function: FunDef) -> String {
-        let name = function.name;
-        let params = self.convert_params(function.params);
-        let statements = match function.statements {
-            Some(_) =>self.convert_statement(function.statements.unwrap()),
-            None => "".to_string()
-        };
-
-        ["function ".to_string(), name, "(".to_string(), params, ")".to_string(),statements,"\n".to_string()].join("")
+
+    fn convert_function(&self, out: &mut String, function: &FunDef) -> fmt::Result {
+        write!(out, "function {}(", function.name)?;
+        self.convert_params(out, &function.params)?;
+        out.push(')');
+        self.write_type_annotation(out, &function.return_type)?;
+        self.convert_body(out, &function.statements, 0)
     }
 
-    fn convert_params(&self, params: Vec<ParamDecl>) -> String {
-        let param_collection: Vec<_> = params.iter().map(|n| n.clone().name).collect();
-        param_collection.join(",")
+    fn convert_params(&self, out: &mut String, params: &[ParamDecl]) -> fmt::Result {
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&param.name);
+            self.write_type_annotation(out, &param.param_type)?;
+            if let Some(default) = &param.default {
+                out.push_str(" = ");
+                self.convert_expression(out, default)?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -36,12 +53,18 @@ mod tests {
     use crate::{lexer::*, parser::*, codegen::*};
 
     fn gen_fun(input: &str) -> String {
+        gen_fun_for(input, Target::JavaScript)
+    }
+
+    fn gen_fun_for(input: &str, target: Target) -> String {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let ast = parser.parse().unwrap();
-        let generator = CodeGenerator::new(ast);
-        let funs = generator.generate_functions(generator.functions.clone());
+        let options = CodeGenOptions { target, ..CodeGenOptions::default() };
+        let generator = CodeGenerator::with_options(ast, options);
+        let mut funs = String::new();
+        generator.generate_functions(&mut funs, &generator.functions).unwrap();
         println!("{}",funs);
         funs
     }
@@ -49,25 +72,92 @@ mod tests {
     #[test]
     fn test_generate_minimal_function() {
         let funs = gen_fun("fun functionName() -> Void {}");
-        assert_eq!(funs, "function functionName(){  }")
+        assert_eq!(funs, "function functionName(){}")
+    }
+
+    #[test]
+    fn test_generate_function_without_body() {
+        let generator = CodeGenerator::new(crate::ast::Entry::default());
+        let function = crate::ast::FunDef { name: "f".to_string(), ..Default::default() };
+        let mut out = String::new();
+        generator.convert_function(&mut out, &function).unwrap();
+        assert_eq!(out, "function f(){}");
+    }
+
+    #[test]
+    fn test_generate_function_with_missing_body() {
+        // the parser reports the missing `{` but still returns the function
+        let tokens = Lexer::new("fun f() -> Void;").tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        assert!(parser.has_errors());
+        let generator = CodeGenerator::new(ast);
+        let mut out = String::new();
+        generator.generate_functions(&mut out, &generator.functions).unwrap();
+        assert_eq!(out, "function f(){}");
     }
 
     #[test]
     fn test_generate_minimal_function_with_params() {
         let funs = gen_fun("fun functionName(intParam: Int, stringParam: Str, boolParam: Boolean) -> Void {}");
-        assert_eq!(funs, "function functionName(intParam,stringParam,boolParam){  }")
+        assert_eq!(funs, "function functionName(intParam,stringParam,boolParam){}")
     }
 
     #[test]
     fn test_generate_function() {
         let funs = gen_fun("fun square(x: Int) -> Int {let square: Int = x*x; return square;}");
-        assert_eq!(funs, "function square(x){ let square = x * x; \nreturn square }")
+        assert_eq!(funs, "function square(x){\n  let square = x * x;\n  return square;\n}")
     }
 
     #[test]
     fn test_generate_multiple_functions() {
         let funs = gen_fun("fun square(x: Int) -> Int {let square: Int = x*x; return square;}
                                     fun bark() -> Void {println(\"bark\");}");
-        assert_eq!(funs, "function square(x){ let square = x * x; \nreturn square }\n\nfunction bark(){ console.log(\"bark\") }")
+        assert_eq!(funs, "function square(x){\n  let square = x * x;\n  return square;\n}\n\nfunction bark(){\n  console.log(\"bark\");\n}")
+    }
+
+    #[test]
+    fn test_generate_default_params() {
+        let source = "fun greet(name: Str, times: Int = 2) -> Void {}";
+        assert_eq!(gen_fun(source), "function greet(name,times = 2){}");
+        assert_eq!(
+            gen_fun_for(source, Target::TypeScript),
+            "function greet(name: string,times: number = 2): void{}"
+        );
+    }
+
+    #[test]
+    fn test_generate_jsdoc() {
+        let tokens = Lexer::new(
+            "fun scale(x: Int, label: Str = \"x\") -> Int { return x * 2; } fun noop() -> Void {}",
+        )
+        .tokenize()
+        .unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let options = CodeGenOptions { jsdoc: true, ..CodeGenOptions::default() };
+        let generator = CodeGenerator::with_options(ast, options);
+        let mut funs = String::new();
+        generator.generate_functions(&mut funs, &generator.functions).unwrap();
+        assert_eq!(
+            funs,
+            "/**\n * @param {number} x\n * @param {string} [label]\n * @returns {number}\n */\n\
+             function scale(x,label = \"x\"){\n  return x * 2;\n}\n\nfunction noop(){}"
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generate_typescript_function() {
+        let source = "fun greet(name: Str, times: Int, loud: Boolean) -> Void {let count: Int = times;}";
+        let js = gen_fun_for(source, Target::JavaScript);
+        let ts = gen_fun_for(source, Target::TypeScript);
+        assert_eq!(js, "function greet(name,times,loud){\n  let count = times;\n}");
+        assert_eq!(ts, "function greet(name: string,times: number,loud: boolean): void{\n  let count: number = times;\n}");
+    }
+
+    #[test]
+    fn test_generate_typescript_class_types() {
+        let source = "fun make(w: Int) -> Rectangle {return new Rectangle(w, w);}";
+        let ts = gen_fun_for(source, Target::TypeScript);
+        assert_eq!(ts, "function make(w: number): Rectangle{\n  return new Rectangle(w,w);\n}");
+    }
+}
@@ -3,9 +3,12 @@ mod stmt;
 mod class;
 mod fun;
 mod expr;
+mod sourcemap;
 
-pub use core::CodeGenerator;
+pub use core::{ts_type, CodeGenOptions, CodeGenerator, ModuleKind, Target};
 use stmt::StatementGenerator;
 use class::ClassGenerator;
 use fun::FunctionGenerator;
-use expr::ExpressionGenerator;
\ No newline at end of file
+use expr::ExpressionGenerator;
+pub(crate) use expr::escape_js_string;
+use sourcemap::build_source_map;
\ No newline at end of file
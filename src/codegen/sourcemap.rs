@@ -0,0 +1,111 @@
+use crate::lexer::Span;
+
+use super::expr::escape_js_string;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Builds a version 3 source map for `code` from `marks`, pairs of a byte
+/// offset into the generated code and the source span emitted there. The
+/// marks must be in output order.
+pub fn build_source_map(code: &str, marks: &[(usize, Span)], source_name: &str) -> String {
+    format!(
+        "{{\"version\":3,\"sources\":[\"{}\"],\"names\":[],\"mappings\":\"{}\"}}",
+        escape_js_string(source_name),
+        encode_mappings(code, marks)
+    )
+}
+
+/// Encodes the `mappings` field: one `;`-separated group per generated line,
+/// each segment holding base64 VLQ deltas for the generated column, source
+/// index, source line and source column. Columns are zero-based and counted
+/// in UTF-16 units, as the format expects.
+fn encode_mappings(code: &str, marks: &[(usize, Span)]) -> String {
+    let mut mappings = String::new();
+    let mut line_start = 0;
+    let mut first_in_line = true;
+    let mut previous = None;
+    let (mut prev_gen_col, mut prev_src_line, mut prev_src_col) = (0i64, 0i64, 0i64);
+
+    for &(offset, span) in marks {
+        let skipped = &code[line_start..offset];
+        if let Some(last_newline) = skipped.rfind('\n') {
+            mappings.push_str(&";".repeat(skipped.matches('\n').count()));
+            line_start += last_newline + 1;
+            first_in_line = true;
+            prev_gen_col = 0;
+        }
+        // nested nodes can start at the same spot as their parent; keep the outer one
+        if previous == Some(offset) {
+            continue;
+        }
+        previous = Some(offset);
+
+        let gen_col = code[line_start..offset].encode_utf16().count() as i64;
+        let src_line = span.line.saturating_sub(1) as i64;
+        let src_col = span.column.saturating_sub(1) as i64;
+
+        if !first_in_line {
+            mappings.push(',');
+        }
+        first_in_line = false;
+        write_vlq(&mut mappings, gen_col - prev_gen_col);
+        write_vlq(&mut mappings, 0);
+        write_vlq(&mut mappings, src_line - prev_src_line);
+        write_vlq(&mut mappings, src_col - prev_src_col);
+        (prev_gen_col, prev_src_line, prev_src_col) = (gen_col, src_line, src_col);
+    }
+    mappings
+}
+
+fn write_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 { (-value << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = vlq & 0b1_1111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b10_0000;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codegen::CodeGenerator, lexer::Lexer, parser::Parser};
+
+    fn vlq(value: i64) -> String {
+        let mut out = String::new();
+        write_vlq(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn test_vlq_encoding() {
+        assert_eq!(vlq(0), "A");
+        assert_eq!(vlq(1), "C");
+        assert_eq!(vlq(-1), "D");
+        assert_eq!(vlq(15), "e");
+        assert_eq!(vlq(16), "gB");
+        assert_eq!(vlq(-123), "3H");
+    }
+
+    #[test]
+    fn test_source_map_for_multi_line_program() {
+        let source = "fun add(a: Int, b: Int) -> Int {\n  return a + b;\n}\nlet x: Int = add(1, 2);\nprintln(x);";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let (code, map) = CodeGenerator::new(ast.clone()).generate_with_sourcemap();
+
+        assert_eq!(code, CodeGenerator::new(ast).generate());
+        assert!(map.starts_with("{\"version\":3,\"sources\":[\"\"],\"names\":[],\"mappings\":\""));
+        let mappings = map.split("\"mappings\":\"").nth(1).unwrap().trim_end_matches("\"}");
+        assert!(!mappings.is_empty());
+        // the generated code starts with an empty line; `function add` maps
+        // to the name at 1:5, `return` to 2:3, and the last two statements
+        // to the starts of lines 4 and 5
+        assert_eq!(mappings, ";AAAI;EACF;;AAEF;AACA");
+    }
+}
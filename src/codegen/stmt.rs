@@ -1,51 +1,211 @@
-use crate::ast::Stmt;
+use std::fmt::{self, Write};
+
+use crate::ast::{Expr, MatchStmt, Stmt};
 use super::*;
 
 pub trait StatementGenerator {
-    fn generate_statements(&self, statements:Vec<Stmt>) -> String;
-    fn convert_statement(&self, statement: Stmt) -> String;
+    fn generate_statements(&self, out: &mut String, statements: &[Stmt], indent: usize) -> fmt::Result;
+    fn convert_statement(&self, out: &mut String, statement: &Stmt, indent: usize) -> fmt::Result;
+    fn convert_branch(&self, out: &mut String, body: &Stmt, indent: usize) -> fmt::Result;
+    fn convert_body(&self, out: &mut String, statements: &[Stmt], indent: usize) -> fmt::Result;
+    fn convert_match(&self, out: &mut String, match_stmt: &MatchStmt, indent: usize) -> fmt::Result;
+    fn convert_declarator(&self, out: &mut String, decl: &Stmt) -> fmt::Result;
 }
 
 impl StatementGenerator for CodeGenerator {
-    fn generate_statements(&self, statements: Vec<Stmt>) -> String {
-        let stmt_collection: Vec<_> = statements.iter().map(|s| self.convert_statement(s.clone())).collect();
-        stmt_collection.join("; \n").trim().to_string()
-    }
-    fn convert_statement(&self, statement: Stmt) -> String {
-         let stmt = match statement {
-            Stmt::Expr(expr_stmt) => self.convert_expression(*expr_stmt.expr),
-            Stmt::VarDecl(var_decl_stmt) => ["let".to_string(), var_decl_stmt.name].join(" "),
-            Stmt::Assign(assign_stmt) => [assign_stmt.name, "=".to_string(), self.convert_expression(*assign_stmt.expr)].join(" "),
-            Stmt::VarDeclWithAssign(var_decl_with_assign) => 
-                ["let".to_string(), var_decl_with_assign.name, "=".to_string(), self.convert_expression(*var_decl_with_assign.expr)].join(" "),
+    fn generate_statements(&self, out: &mut String, statements: &[Stmt], indent: usize) -> fmt::Result {
+        let mut first = true;
+        for statement in statements {
+            if let Stmt::Empty = statement {
+                continue;
+            }
+            if !first {
+                out.push('\n');
+            }
+            first = false;
+            self.write_indent(out, indent)?;
+            if let Some(span) = statement.span() {
+                self.mark(out, span);
+            }
+            self.convert_statement(out, statement, indent)?;
+        }
+        Ok(())
+    }
+
+    fn convert_statement(&self, out: &mut String, statement: &Stmt, indent: usize) -> fmt::Result {
+        match statement {
+            Stmt::Expr(expr_stmt) => {
+                if let Expr::Assert(assert) = &*expr_stmt.expr {
+                    out.push_str("if (!(");
+                    self.convert_expression(out, &assert.cond)?;
+                    out.push_str(")) ");
+                    self.convert_assert_throw(out, assert)?;
+                } else {
+                    self.convert_expression(out, &expr_stmt.expr)?;
+                }
+                out.push(';');
+            }
+            Stmt::VarDecl(var_decl_stmt) => {
+                write!(out, "{} ", var_decl_stmt.keyword())?;
+                self.convert_declarator(out, statement)?;
+                out.push(';');
+            }
+            Stmt::Assign(assign_stmt) => {
+                write!(out, "{} = ", assign_stmt.name)?;
+                self.convert_expression(out, &assign_stmt.expr)?;
+                out.push(';');
+            }
+            Stmt::VarDeclWithAssign(var_decl_with_assign) => {
+                write!(out, "{} ", var_decl_with_assign.keyword())?;
+                self.convert_declarator(out, statement)?;
+                out.push(';');
+            }
+            Stmt::MultiVarDecl(multi) => {
+                write!(out, "{} ", multi.keyword())?;
+                for (i, decl) in multi.decls.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.convert_declarator(out, decl)?;
+                }
+                out.push(';');
+            }
             Stmt::While(while_stmt) => {
-                let condition = self.convert_expression(*while_stmt.condition);
-                let body = self.convert_statement(*while_stmt.body);
-                ["while (".to_string(), condition, ") ".to_string(), body].join("")
+                out.push_str("while (");
+                self.convert_expression(out, &while_stmt.condition)?;
+                out.push_str(") ");
+                self.convert_branch(out, &while_stmt.body, indent)?;
+            }
+            Stmt::ForRange(for_range) => {
+                write!(out, "for (let {} = ", for_range.var)?;
+                self.convert_expression(out, &for_range.start)?;
+                write!(out, "; {} {} ", for_range.var, if for_range.inclusive { "<=" } else { "<" })?;
+                self.convert_expression(out, &for_range.end)?;
+                write!(out, "; {}++) ", for_range.var)?;
+                self.convert_branch(out, &for_range.body, indent)?;
+            }
+            Stmt::DoWhile(do_while) => {
+                out.push_str("do ");
+                self.convert_branch(out, &do_while.body, indent)?;
+                out.push_str(" while (");
+                self.convert_expression(out, &do_while.condition)?;
+                out.push_str(");");
             }
             Stmt::If(if_stmt) => {
-                let condition = self.convert_expression(*if_stmt.condition);
-                let then = self.convert_statement(*if_stmt.then_branch);
-                let els = match if_stmt.else_branch {
-                    Some(stmt) => ["else {".to_string(),self.convert_statement(*stmt), "}".to_string()].join(""),
-                    None => "".to_string(),
-                };
-                ["if (".to_string(), condition, ") {".to_string(), then, "} ".to_string(), els ].join("")
-            }
-            Stmt::Break(_break_stmt) => "break".to_string(),
-            Stmt::Return(return_stmt) => {
-                match return_stmt.value {
-                    Some(expr) => ["return".to_string(), self.convert_expression(*expr)].join(" "),
-                    None => "return".to_string(),
+                out.push_str("if (");
+                self.convert_expression(out, &if_stmt.condition)?;
+                out.push_str(") ");
+                self.convert_branch(out, &if_stmt.then_branch, indent)?;
+                match if_stmt.else_branch.as_deref() {
+                    // keep `else if` chains flat rather than nesting each `if` a level deeper
+                    Some(else_if @ Stmt::If(_)) => {
+                        out.push_str(" else ");
+                        self.convert_statement(out, else_if, indent)?;
+                    }
+                    Some(else_branch) => {
+                        out.push_str(" else ");
+                        self.convert_branch(out, else_branch, indent)?;
+                    }
+                    None => {}
+                }
+            }
+            Stmt::Match(match_stmt) => self.convert_match(out, match_stmt, indent)?,
+            Stmt::Break(_break_stmt) => out.push_str("break;"),
+            Stmt::Continue(_continue_stmt) => out.push_str("continue;"),
+            Stmt::Return(return_stmt) => match &return_stmt.value {
+                Some(expr) => {
+                    out.push_str("return ");
+                    self.convert_expression(out, expr)?;
+                    out.push(';');
                 }
+                None => out.push_str("return;"),
             },
-            Stmt::Block(block_stmt) => {
-                ["{".to_string(), self.generate_statements(block_stmt.statements), "}".to_string()].join(" ")
+            Stmt::Block(block_stmt) => self.convert_body(out, &block_stmt.statements, indent)?,
+            Stmt::Empty => {}
+        }
+        Ok(())
+    }
+
+    /// Writes an `if`/`else`/`while` body, bracing it when the source left a
+    /// single statement unbraced.
+    fn convert_branch(&self, out: &mut String, body: &Stmt, indent: usize) -> fmt::Result {
+        match body {
+            Stmt::Block(block_stmt) => self.convert_body(out, &block_stmt.statements, indent),
+            stmt => self.convert_body(out, std::slice::from_ref(stmt), indent),
+        }
+    }
+
+    /// Writes `{`, the statements one level deeper and `}` at `indent`, or
+    /// just `{}` when there are none. Used for blocks and callable bodies.
+    fn convert_body(&self, out: &mut String, statements: &[Stmt], indent: usize) -> fmt::Result {
+        if statements.is_empty() {
+            out.push_str("{}");
+            return Ok(());
+        }
+        out.push_str("{\n");
+        self.generate_statements(out, statements, indent + 1)?;
+        out.push('\n');
+        self.write_indent(out, indent)?;
+        out.push('}');
+        Ok(())
+    }
+
+    /// Writes the `name = expr` of a declaration, without its keyword, so
+    /// several can share one `let`.
+    fn convert_declarator(&self, out: &mut String, decl: &Stmt) -> fmt::Result {
+        match decl {
+            Stmt::VarDecl(var_decl) => {
+                out.push_str(&var_decl.name);
+                self.write_type_annotation(out, &var_decl.var_type)
             }
-            Stmt::Empty => "".to_string(),
-        };
-        //[stmt,";".to_string()].join("")
-        stmt
+            Stmt::VarDeclWithAssign(var_decl) => {
+                out.push_str(&var_decl.name);
+                if let Some(var_type) = &var_decl.var_type {
+                    self.write_type_annotation(out, var_type)?;
+                }
+                out.push_str(" = ");
+                self.convert_expression(out, &var_decl.expr)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes a `switch` with one braced `case` per arm, each ending in a
+    /// `break` (unless it already returns or continues) so arms never fall
+    /// through, and `default` for the `_` arm.
+    fn convert_match(&self, out: &mut String, match_stmt: &MatchStmt, indent: usize) -> fmt::Result {
+        out.push_str("switch (");
+        self.convert_expression(out, &match_stmt.scrutinee)?;
+        out.push_str(") {\n");
+        for arm in &match_stmt.arms {
+            self.write_indent(out, indent + 1)?;
+            out.push_str("case ");
+            self.convert_expression(out, &arm.pattern)?;
+            out.push_str(": {\n");
+            let statements = match &*arm.body {
+                Stmt::Block(block_stmt) => block_stmt.statements.as_slice(),
+                stmt => std::slice::from_ref(stmt),
+            };
+            self.generate_statements(out, statements, indent + 2)?;
+            if statements.iter().any(|stmt| !matches!(stmt, Stmt::Empty)) {
+                out.push('\n');
+            }
+            if !matches!(statements.last(), Some(Stmt::Return(_) | Stmt::Continue(_))) {
+                self.write_indent(out, indent + 2)?;
+                out.push_str("break;\n");
+            }
+            self.write_indent(out, indent + 1)?;
+            out.push_str("}\n");
+        }
+        if let Some(default) = &match_stmt.default {
+            self.write_indent(out, indent + 1)?;
+            out.push_str("default: ");
+            self.convert_branch(out, default, indent + 1)?;
+            out.push('\n');
+        }
+        self.write_indent(out, indent)?;
+        out.push('}');
+        Ok(())
     }
 }
 
@@ -60,7 +220,8 @@ mod tests {
         let ast = parser.parse().unwrap();
         println!("{:?}",ast);
         let generator = CodeGenerator::new(ast);
-        let stmt = generator.generate_statements(generator.statements.clone());
+        let mut stmt = String::new();
+        generator.generate_statements(&mut stmt, &generator.statements, 0).unwrap();
         println!("{}",stmt);
         stmt
     }
@@ -74,72 +235,177 @@ mod tests {
     #[test]
     fn test_generate_binop_statements() {
         let stmt = gen_stmt("1+2; 1-2; 1*2; 1/2; x==1; x != 1; 1>0; 0<1; x>=1; x<=1; true || false; true && true");
-        assert_eq!(stmt, "1 + 2; \n1 - 2; \n1 * 2; \n1 / 2; \nx == 1; \nx != 1; \n1 > 0; \n0 < 1; \nx >= 1; \nx <= 1; \ntrue || false; \ntrue && true")
+        assert_eq!(stmt, "1 + 2;\n1 - 2;\n1 * 2;\n1 / 2;\nx == 1;\nx != 1;\n1 > 0;\n0 < 1;\nx >= 1;\nx <= 1;\ntrue || false;\ntrue && true;")
+    }
+
+    #[test]
+    fn test_generate_power_statements() {
+        let stmt = gen_stmt("2 ** 3; 2 ** 3 ** 2; 2 * 3 ** 2");
+        assert_eq!(stmt, "2 ** 3;\n2 ** 3 ** 2;\n2 * 3 ** 2;");
     }
 
     #[test]
     fn test_generate_unop_statements() {
         let stmt = gen_stmt("!true; -x; +x");
-        assert_eq!(stmt, "!true; \n-x; \n+x");
+        assert_eq!(stmt, "!true;\n-x;\n+x;");
     }
 
     #[test]
     fn test_generate_var_decl() {
         let stmt = gen_stmt("let x: Int;");
-        assert_eq!(stmt, "let x".to_string())
+        assert_eq!(stmt, "let x;".to_string())
+    }
+
+    #[test]
+    fn generate_unbraced_bodies() {
+        let stmt = gen_stmt("if (x) return 1; else x = 2; while (x) x = x - 1;");
+        assert_eq!(
+            stmt,
+            "if (x) {\n  return 1;\n} else {\n  x = 2;\n}\nwhile (x) {\n  x = x - 1;\n}"
+        );
+    }
+
+    #[test]
+    fn generate_declaration_bodies() {
+        // JS rejects `while (c) let x = 1;`, so a lone declaration must be braced
+        let stmt = gen_stmt("while (c) let x: Int = 1; for (i in 0..3) const y: Int = i; if (c) let z: Int = 2;");
+        assert_eq!(
+            stmt,
+            "while (c) {\n  let x = 1;\n}\nfor (let i = 0; i < 3; i++) {\n  const y = i;\n}\nif (c) {\n  let z = 2;\n}"
+        );
+    }
+
+    #[test]
+    fn test_generate_const_decl() {
+        let stmt = gen_stmt("const pi: Int = 3; let r: Int = 2;");
+        assert_eq!(stmt, "const pi = 3;\nlet r = 2;")
+    }
+
+    #[test]
+    fn test_generate_multi_var_decl() {
+        assert_eq!(gen_stmt("let a: Int, b: Int = 0;"), "let a, b = 0;");
+        assert_eq!(gen_stmt("const x: Int = 1, y = x + 1;"), "const x = 1, y = x + 1;");
+
+        let tokens = Lexer::new("let a: Int, b: Str = \"s\";").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let options = CodeGenOptions { target: Target::TypeScript, ..CodeGenOptions::default() };
+        let program = CodeGenerator::with_options(ast, options).generate();
+        assert_eq!(program.trim(), "let a: number, b: string = \"s\";");
+    }
+
+    #[test]
+    fn test_generate_function_typed_var_decl() {
+        assert_eq!(gen_stmt("let f: (Int) -> Int;"), "let f;");
+
+        let tokens = Lexer::new("let f: (Int, Str) -> Boolean;").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let options = CodeGenOptions { target: Target::TypeScript, ..CodeGenOptions::default() };
+        let program = CodeGenerator::with_options(ast, options).generate();
+        assert_eq!(program.trim(), "let f: (arg0: number, arg1: string) => boolean;");
     }
 
     #[test]
     fn test_generate_var_assignment() {
         let stmt = gen_stmt("x = 5;");
-        assert_eq!(stmt, "x = 5")
+        assert_eq!(stmt, "x = 5;")
     }
 
     #[test]
     fn generate_var_decl_w_assignment() {
         let stmt = gen_stmt("let x: Int = 5;");
-        assert_eq!(stmt, "let x = 5")
+        assert_eq!(stmt, "let x = 5;")
+    }
+
+    #[test]
+    fn generate_inferred_var_decl() {
+        let stmt = gen_stmt("let x = 5;");
+        assert_eq!(stmt, "let x = 5;")
     }
 
     #[test]
     fn generate_while_loop() {
         let stmt = gen_stmt("while (i < 5) {i = i + 1; println(i);}");
-        assert_eq!(stmt, "while (i < 5) { i = i + 1; \nconsole.log(i) }")
+        assert_eq!(stmt, "while (i < 5) {\n  i = i + 1;\n  console.log(i);\n}")
+    }
+
+    #[test]
+    fn generate_do_while_loop() {
+        let stmt = gen_stmt("do {i = i + 1;} while (i < 5); do {} while (false);");
+        assert_eq!(stmt, "do {\n  i = i + 1;\n} while (i < 5);\ndo {} while (false);")
+    }
+
+    #[test]
+    fn generate_for_range_loop() {
+        let stmt = gen_stmt("for (i in 0..n) {println(i);} for (j in 1..=3) println(j);");
+        assert_eq!(
+            stmt,
+            "for (let i = 0; i < n; i++) {\n  console.log(i);\n}\nfor (let j = 1; j <= 3; j++) {\n  console.log(j);\n}"
+        )
     }
 
     #[test]
     fn generate_if_stmt() {
         let stmt = gen_stmt("if (true) {print(0)}}");
-        assert_eq!(stmt, "if (true) {console.log(0)}")
+        assert_eq!(stmt, "if (true) {\n  process.stdout.write(String(0));\n}")
     }
 
     #[test]
     fn generate_ifelse_stmt() {
         let stmt = gen_stmt("if (false) {print(0)} else {print(1)}");
-        assert_eq!(stmt, "if (false) {console.log(0)} else {console.log(1)}")
+        assert_eq!(stmt, "if (false) {\n  process.stdout.write(String(0));\n} else {\n  process.stdout.write(String(1));\n}")
+    }
+
+    #[test]
+    fn generate_else_if_chain() {
+        let stmt = gen_stmt("if (a) {print(0)} else if (b) print(1); else {print(2)}");
+        assert_eq!(
+            stmt,
+            "if (a) {\n  process.stdout.write(String(0));\n} else if (b) {\n  process.stdout.write(String(1));\n} else {\n  process.stdout.write(String(2));\n}"
+        )
+    }
+
+    #[test]
+    fn generate_break_and_continue() {
+        let stmt = gen_stmt("while (i < 5) { if (i == 2) { continue; } break; }");
+        assert_eq!(stmt, "while (i < 5) {\n  if (i == 2) {\n    continue;\n  }\n  break;\n}")
+    }
+
+    #[test]
+    fn generate_nested_indentation() {
+        let stmt = gen_stmt("while (i < 5) { if (i == 2) { return i; } i = i + 1; }");
+        assert_eq!(stmt, "while (i < 5) {\n  if (i == 2) {\n    return i;\n  }\n  i = i + 1;\n}")
+    }
+
+    #[test]
+    fn generate_match_as_switch() {
+        let stmt = gen_stmt("match (x) { 1 => { println(\"one\"); } 2 => return 2; _ => { println(\"other\"); } }");
+        assert_eq!(
+            stmt,
+            "switch (x) {\n  case 1: {\n    console.log(\"one\");\n    break;\n  }\n  case 2: {\n    return 2;\n  }\n  default: {\n    console.log(\"other\");\n  }\n}"
+        )
     }
 
     #[test]
     fn generate_funcall() {
-        let stmt = gen_stmt("sum(1,2,3,4,5,6)");
-        assert_eq!(stmt, "sum(1,2,3,4,5,6)")
+        let stmt = gen_stmt("sum(1,2,3,4,5,6);");
+        assert_eq!(stmt, "sum(1,2,3,4,5,6);")
     }
 
     #[test]
     fn generate_methodcalls() {
         let stmt = gen_stmt("cat.meow(); Math.add(2, sum(1,2))");
-        assert_eq!(stmt,"cat.meow(); \nMath.add(2,sum(1,2))")
+        assert_eq!(stmt,"cat.meow();\nMath.add(2,sum(1,2));")
     }
 
     #[test]
     fn generate_new_instance() {
         let stmt = gen_stmt("let cat: Animal = new Animal(\"meow\");");
-        assert_eq!(stmt, "let cat = new Animal(\"meow\")")
+        assert_eq!(stmt, "let cat = new Animal(\"meow\");")
     }
 
     #[test]
     fn generate_nested_statement() {
         let stmt = gen_stmt("let x:Int = sum(sum(a,b),sum(c,Math.sqrt(d)));");
-        assert_eq!(stmt, "let x = sum(sum(a,b),sum(c,Math.sqrt(d)))")
+        assert_eq!(stmt, "let x = sum(sum(a,b),sum(c,Math.sqrt(d)));")
     }
 }
\ No newline at end of file
@@ -0,0 +1,240 @@
+use crate::codegen::escape_js_string;
+use crate::lexer::{LexicalError, Span};
+use crate::parser::{self, ParseError};
+use crate::sema::{self, SemanticError};
+use colored::*;
+use std::ops::RangeInclusive;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CompileError {
+    #[error("Lexical error: {0}")]
+    Lexical(#[from] LexicalError),
+
+    #[error("Found {} parse error(s)", .0.len())]
+    Parse(Vec<ParseError>),
+
+    #[error("Found {} semantic error(s)", .0.len())]
+    Semantic(Vec<SemanticError>),
+}
+
+impl CompileError {
+    pub fn print_with_context(&self, source: &str) {
+        match self {
+            Self::Lexical(error) => eprintln!("Lexical error: {}", error),
+            Self::Parse(errors) => parser::print_errors(errors, source),
+            Self::Semantic(errors) => sema::print_errors(errors, source),
+        }
+    }
+
+    /// Every error as a one-line JSON object, for editors and other tools.
+    pub fn to_json(&self) -> Vec<String> {
+        match self {
+            Self::Lexical(error) => vec![error.to_json()],
+            Self::Parse(errors) => errors.iter().map(ParseError::to_json).collect(),
+            Self::Semantic(errors) => errors.iter().map(SemanticError::to_json).collect(),
+        }
+    }
+}
+
+/// How serious a diagnostic is. Errors stop compilation, warnings don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+
+    fn label(self) -> ColoredString {
+        match self {
+            Severity::Error => self.name().red().bold(),
+            Severity::Warning => self.name().yellow().bold(),
+        }
+    }
+}
+
+/// Prints a single diagnostic with its code, message and, when the span points
+/// into `source`, the surrounding lines with the offending text underlined.
+pub(crate) fn print_diagnostic(
+    severity: Severity,
+    code: &str,
+    message: &str,
+    span: Option<&Span>,
+    source: &str,
+) {
+    eprintln!(
+        "{}: {} {}",
+        severity.label(),
+        code.yellow(),
+        message.white().bold()
+    );
+
+    // print source context if we have a valid span
+    if let Some(span) = span {
+        let lines: Vec<&str> = source.lines().collect();
+
+        if span.line > 0 && span.line <= lines.len() {
+            // print file location
+            eprintln!(
+                "{} {}:{}:{}",
+                "-->".blue().bold(),
+                "input".cyan(),
+                span.line,
+                span.column
+            );
+
+            eprintln!("{}", "    |".blue().bold());
+
+            for line_num in context_lines(span.line, lines.len()) {
+                let line = lines[line_num - 1];
+                print_context_line(line_num, line);
+                if line_num == span.line {
+                    let indicator = underline(span, source, line);
+                    eprintln!("{} {}", "    |".blue().bold(), indicator.red().bold());
+                }
+            }
+        }
+    }
+
+    eprintln!();
+}
+
+/// The 1-based numbers of the lines shown around an error on `line`: the
+/// line itself plus its neighbours, where the input has them.
+fn context_lines(line: usize, line_count: usize) -> RangeInclusive<usize> {
+    line.saturating_sub(1).max(1)..=(line + 1).min(line_count)
+}
+
+/// Renders a diagnostic as a single-line JSON object with its `code`,
+/// `message`, `line`, `column` and `severity`. Without a span, `line` and
+/// `column` are `null`.
+pub(crate) fn diagnostic_json(severity: Severity, code: &str, message: &str, span: Option<&Span>) -> String {
+    let (line, column) = match span {
+        Some(span) => (span.line.to_string(), span.column.to_string()),
+        None => ("null".to_string(), "null".to_string()),
+    };
+    format!(
+        r#"{{"code":"{}","message":"{}","line":{},"column":{},"severity":"{}"}}"#,
+        escape_js_string(code),
+        escape_js_string(message),
+        line,
+        column,
+        severity.name()
+    )
+}
+
+/// Builds the `^^^` marker for `span` on its source line. The run covers the
+/// span's text, clipped to the end of the line, and is never shorter than one
+/// caret so zero-width spans (like EOF) still point somewhere.
+fn underline(span: &Span, source: &str, line: &str) -> String {
+    let column = span.column.saturating_sub(1);
+    let width = source
+        .get(span.start..span.end)
+        .map_or(0, |text| text.chars().count())
+        .min(line.chars().count().saturating_sub(column))
+        .max(1);
+    " ".repeat(column) + &"^".repeat(width)
+}
+
+pub(crate) fn print_context_line(line_num: usize, content: &str) {
+    eprintln!("{} {}", format!("{:3} |", line_num).blue().bold(), content);
+}
+
+/// Prints every error in `errors` between a count header and an abort footer.
+pub(crate) fn print_error_list<E>(errors: &[E], source: &str, print: fn(&E, &str)) {
+    if errors.is_empty() {
+        return;
+    }
+
+    let error_count = errors.len();
+    let error_text = if error_count == 1 { "error" } else { "errors" };
+
+    eprintln!(
+        "\n{}",
+        format!("Found {} {}", error_count, error_text).red().bold()
+    );
+
+    for err in errors {
+        print(err, source);
+    }
+
+    eprintln!(
+        "{}: {} {}",
+        "error".red().bold(),
+        format!("aborting due to {} {}", error_count, error_text).bold(),
+        "😞".yellow()
+    );
+
+    eprintln!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    #[test]
+    fn test_underline_covers_identifier() {
+        let source = "let x: Int = 5 counter;";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse();
+        let span = parser.get_errors()[0].get_span().copied().unwrap();
+
+        assert_eq!(underline(&span, source, source), "               ^^^^^^^");
+    }
+
+    #[test]
+    fn test_context_lines() {
+        // a single line has no neighbours to show
+        assert_eq!(context_lines(1, 1), 1..=1);
+        assert_eq!(context_lines(1, 3), 1..=2);
+        assert_eq!(context_lines(2, 3), 1..=3);
+        // nothing past the last line
+        assert_eq!(context_lines(3, 3), 2..=3);
+
+        let source = "let a: Int = 1;\nlet b: Int = 2;\nlet c: Int = 3 d;";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse();
+        let span = parser.get_errors()[0].get_span().copied().unwrap();
+        let lines: Vec<&str> = source.lines().collect();
+        assert_eq!(context_lines(span.line, lines.len()), 2..=3);
+        assert_eq!(underline(&span, source, lines[span.line - 1]), "               ^");
+    }
+
+    #[test]
+    fn test_parse_error_as_json() {
+        let Err(error) = crate::compile_str("let x: Int = 5;\nlet y: Int = 5 z;") else {
+            panic!("expected a parse error");
+        };
+        let json = error.to_json();
+        assert_eq!(json.len(), 1);
+        assert_eq!(
+            json[0],
+            r#"{"code":"E012","message":"Expected ';' but found 'Identifier(z)' at line 2, column 16","line":2,"column":16,"severity":"error"}"#
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_json_escapes_and_nulls() {
+        let json = diagnostic_json(Severity::Warning, "W001", "say \"hi\"\n", None);
+        assert_eq!(
+            json,
+            r#"{"code":"W001","message":"say \"hi\"\n","line":null,"column":null,"severity":"warning"}"#
+        );
+    }
+
+    #[test]
+    fn test_underline_zero_width() {
+        let span = Span::new(3, 3, 1, 4);
+        assert_eq!(underline(&span, "abc", "abc"), "   ^");
+    }
+}
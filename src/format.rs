@@ -0,0 +1,515 @@
+use crate::ast::*;
+use std::fmt::{self, Write};
+
+const INDENT: &str = "  ";
+
+/// Renders `entry` back into canonical LavaScript source: two-space indents,
+/// one statement per line and single spaces around operators. Unlike the
+/// `PrettyPrint` view this is plain text that parses back to the same tree.
+///
+/// The AST keeps classes, functions and top-level statements in separate
+/// lists, so they come out in that order regardless of how they were
+/// interleaved in the input.
+///
+/// Only the doc comments of classes, functions and methods survive, and
+/// only when the source was lexed with comments kept; the AST has nowhere
+/// to hold any other comment.
+pub fn format_program(entry: &Entry) -> String {
+    let mut out = String::new();
+    write_entry(&mut out, entry).expect("writing to a String cannot fail");
+    out
+}
+
+fn write_entry(out: &mut String, entry: &Entry) -> fmt::Result {
+    let mut first = true;
+    let mut separate = |out: &mut String| {
+        if !std::mem::take(&mut first) {
+            out.push('\n');
+        }
+    };
+
+    for class_def in &entry.class_defs {
+        separate(out);
+        write_class(out, class_def)?;
+    }
+    for fun_def in &entry.fun_defs {
+        separate(out);
+        write_doc(out, &fun_def.doc, 0);
+        write_callable(out, "fun", &fun_def.name, &fun_def.params, &fun_def.return_type, &fun_def.statements, 0)?;
+    }
+    if !entry.statements.is_empty() {
+        separate(out);
+        for stmt in &entry.statements {
+            write_stmt(out, stmt, 0)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_indent(out: &mut String, level: usize) {
+    out.push_str(&INDENT.repeat(level));
+}
+
+/// The indentation level of the line currently being written.
+fn current_level(out: &str) -> usize {
+    let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+    (line.len() - line.trim_start_matches(' ').len()) / INDENT.len()
+}
+
+/// Writes a doc comment on the lines above its declaration, re-indented to
+/// `level`, with the `*` of `/* */` continuation lines lined up.
+fn write_doc(out: &mut String, doc: &Option<String>, level: usize) {
+    let Some(doc) = doc else {
+        return;
+    };
+    for line in doc.lines() {
+        let line = line.trim();
+        write_indent(out, level);
+        if line.starts_with('*') {
+            out.push(' ');
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn write_class(out: &mut String, class_def: &ClassDef) -> fmt::Result {
+    write_doc(out, &class_def.doc, 0);
+    write!(out, "class {}", class_def.name)?;
+    if let Some(parent) = &class_def.extends {
+        write!(out, " extends {}", parent)?;
+    }
+    out.push_str(" {\n");
+
+    for var in &class_def.vars {
+        write_indent(out, 1);
+        writeln!(out, "{} {}: {};", var.keyword(), var.name, var.var_type)?;
+    }
+
+    let constructor = &class_def.constructor;
+    write_indent(out, 1);
+    out.push_str("init(");
+    write_params(out, &constructor.params)?;
+    out.push_str(") {");
+    if constructor.super_call.is_none() && constructor.statements.is_empty() {
+        out.push_str("}\n");
+    } else {
+        out.push('\n');
+        write_constructor_body(out, constructor)?;
+    }
+
+    for method in &class_def.methods {
+        write_doc(out, &method.doc, 1);
+        write_callable(out, "meth", &method.name, &method.params, &method.return_type, &method.statements, 1)?;
+    }
+
+    out.push_str("}\n");
+    Ok(())
+}
+
+fn write_constructor_body(out: &mut String, constructor: &Constructor) -> fmt::Result {
+    let (before_super, after_super) = constructor.split_at_super();
+    for stmt in before_super {
+        write_stmt(out, stmt, 2)?;
+    }
+    if let Some(super_call) = &constructor.super_call {
+        write_indent(out, 2);
+        out.push_str("super(");
+        write_exprs(out, &super_call.args)?;
+        out.push_str(");\n");
+    }
+    for stmt in after_super {
+        write_stmt(out, stmt, 2)?;
+    }
+    write_indent(out, 1);
+    out.push_str("}\n");
+    Ok(())
+}
+
+fn write_callable(
+    out: &mut String,
+    keyword: &str,
+    name: &str,
+    params: &[ParamDecl],
+    return_type: &crate::lexer::TypeName,
+    body: &[Stmt],
+    level: usize,
+) -> fmt::Result {
+    write_indent(out, level);
+    write!(out, "{} {}(", keyword, name)?;
+    write_params(out, params)?;
+    write!(out, ") -> {} ", return_type)?;
+    write_block(out, body, level)
+}
+
+fn write_params(out: &mut String, params: &[ParamDecl]) -> fmt::Result {
+    for (i, param) in params.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write!(out, "{}: {}", param.name, param.param_type)?;
+        if let Some(default) = &param.default {
+            out.push_str(" = ");
+            write_expr(out, default)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `{`, the statements one level deeper, and a closing `}`
+/// at `level`. The caller has already written whatever precedes the brace.
+fn write_block(out: &mut String, statements: &[Stmt], level: usize) -> fmt::Result {
+    if statements.is_empty() {
+        out.push_str("{}\n");
+        return Ok(());
+    }
+    out.push_str("{\n");
+    for stmt in statements {
+        write_stmt(out, stmt, level + 1)?;
+    }
+    write_indent(out, level);
+    out.push_str("}\n");
+    Ok(())
+}
+
+/// Writes an `if`/`else`/`while` body, always braced, without a trailing
+/// newline so an `else` can follow on the same line.
+fn write_branch(out: &mut String, body: &Stmt, level: usize) -> fmt::Result {
+    let statements = match body {
+        Stmt::Block(block) => block.statements.as_slice(),
+        stmt => std::slice::from_ref(stmt),
+    };
+    if statements.iter().all(|stmt| matches!(stmt, Stmt::Empty)) {
+        out.push_str("{}");
+        return Ok(());
+    }
+    out.push_str("{\n");
+    for stmt in statements {
+        write_stmt(out, stmt, level + 1)?;
+    }
+    write_indent(out, level);
+    out.push('}');
+    Ok(())
+}
+
+fn write_stmt(out: &mut String, stmt: &Stmt, level: usize) -> fmt::Result {
+    if matches!(stmt, Stmt::Empty) {
+        return Ok(());
+    }
+    write_indent(out, level);
+
+    match stmt {
+        Stmt::Expr(s) => {
+            write_expr(out, &s.expr)?;
+            out.push_str(";\n");
+        }
+        Stmt::VarDecl(s) => {
+            write!(out, "{} ", s.keyword())?;
+            write_declarator(out, stmt)?;
+            out.push_str(";\n");
+        }
+        Stmt::Assign(s) => {
+            write!(out, "{} = ", s.name)?;
+            write_expr(out, &s.expr)?;
+            out.push_str(";\n");
+        }
+        Stmt::VarDeclWithAssign(s) => {
+            write!(out, "{} ", s.keyword())?;
+            write_declarator(out, stmt)?;
+            out.push_str(";\n");
+        }
+        Stmt::MultiVarDecl(s) => {
+            write!(out, "{} ", s.keyword())?;
+            for (i, decl) in s.decls.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_declarator(out, decl)?;
+            }
+            out.push_str(";\n");
+        }
+        Stmt::While(s) => {
+            out.push_str("while (");
+            write_expr(out, &s.condition)?;
+            out.push_str(") ");
+            write_branch(out, &s.body, level)?;
+            out.push('\n');
+        }
+        Stmt::ForRange(s) => {
+            write!(out, "for ({} in ", s.var)?;
+            write_expr(out, &s.start)?;
+            out.push_str(if s.inclusive { "..=" } else { ".." });
+            write_expr(out, &s.end)?;
+            out.push_str(") ");
+            write_branch(out, &s.body, level)?;
+            out.push('\n');
+        }
+        Stmt::DoWhile(s) => {
+            out.push_str("do ");
+            write_branch(out, &s.body, level)?;
+            out.push_str(" while (");
+            write_expr(out, &s.condition)?;
+            out.push_str(");\n");
+        }
+        Stmt::If(s) => {
+            out.push_str("if (");
+            write_expr(out, &s.condition)?;
+            out.push_str(") ");
+            write_branch(out, &s.then_branch, level)?;
+            if let Some(else_branch) = &s.else_branch {
+                out.push_str(" else ");
+                write_branch(out, else_branch, level)?;
+            }
+            out.push('\n');
+        }
+        Stmt::Match(s) => {
+            out.push_str("match (");
+            write_expr(out, &s.scrutinee)?;
+            out.push_str(") {\n");
+            for arm in &s.arms {
+                write_indent(out, level + 1);
+                write_expr(out, &arm.pattern)?;
+                out.push_str(" => ");
+                write_branch(out, &arm.body, level + 1)?;
+                out.push('\n');
+            }
+            if let Some(default) = &s.default {
+                write_indent(out, level + 1);
+                out.push_str("_ => ");
+                write_branch(out, default, level + 1)?;
+                out.push('\n');
+            }
+            write_indent(out, level);
+            out.push_str("}\n");
+        }
+        Stmt::Break(_) => out.push_str("break;\n"),
+        Stmt::Continue(_) => out.push_str("continue;\n"),
+        Stmt::Return(s) => {
+            out.push_str("return");
+            if let Some(value) = &s.value {
+                out.push(' ');
+                write_expr(out, value)?;
+            }
+            out.push_str(";\n");
+        }
+        Stmt::Block(block) => write_block(out, &block.statements, level)?,
+        Stmt::Empty => {}
+    }
+    Ok(())
+}
+
+fn write_exprs(out: &mut String, exprs: &[Expr]) -> fmt::Result {
+    for (i, expr) in exprs.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_expr(out, expr)?;
+    }
+    Ok(())
+}
+
+/// Writes the `name: type = expr` of a declaration, without its keyword.
+fn write_declarator(out: &mut String, decl: &Stmt) -> fmt::Result {
+    match decl {
+        Stmt::VarDecl(s) => write!(out, "{}: {}", s.name, s.var_type),
+        Stmt::VarDeclWithAssign(s) => {
+            out.push_str(&s.name);
+            if let Some(var_type) = &s.var_type {
+                write!(out, ": {}", var_type)?;
+            }
+            out.push_str(" = ");
+            write_expr(out, &s.expr)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn write_expr(out: &mut String, expr: &Expr) -> fmt::Result {
+    match expr {
+        Expr::IntegerLiteral(lit) => write!(out, "{}", lit.value)?,
+        Expr::StringLiteral(lit) => write!(out, "\"{}\"", escape_string(&lit.value))?,
+        Expr::CharLiteral(lit) => match lit.value {
+            '\'' => out.push_str("'\\''"),
+            '"' => out.push_str("'\"'"),
+            ch => write!(out, "'{}'", escape_string(&ch.to_string()))?,
+        },
+        Expr::BooleanLiteral(lit) => write!(out, "{}", lit.value)?,
+        Expr::Nil(_) => out.push_str("nil"),
+        Expr::Variable(var) => out.push_str(&var.name),
+        // the parser keeps explicit parentheses as `Grouped`, so precedence
+        // is already encoded in the tree and no extra parens are needed
+        Expr::Binary(binary) => {
+            write_expr(out, &binary.left)?;
+            write!(out, " {} ", binary.operator)?;
+            write_expr(out, &binary.right)?;
+        }
+        Expr::Unary(unary) => {
+            write!(out, "{}", unary.operator)?;
+            write_expr(out, &unary.expr)?;
+        }
+        Expr::FunCall(call) => {
+            write!(out, "{}(", call.callee)?;
+            write_exprs(out, &call.args)?;
+            out.push(')');
+        }
+        Expr::MethCall(call) => {
+            write_expr(out, &call.object)?;
+            write!(out, ".{}(", call.meth)?;
+            write_exprs(out, &call.args)?;
+            out.push(')');
+        }
+        Expr::Field(field) => {
+            write_expr(out, &field.object)?;
+            write!(out, ".{}", field.field)?;
+        }
+        Expr::New(new_expr) => {
+            write!(out, "new {}(", new_expr.class_name)?;
+            write_exprs(out, &new_expr.args)?;
+            out.push(')');
+        }
+        Expr::This(_) => out.push_str("this"),
+        Expr::Super(_) => out.push_str("super"),
+        Expr::Println(p) => {
+            out.push_str("println(");
+            write_exprs(out, &p.args)?;
+            out.push(')');
+        }
+        Expr::Print(p) => {
+            out.push_str("print(");
+            write_exprs(out, &p.args)?;
+            out.push(')');
+        }
+        Expr::Len(l) => {
+            out.push_str("len(");
+            write_expr(out, &l.arg)?;
+            out.push(')');
+        }
+        Expr::TypeNameOf(t) => {
+            out.push_str("typeName(");
+            write_expr(out, &t.arg)?;
+            out.push(')');
+        }
+        Expr::Lambda(lambda) => {
+            out.push_str("fun (");
+            write_params(out, &lambda.params)?;
+            write!(out, ") -> {} ", lambda.return_type)?;
+            if lambda.body.is_empty() {
+                out.push_str("{}");
+            } else {
+                // expressions don't track how deeply they're nested, so the
+                // body is indented from the line the lambda starts on
+                let level = current_level(out);
+                out.push_str("{\n");
+                for stmt in &lambda.body {
+                    write_stmt(out, stmt, level + 1)?;
+                }
+                write_indent(out, level);
+                out.push('}');
+            }
+        }
+        Expr::Assert(a) => {
+            out.push_str("assert(");
+            write_expr(out, &a.cond)?;
+            if let Some(message) = &a.message {
+                out.push_str(", ");
+                write_expr(out, message)?;
+            }
+            out.push(')');
+        }
+        Expr::Grouped(inner, _) => {
+            out.push('(');
+            write_expr(out, inner)?;
+            out.push(')');
+        }
+        Expr::Empty => {}
+    }
+    Ok(())
+}
+
+/// Undoes the lexer's escape handling so the literal reads back the same.
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\0' => escaped.push_str("\\0"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn format(input: &str) -> String {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        assert!(!parser.has_errors(), "{:?}", parser.get_errors());
+        format_program(&ast)
+    }
+
+    #[test]
+    fn test_format_var_decl() {
+        assert_eq!(format("let   x:Int=5 ;"), "let x: Int = 5;\n");
+        assert_eq!(format("let a:Int,b=a+1 ;"), "let a: Int, b = a + 1;\n");
+    }
+
+    #[test]
+    fn test_format_char_literals() {
+        assert_eq!(format(r#"let c:Str='\''+'"'+'\n';"#), "let c: Str = '\\'' + '\"' + '\\n';\n");
+    }
+
+    #[test]
+    fn test_format_keeps_doc_comments() {
+        let source = "// An animal.\nclass A{\n  init(){}\n  /* Says\n   * hi. */\n  meth hi()->Void{}\n}\n// Adds one.\nfun f(x:Int)->Int{return x+1;}";
+        let tokens = Lexer::with_comments(source).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        assert!(parser.dropped_comments.is_empty());
+        assert_eq!(
+            format_program(&ast),
+            "// An animal.\nclass A {\n  init() {}\n  /* Says\n   * hi. */\n  meth hi() -> Void {}\n}\n\n// Adds one.\nfun f(x: Int) -> Int {\n  return x + 1;\n}\n"
+        );
+
+        // a comment inside a body has nowhere to go in the AST
+        let tokens = Lexer::with_comments("fun f() -> Void {\n  // gone\n  println(1);\n}").tokenize().unwrap();
+        let parser = Parser::new(tokens);
+        assert!(matches!(parser.dropped_comments.as_slice(), [span] if span.line == 2));
+    }
+
+    #[test]
+    fn test_format_empty_bodies() {
+        assert_eq!(
+            format("class A{init(){}} fun f()->Void{}"),
+            "class A {\n  init() {}\n}\n\nfun f() -> Void {}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_round_trips() {
+        let messy = r#"
+            class Shape { let name:Str; init(name:Str){ this.name=name; } meth describe()->Str{ return this.name; } }
+            class Square extends Shape { init(){ super("square"); } }
+            fun area(w:Int,h:Int)->Int{ let a:Int = w*(h+1); while(a>10){ a=a-1; if(a==5){ break; } else { continue; } } return a; }
+            let s:Square=new Square(); println(s.describe(), area(2, -3), "say \"hi\"\n");
+        "#;
+        let formatted = format(messy);
+        assert_eq!(format(&formatted), formatted);
+        assert_eq!(
+            crate::compile_str(&formatted).unwrap(),
+            crate::compile_str(messy).unwrap()
+        );
+        assert!(formatted.contains("  meth describe() -> Str {\n    return this.name;\n  }\n"));
+        assert!(formatted.contains(r#""say \"hi\"\n""#));
+        assert!(formatted.contains("let a: Int = w * (h + 1);"));
+    }
+}
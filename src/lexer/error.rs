@@ -1,3 +1,4 @@
+use crate::error::{diagnostic_json, Severity};
 use crate::lexer::Span;
 use thiserror::Error;
 
@@ -9,12 +10,59 @@ pub enum LexicalError {
     #[error("Unterminated string literal at {span}")]
     UnterminatedString { span: Span },
 
+    #[error("Unterminated character literal at {span}")]
+    UnterminatedChar { span: Span },
+
+    #[error("Empty character literal at {span}")]
+    EmptyCharLiteral { span: Span },
+
+    #[error("Character literal '{value}' holds more than one character at {span}")]
+    MultiCharLiteral { value: String, span: Span },
+
     #[error("Invalid number format '{value}' at {span}")]
     InvalidNumber { value: String, span: Span },
 
+    #[error("Integer literal '{value}' is too large (the maximum is {}) at {span}", i64::MAX)]
+    IntegerOverflow { value: String, span: Span },
+
     #[error("Invalid escape sequence '\\{escape}' at {span}")]
     InvalidEscapeSequence { escape: char, span: Span },
 
     #[error("Unexpected end of file at {span}")]
     UnexpectedEOF { span: Span },
 }
+
+impl LexicalError {
+    pub fn get_span(&self) -> Option<&Span> {
+        match self {
+            Self::InvalidChar { span, .. }
+            | Self::UnterminatedString { span }
+            | Self::UnterminatedChar { span }
+            | Self::EmptyCharLiteral { span }
+            | Self::MultiCharLiteral { span, .. }
+            | Self::InvalidNumber { span, .. }
+            | Self::IntegerOverflow { span, .. }
+            | Self::InvalidEscapeSequence { span, .. }
+            | Self::UnexpectedEOF { span } => Some(span),
+        }
+    }
+
+    /// Lexer codes sit below the parser's; E009 is the parser's end-of-file error.
+    pub fn get_code(&self) -> &str {
+        match self {
+            Self::InvalidChar { .. } => "E001",
+            Self::UnterminatedString { .. } => "E002",
+            Self::UnterminatedChar { .. } => "E003",
+            Self::EmptyCharLiteral { .. } => "E004",
+            Self::MultiCharLiteral { .. } => "E005",
+            Self::InvalidNumber { .. } => "E006",
+            Self::IntegerOverflow { .. } => "E007",
+            Self::InvalidEscapeSequence { .. } => "E008",
+            Self::UnexpectedEOF { .. } => "E010",
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        diagnostic_json(Severity::Error, self.get_code(), &self.to_string(), self.get_span())
+    }
+}
@@ -8,42 +8,71 @@ pub use token::{Token, TokenType, TypeName};
 
 pub type Result<T> = std::result::Result<T, LexicalError>;
 
-pub struct Lexer {
-    input: Vec<char>,
+pub struct Lexer<'a> {
+    input: &'a str,
+    /// Byte offset into `input`, always on a char boundary.
     position: usize,
     line: usize,
     column: usize,
     start_column: usize,
+    start_position: usize,
+    /// Whether the last token was a `-`, the one place `i64::MIN`'s magnitude
+    /// may appear as a literal.
+    after_minus: bool,
+    /// Emit comments as [`TokenType::Comment`] instead of skipping them.
+    keep_comments: bool,
+    /// Set once iteration has yielded `EOF` or an error.
+    finished: bool,
 }
 
-impl Lexer {
-    pub fn new(source: &str) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
         Lexer {
-            input: source.chars().collect(),
+            input: source,
             position: 0,
             line: 1,
             column: 1,
             start_column: 1,
+            start_position: 0,
+            after_minus: false,
+            keep_comments: false,
+            finished: false,
         }
     }
 
+    /// A lexer that keeps comments as tokens, so the parser can carry doc
+    /// comments through to the generated code.
+    pub fn with_comments(source: &'a str) -> Self {
+        Self {
+            keep_comments: true,
+            ..Self::new(source)
+        }
+    }
+
+    fn remaining(&self) -> std::str::Chars<'a> {
+        self.input[self.position..].chars()
+    }
+
     fn peek(&self) -> Option<char> {
-        self.input.get(self.position).copied()
+        self.remaining().next()
     }
 
     fn peek_ahead(&self) -> Option<char> {
-        self.input.get(self.position + 1).copied()
+        self.remaining().nth(1)
     }
 
     fn advance(&mut self) -> Option<char> {
         let ch = self.peek();
-        self.position += 1;
         if let Some(c) = ch {
-            if c == '\n' {
-                self.line += 1;
-                self.column = 1;
-            } else {
-                self.column += 1;
+            self.position += c.len_utf8();
+            match c {
+                '\n' => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                // the `\r` of a `\r\n` pair takes no column, the `\n` ends the line
+                '\r' if self.peek() == Some('\n') => {}
+                _ => self.column += 1,
             }
         }
         ch
@@ -57,6 +86,7 @@ impl Lexer {
             self.advance();
         }
         self.start_column = self.column;
+        self.start_position = self.position;
     }
 
     fn skip_line_comment(&mut self) {
@@ -67,6 +97,7 @@ impl Lexer {
             self.advance();
         }
         self.start_column = self.column;
+        self.start_position = self.position;
     }
 
     fn skip_block_comment(&mut self) {
@@ -83,52 +114,115 @@ impl Lexer {
         self.advance();
         self.advance();
         self.start_column = self.column;
+        self.start_position = self.position;
+    }
+
+    /// Turns the comment just skipped into a token when comments are kept,
+    /// and otherwise moves on to the token after it.
+    fn comment_or_next(&mut self, mut token: Token) -> Result<Token> {
+        if !self.keep_comments {
+            return self.next_token();
+        }
+        let text = self.input[token.span.start..self.position].to_string();
+        token.set_type(TokenType::Comment(text));
+        Ok(token)
     }
 
     fn read_string(&mut self) -> Result<Token> {
         let start_span = self.current_location();
+        match self.read_quoted('"')? {
+            Some(string) => Ok(Token::new(TokenType::StringLiteral(string), start_span)),
+            None => Err(LexicalError::UnterminatedString { span: start_span }),
+        }
+    }
+
+    fn read_char(&mut self) -> Result<Token> {
+        let start_span = self.current_location();
+        let Some(value) = self.read_quoted('\'')? else {
+            return Err(LexicalError::UnterminatedChar { span: start_span });
+        };
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(Token::new(TokenType::CharLiteral(ch), start_span)),
+            (None, _) => Err(LexicalError::EmptyCharLiteral { span: start_span }),
+            (Some(_), Some(_)) => Err(LexicalError::MultiCharLiteral { value, span: start_span }),
+        }
+    }
+
+    /// Reads the text between `quote` and its closing match, resolving
+    /// escapes. Returns `None` when the input ends first.
+    fn read_quoted(&mut self, quote: char) -> Result<Option<String>> {
         let mut string = String::new();
         self.advance(); // skip opening quote
 
         while let Some(ch) = self.peek() {
             match ch {
-                '"' => {
+                ch if ch == quote => {
                     self.advance(); // skip closing quote
-                    return Ok(Token::new(TokenType::StringLiteral(string), start_span));
-                }
-                '\\' => {
-                    self.advance();
-                    if let Some(next) = self.peek() {
-                        let escaped = match next {
-                            'n' => '\n',
-                            't' => '\t',
-                            'r' => '\r',
-                            '"' => '"',
-                            '\\' => '\\',
-                            _ => {
-                                return Err(LexicalError::InvalidEscapeSequence {
-                                    escape: next,
-                                    span: start_span,
-                                })
-                            }
-                        };
-                        string.push(escaped);
-                        self.advance();
-                    } else {
-                        return Err(LexicalError::UnexpectedEOF { span: start_span });
-                    }
+                    return Ok(Some(string));
                 }
+                '\\' => string.push(self.read_escape()?),
                 _ => {
                     string.push(ch);
                     self.advance();
                 }
             }
         }
-        Err(LexicalError::UnterminatedString { span: start_span })
+        Ok(None)
+    }
+
+    /// Reads one escape sequence, backslash included, for string and char
+    /// literals alike: `\n \t \r \" \' \\ \0`, `\xNN` with two hex digits,
+    /// and `\u{...}` with one to six.
+    fn read_escape(&mut self) -> Result<char> {
+        let span = self.current_location();
+        self.advance(); // skip the backslash
+        let Some(escape) = self.advance() else {
+            return Err(LexicalError::UnexpectedEOF { span });
+        };
+        let invalid = LexicalError::InvalidEscapeSequence { escape, span };
+        let code = match escape {
+            'n' => return Ok('\n'),
+            't' => return Ok('\t'),
+            'r' => return Ok('\r'),
+            '0' => return Ok('\0'),
+            '"' | '\'' | '\\' => return Ok(escape),
+            'x' => self.read_hex_digits(2, 2),
+            'u' => {
+                if self.advance() != Some('{') {
+                    return Err(invalid);
+                }
+                let code = self.read_hex_digits(1, 6);
+                if self.advance() != Some('}') {
+                    return Err(invalid);
+                }
+                code
+            }
+            _ => None,
+        };
+        code.and_then(char::from_u32).ok_or(invalid)
+    }
+
+    /// Reads between `min` and `max` hex digits as one number.
+    fn read_hex_digits(&mut self, min: usize, max: usize) -> Option<u32> {
+        let mut digits = String::new();
+        while digits.len() < max {
+            match self.peek() {
+                Some(ch) if ch.is_ascii_hexdigit() => {
+                    digits.push(ch);
+                    self.advance();
+                }
+                _ => break,
+            }
+        }
+        if digits.len() < min {
+            return None;
+        }
+        u32::from_str_radix(&digits, 16).ok()
     }
 
     fn current_location(&self) -> Span {
-        Span::new(self.line, self.start_column)
+        Span::new(self.start_position, self.start_position, self.line, self.start_column)
     }
 
     fn read_number(&mut self) -> Result<Token> {
@@ -149,9 +243,15 @@ impl Lexer {
             }
         }
 
+        // only digits were collected, so the one way parsing can fail is overflow
         match number.parse::<i64>() {
             Ok(n) => Ok(Token::new(TokenType::IntegerLiteral(n), start_span)),
-            Err(_) => Err(LexicalError::InvalidNumber {
+            // `-9223372036854775808` fits even though its magnitude doesn't; the
+            // parser folds the negation back in and rejects it anywhere else
+            Err(_) if self.after_minus && number.parse::<u64>() == Ok(i64::MIN.unsigned_abs()) => {
+                Ok(Token::new(TokenType::IntegerLiteral(i64::MIN), start_span))
+            }
+            Err(_) => Err(LexicalError::IntegerOverflow {
                 value: number,
                 span: start_span,
             }),
@@ -159,19 +259,29 @@ impl Lexer {
     }
 
     fn read_identifier(&mut self) -> String {
-        let mut identifier = String::new();
+        let start = self.position;
         while let Some(ch) = self.peek() {
             if ch.is_alphanumeric() || ch == '_' {
-                identifier.push(ch);
                 self.advance();
             } else {
                 break;
             }
         }
-        identifier
+        self.input[start..self.position].to_string()
     }
 
     pub fn next_token(&mut self) -> Result<Token> {
+        let mut token = self.scan_token()?;
+        token.span.end = self.position;
+        self.after_minus = token.token_type == TokenType::Minus;
+        Ok(token)
+    }
+
+    fn scan_token(&mut self) -> Result<Token> {
+        // a `#!` interpreter line is only meaningful as the very first line
+        if self.position == 0 && self.input.starts_with("#!") {
+            self.skip_line_comment();
+        }
         self.skip_whitespace();
         let start_span = self.current_location();
         let mut current_token = Token::new_with_span(start_span);
@@ -197,17 +307,24 @@ impl Lexer {
                     }
                 }
                 '*' => {
-                    self.advance();
-                    current_token.set_type(TokenType::Star);
-                    Ok(current_token)
+                    if self.peek_ahead() == Some('*') {
+                        self.advance();
+                        self.advance();
+                        current_token.set_type(TokenType::StarStar);
+                        Ok(current_token)
+                    } else {
+                        self.advance();
+                        current_token.set_type(TokenType::Star);
+                        Ok(current_token)
+                    }
                 }
                 '/' => {
                     if self.peek_ahead() == Some('/') {
                         self.skip_line_comment();
-                        return self.next_token();
+                        self.comment_or_next(current_token)
                     } else if self.peek_ahead() == Some('*') {
                         self.skip_block_comment();
-                        return self.next_token();
+                        self.comment_or_next(current_token)
                     } else {
                         self.advance();
                         current_token.set_type(TokenType::Slash);
@@ -220,6 +337,11 @@ impl Lexer {
                         self.advance();
                         current_token.set_type(TokenType::Equal);
                         Ok(current_token)
+                    } else if self.peek_ahead() == Some('>') {
+                        self.advance();
+                        self.advance();
+                        current_token.set_type(TokenType::FatArrow);
+                        Ok(current_token)
                     } else {
                         self.advance();
                         current_token.set_type(TokenType::Assign);
@@ -232,6 +354,11 @@ impl Lexer {
                         self.advance();
                         current_token.set_type(TokenType::GreaterEqual);
                         Ok(current_token)
+                    } else if self.peek_ahead() == Some('>') {
+                        self.advance();
+                        self.advance();
+                        current_token.set_type(TokenType::Shr);
+                        Ok(current_token)
                     } else {
                         self.advance();
                         current_token.set_type(TokenType::Greater);
@@ -244,6 +371,11 @@ impl Lexer {
                         self.advance();
                         current_token.set_type(TokenType::LessEqual);
                         Ok(current_token)
+                    } else if self.peek_ahead() == Some('<') {
+                        self.advance();
+                        self.advance();
+                        current_token.set_type(TokenType::Shl);
+                        Ok(current_token)
                     } else {
                         self.advance();
                         current_token.set_type(TokenType::Less);
@@ -299,7 +431,17 @@ impl Lexer {
                 }
                 '.' => {
                     self.advance();
-                    current_token.set_type(TokenType::Dot);
+                    if self.peek() == Some('.') {
+                        self.advance();
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            current_token.set_type(TokenType::DotDotEqual);
+                        } else {
+                            current_token.set_type(TokenType::DotDot);
+                        }
+                    } else {
+                        current_token.set_type(TokenType::Dot);
+                    }
                     Ok(current_token)
                 }
                 '[' => {
@@ -319,10 +461,9 @@ impl Lexer {
                         current_token.set_type(TokenType::And);
                         Ok(current_token)
                     } else {
-                        return Err(LexicalError::InvalidChar {
-                            character: ch,
-                            span: start_span,
-                        });
+                        self.advance();
+                        current_token.set_type(TokenType::BitAnd);
+                        Ok(current_token)
                     }
                 }
                 '|' => {
@@ -332,13 +473,23 @@ impl Lexer {
                         current_token.set_type(TokenType::Or);
                         Ok(current_token)
                     } else {
-                        return Err(LexicalError::InvalidChar {
-                            character: ch,
-                            span: start_span,
-                        });
+                        self.advance();
+                        current_token.set_type(TokenType::BitOr);
+                        Ok(current_token)
                     }
                 }
+                '^' => {
+                    self.advance();
+                    current_token.set_type(TokenType::Caret);
+                    Ok(current_token)
+                }
+                '~' => {
+                    self.advance();
+                    current_token.set_type(TokenType::Tilde);
+                    Ok(current_token)
+                }
                 '"' => self.read_string(),
+                '\'' => self.read_char(),
 
                 // numbers
                 ch if ch.is_digit(10) => self.read_number(),
@@ -375,10 +526,26 @@ impl Lexer {
                             current_token.set_type(TokenType::While);
                             Ok(current_token)
                         }
+                        "do" => {
+                            current_token.set_type(TokenType::Do);
+                            Ok(current_token)
+                        }
+                        "for" => {
+                            current_token.set_type(TokenType::For);
+                            Ok(current_token)
+                        }
+                        "in" => {
+                            current_token.set_type(TokenType::In);
+                            Ok(current_token)
+                        }
                         "break" => {
                             current_token.set_type(TokenType::Break);
                             Ok(current_token)
                         }
+                        "continue" => {
+                            current_token.set_type(TokenType::Continue);
+                            Ok(current_token)
+                        }
                         "return" => {
                             current_token.set_type(TokenType::Return);
                             Ok(current_token)
@@ -403,6 +570,10 @@ impl Lexer {
                             current_token.set_type(TokenType::False);
                             Ok(current_token)
                         }
+                        "nil" => {
+                            current_token.set_type(TokenType::Nil);
+                            Ok(current_token)
+                        }
                         "print" => {
                             current_token.set_type(TokenType::Print);
                             Ok(current_token)
@@ -411,6 +582,22 @@ impl Lexer {
                             current_token.set_type(TokenType::Println);
                             Ok(current_token)
                         }
+                        "assert" => {
+                            current_token.set_type(TokenType::Assert);
+                            Ok(current_token)
+                        }
+                        "len" => {
+                            current_token.set_type(TokenType::Len);
+                            Ok(current_token)
+                        }
+                        "typeName" => {
+                            current_token.set_type(TokenType::TypeNameOf);
+                            Ok(current_token)
+                        }
+                        "match" => {
+                            current_token.set_type(TokenType::Match);
+                            Ok(current_token)
+                        }
                         "const" => {
                             current_token.set_type(TokenType::Const);
                             Ok(current_token)
@@ -456,16 +643,22 @@ impl Lexer {
     }
 
     pub fn tokenize(&mut self) -> Result<Vec<Token>> {
-        let mut tokens = Vec::new();
-        loop {
-            let token = self.next_token()?;
-            let is_eof = token.token_type == TokenType::EOF;
-            tokens.push(token);
-            if is_eof {
-                break;
-            }
+        self.collect()
+    }
+}
+
+/// Yields tokens one at a time, ending after the `EOF` token or the first
+/// error.
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
         }
-        Ok(tokens)
+        let result = self.next_token();
+        self.finished = !matches!(&result, Ok(token) if token.token_type != TokenType::EOF);
+        Some(result)
     }
 }
 
@@ -475,8 +668,22 @@ mod tests {
     pub use error::LexicalError;
     use token::{Token, TokenType, TypeName};
 
-    fn create_token(token_type: TokenType, line: usize, column: usize) -> Token {
-        Token::new(token_type, Span::new(line, column))
+    fn create_token(token_type: TokenType, start: usize, end: usize, line: usize, column: usize) -> Token {
+        Token::new(token_type, Span::new(start, end, line, column))
+    }
+
+    #[test]
+    fn iterate_matches_tokenize() {
+        let source = "fun main() -> Void {\n  let s: Str = \"hi\"; // done\n  println(s, 'c', 1 ** 2);\n}";
+        let iterated: Vec<Token> = Lexer::new(source).map(|token| token.unwrap()).collect();
+        assert_eq!(iterated, Lexer::new(source).tokenize().unwrap());
+        assert_eq!(iterated.last().unwrap().token_type, TokenType::EOF);
+
+        // the iterator stops after an error instead of scanning on
+        let mut lexer = Lexer::new("let @x = 5;");
+        assert!(matches!(lexer.next(), Some(Ok(_))));
+        assert!(matches!(lexer.next(), Some(Err(LexicalError::InvalidChar { .. }))));
+        assert!(lexer.next().is_none());
     }
 
     #[test]
@@ -512,6 +719,19 @@ mod tests {
         assert_eq!(tokens[5].token_type, TokenType::EOF);
     }
 
+    #[test]
+    fn tokenize_power() {
+        let mut lexer = Lexer::new("2 ** 3 * * 4");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[0].token_type, TokenType::IntegerLiteral(2));
+        assert_eq!(tokens[1].token_type, TokenType::StarStar);
+        assert_eq!(tokens[2].token_type, TokenType::IntegerLiteral(3));
+        assert_eq!(tokens[3].token_type, TokenType::Star);
+        assert_eq!(tokens[4].token_type, TokenType::Star);
+        assert_eq!(tokens[5].token_type, TokenType::IntegerLiteral(4));
+        assert_eq!(tokens[6].token_type, TokenType::EOF);
+    }
+
     #[test]
     fn tokenize_boolean_ops() {
         let mut lexer = Lexer::new("< <= == > >= !");
@@ -527,7 +747,7 @@ mod tests {
 
     #[test]
     fn tokenize_keywords() {
-        let mut lexer = Lexer::new("class meth init extends this super while break return if else new true false println fun let");
+        let mut lexer = Lexer::new("class meth init extends this super while break return if else new true false println fun let do");
         let tokens = lexer.tokenize().unwrap();
         assert_eq!(tokens[0].token_type, TokenType::Class);
         assert_eq!(tokens[1].token_type, TokenType::Meth);
@@ -546,7 +766,49 @@ mod tests {
         assert_eq!(tokens[14].token_type, TokenType::Println);
         assert_eq!(tokens[15].token_type, TokenType::Fun);
         assert_eq!(tokens[16].token_type, TokenType::Let);
-        assert_eq!(tokens[17].token_type, TokenType::EOF);
+        assert_eq!(tokens[17].token_type, TokenType::Do);
+        assert_eq!(tokens[18].token_type, TokenType::EOF);
+    }
+
+    #[test]
+    fn tokenize_ranges() {
+        let tokens = Lexer::new("for (i in 0..n) a.b ..=").tokenize().unwrap();
+        let types: Vec<TokenType> = tokens.into_iter().map(|token| token.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::For,
+                TokenType::LeftParen,
+                TokenType::Identifier("i".to_string()),
+                TokenType::In,
+                TokenType::IntegerLiteral(0),
+                TokenType::DotDot,
+                TokenType::Identifier("n".to_string()),
+                TokenType::RightParen,
+                TokenType::Identifier("a".to_string()),
+                TokenType::Dot,
+                TokenType::Identifier("b".to_string()),
+                TokenType::DotDotEqual,
+                TokenType::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_nil() {
+        let mut lexer = Lexer::new("nil nile");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[0].token_type, TokenType::Nil);
+        assert_eq!(tokens[1].token_type, TokenType::Identifier("nile".to_string()));
+    }
+
+    #[test]
+    fn tokenize_continue() {
+        let mut lexer = Lexer::new("continue; continued");
+        let tokens = lexer.tokenize().unwrap();
+        assert_eq!(tokens[0].token_type, TokenType::Continue);
+        assert_eq!(tokens[1].token_type, TokenType::Semicolon);
+        assert_eq!(tokens[2].token_type, TokenType::Identifier("continued".to_string()));
     }
 
     #[test]
@@ -662,6 +924,15 @@ mod tests {
         assert_eq!(tokens[10].token_type, TokenType::EOF);
     }
 
+    #[test]
+    fn tokenize_kept_comments() {
+        let tokens = Lexer::with_comments("// note\nx /* a\n b */ y").tokenize().unwrap();
+        assert_eq!(tokens[0].token_type, TokenType::Comment("// note".to_string()));
+        assert_eq!(tokens[1].token_type, TokenType::Identifier("x".to_string()));
+        assert_eq!(tokens[2].token_type, TokenType::Comment("/* a\n b */".to_string()));
+        assert_eq!(tokens[3].token_type, TokenType::Identifier("y".to_string()));
+    }
+
     #[test]
     fn tokenize_string() {
         let mut lexer = Lexer::new("\"hello\"");
@@ -735,7 +1006,32 @@ mod tests {
         assert!(matches!(
             result,
             Err(LexicalError::UnterminatedString { span })
-            if span == Span::new(1, 8)
+            if span == Span::new(7, 7, 1, 8)
+        ));
+    }
+
+    #[test]
+    fn tokenize_char() {
+        let tokens = Lexer::new("'a' '\\n' '\\''").tokenize().unwrap();
+        assert_eq!(tokens[0], create_token(TokenType::CharLiteral('a'), 0, 3, 1, 1));
+        assert_eq!(tokens[1].token_type, TokenType::CharLiteral('\n'));
+        assert_eq!(tokens[2].token_type, TokenType::CharLiteral('\''));
+        assert_eq!(tokens[3].token_type, TokenType::EOF);
+    }
+
+    #[test]
+    fn tokenize_invalid_char() {
+        assert!(matches!(
+            Lexer::new("''").tokenize(),
+            Err(LexicalError::EmptyCharLiteral { span }) if span == Span::new(0, 0, 1, 1)
+        ));
+        assert!(matches!(
+            Lexer::new("x = 'ab';").tokenize(),
+            Err(LexicalError::MultiCharLiteral { value, .. }) if value == "ab"
+        ));
+        assert!(matches!(
+            Lexer::new("'a").tokenize(),
+            Err(LexicalError::UnterminatedChar { .. })
         ));
     }
 
@@ -746,10 +1042,42 @@ mod tests {
         assert!(matches!(
             result,
             Err(LexicalError::InvalidNumber { value, span })
-            if value == "123a" && span == Span::new(2, 14)
+            if value == "123a" && span == Span::new(21, 21, 2, 14)
         ));
     }
 
+    #[test]
+    fn tokenize_exponent_is_invalid_number() {
+        // there are no float literals yet, so an exponent is never valid,
+        // whether or not its digits follow
+        for (input, value) in [("1e3", "1e"), ("1e", "1e"), ("1e+", "1e"), ("6E-4", "6E")] {
+            assert!(
+                matches!(
+                    Lexer::new(input).tokenize(),
+                    Err(LexicalError::InvalidNumber { value: found, .. }) if found == value
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn tokenize_integer_overflow() {
+        let result = Lexer::new("let big: Int = 99999999999999999999;").tokenize();
+        assert!(matches!(
+            result,
+            Err(LexicalError::IntegerOverflow { value, span })
+            if value == "99999999999999999999" && span == Span::new(15, 15, 1, 16)
+        ));
+
+        let tokens = Lexer::new("9223372036854775807").tokenize().unwrap();
+        assert_eq!(tokens[0].token_type, TokenType::IntegerLiteral(i64::MAX));
+
+        let tokens = Lexer::new("-9223372036854775808").tokenize().unwrap();
+        assert_eq!(tokens[1].token_type, TokenType::IntegerLiteral(i64::MIN));
+        assert!(Lexer::new("9223372036854775808").tokenize().is_err());
+    }
+
     #[test]
     fn tokenize_invalid_character() {
         let mut lexer = Lexer::new("13 * 2 \nlet $ = 123");
@@ -757,7 +1085,7 @@ mod tests {
         assert!(matches!(
             result,
             Err(LexicalError::InvalidChar { character, span })
-            if character == '$' && span == Span::new(2, 5)
+            if character == '$' && span == Span::new(12, 12, 2, 5)
         ));
     }
 
@@ -768,10 +1096,52 @@ mod tests {
         assert!(matches!(
             result,
             Err(LexicalError::InvalidEscapeSequence { escape, span })
-            if escape == 'w' && span == Span::new(1, 8)
+            if escape == 'w' && span == Span::new(7, 7, 1, 8)
         ));
     }
 
+    #[test]
+    fn tokenize_escapes_agree_in_strings_and_chars() {
+        let escapes = [
+            (r"\n", '\n'),
+            (r"\t", '\t'),
+            (r"\r", '\r'),
+            (r#"\""#, '"'),
+            (r"\'", '\''),
+            (r"\\", '\\'),
+            (r"\0", '\0'),
+            (r"\x41", 'A'),
+            (r"\x7f", '\x7f'),
+            (r"\u{e9}", 'é'),
+            (r"\u{1F600}", '😀'),
+        ];
+        for (escape, expected) in escapes {
+            let tokens = Lexer::new(&format!("\"{escape}\" '{escape}'")).tokenize().unwrap();
+            assert_eq!(tokens[0].token_type, TokenType::StringLiteral(expected.to_string()), "{escape}");
+            assert_eq!(tokens[1].token_type, TokenType::CharLiteral(expected), "{escape}");
+        }
+    }
+
+    #[test]
+    fn tokenize_invalid_numeric_escape() {
+        for (input, escape) in [
+            (r#""\x4""#, 'x'),
+            (r"'\xg1'", 'x'),
+            (r#""\u41""#, 'u'),
+            (r"'\u{}'", 'u'),
+            (r#""\u{1234567}""#, 'u'),
+            (r"'\u{D800}'", 'u'),
+        ] {
+            assert!(
+                matches!(
+                    Lexer::new(input).tokenize(),
+                    Err(LexicalError::InvalidEscapeSequence { escape: found, .. }) if found == escape
+                ),
+                "{input}"
+            );
+        }
+    }
+
     #[test]
     fn tokenize_unexpected_eof() {
         let mut lexer = Lexer::new("string \"Hello \\");
@@ -779,10 +1149,38 @@ mod tests {
         assert!(matches!(
             result,
             Err(LexicalError::UnexpectedEOF { span })
-            if span == Span::new(1, 8)
+            if span == Span::new(7, 7, 1, 8)
         ));
     }
 
+    #[test]
+    fn test_bitwise_operators() {
+        let tokens = Lexer::new("a & b | c ^ ~d << 1 >> 2 && || <= >=").tokenize().unwrap();
+        let types: Vec<TokenType> = tokens.into_iter().map(|token| token.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Identifier("a".to_string()),
+                TokenType::BitAnd,
+                TokenType::Identifier("b".to_string()),
+                TokenType::BitOr,
+                TokenType::Identifier("c".to_string()),
+                TokenType::Caret,
+                TokenType::Tilde,
+                TokenType::Identifier("d".to_string()),
+                TokenType::Shl,
+                TokenType::IntegerLiteral(1),
+                TokenType::Shr,
+                TokenType::IntegerLiteral(2),
+                TokenType::And,
+                TokenType::Or,
+                TokenType::LessEqual,
+                TokenType::GreaterEqual,
+                TokenType::EOF,
+            ]
+        );
+    }
+
     #[test]
     fn test_integers() {
         let src = "let myNumber: Int = 123;";
@@ -917,4 +1315,54 @@ mod tests {
         assert_eq!(tokens[8].token_type, TokenType::Semicolon);
         assert_eq!(tokens[9].token_type, TokenType::EOF);
     }
+
+    #[test]
+    fn test_token_byte_offsets() {
+        let src = "let é = \"hi\";\nx";
+        let tokens = Lexer::new(src).tokenize().unwrap();
+
+        assert_eq!(tokens[0], create_token(TokenType::Let, 0, 3, 1, 1));
+        assert_eq!(tokens[1], create_token(TokenType::Identifier("é".to_string()), 4, 6, 1, 5));
+        assert_eq!(tokens[3], create_token(TokenType::StringLiteral("hi".to_string()), 9, 13, 1, 9));
+        assert_eq!(tokens[5], create_token(TokenType::Identifier("x".to_string()), 15, 16, 2, 1));
+        assert_eq!(&src[tokens[3].span.start..tokens[3].span.end], "\"hi\"");
+    }
+
+    #[test]
+    fn test_multibyte_source() {
+        let src = "/* ü */ print(\"日本\"); // 🔥";
+        let tokens = Lexer::new(src).tokenize().unwrap();
+
+        assert_eq!(tokens[0], create_token(TokenType::Print, 9, 14, 1, 9));
+        assert_eq!(tokens[2].token_type, TokenType::StringLiteral("日本".to_string()));
+        assert_eq!(&src[tokens[2].span.start..tokens[2].span.end], "\"日本\"");
+        assert_eq!(tokens[5].token_type, TokenType::EOF);
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let src = "let x: Int = 1;\r\n  x = 2;\r\n";
+        let tokens = Lexer::new(src).tokenize().unwrap();
+
+        assert_eq!(tokens[7], create_token(TokenType::Identifier("x".to_string()), 19, 20, 2, 3));
+        assert_eq!(tokens[8], create_token(TokenType::Assign, 21, 22, 2, 5));
+        assert_eq!(tokens[11].token_type, TokenType::EOF);
+        assert_eq!((tokens[11].span.line, tokens[11].span.column), (3, 1));
+    }
+
+    #[test]
+    fn test_shebang_line() {
+        let body = "let x: Int = 1;\nprintln(x);";
+        let types = |src: &str| -> Vec<TokenType> {
+            Lexer::new(src).tokenize().unwrap().into_iter().map(|t| t.token_type).collect()
+        };
+        let with_shebang = format!("#!/usr/bin/env lavascript\n{}", body);
+        assert_eq!(types(&with_shebang), types(body));
+
+        let tokens = Lexer::new(&with_shebang).tokenize().unwrap();
+        assert_eq!(tokens[0], create_token(TokenType::Let, 26, 29, 2, 1));
+
+        // only the first line may be a shebang
+        assert!(Lexer::new(" #!/usr/bin/env lavascript").tokenize().is_err());
+    }
 }
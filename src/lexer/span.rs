@@ -1,14 +1,39 @@
 use std::fmt;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Location of a piece of source text. `start`/`end` are byte offsets into the
+/// source (end exclusive); `line`/`column` are 1-based and point at `start`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Span {
+    pub start: usize,
+    pub end: usize,
     pub line: usize,
     pub column: usize,
 }
 
 impl Span {
-    pub fn new(line: usize, column: usize) -> Self {
-        Self { line, column }
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An empty span just past the end of this one, for pointing at where
+    /// something that should follow it is missing. Assumes the span doesn't
+    /// cross a line break.
+    pub fn after(&self) -> Span {
+        Span::new(self.end, self.end, self.line, self.column + self.len())
     }
 }
 
@@ -13,15 +13,24 @@ pub enum TokenType {
     This,
     Super,
     While,
+    Do,
+    For,
+    In,
     Break,
+    Continue,
     Return,
     If,
     Else,
     New,
     True,
     False,
+    Nil,
     Print,
     Println,
+    Assert,
+    Len,
+    TypeNameOf,
+    Match,
     Const,
     Fun,
 
@@ -32,13 +41,20 @@ pub enum TokenType {
     Identifier(String),
     IntegerLiteral(i64),
     StringLiteral(String),
+    CharLiteral(char),
+
+    /// A `//` or `/* */` comment, verbatim. Only produced by
+    /// [`Lexer::with_comments`](crate::lexer::Lexer::with_comments).
+    Comment(String),
 
     // operators
     Plus,
     Minus,
     Star,
+    StarStar,
     Slash,
     Assign,
+    FatArrow,
 
     // boolean_operators
     Greater,
@@ -53,6 +69,14 @@ pub enum TokenType {
     Or,
     And,
 
+    // bitwise ops
+    BitAnd,
+    BitOr,
+    Caret,
+    Tilde,
+    Shl,
+    Shr,
+
     // punctuation
     LeftParen,
     RightParen,
@@ -62,6 +86,8 @@ pub enum TokenType {
     Colon,
     Comma,
     Dot,
+    DotDot,
+    DotDotEqual,
     LeftBracket,
     RightBracket,
 
@@ -71,11 +97,49 @@ pub enum TokenType {
 }
 
 impl TokenType {
+    /// Whether this token is a reserved word, i.e. something that lexes like an
+    /// identifier but can't be used as one.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Let
+                | TokenType::Class
+                | TokenType::Meth
+                | TokenType::Init
+                | TokenType::Extends
+                | TokenType::This
+                | TokenType::Super
+                | TokenType::While
+                | TokenType::Do
+                | TokenType::For
+                | TokenType::In
+                | TokenType::Break
+                | TokenType::Continue
+                | TokenType::Return
+                | TokenType::If
+                | TokenType::Else
+                | TokenType::New
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Nil
+                | TokenType::Print
+                | TokenType::Println
+                | TokenType::Assert
+                | TokenType::Len
+                | TokenType::TypeNameOf
+                | TokenType::Match
+                | TokenType::Const
+                | TokenType::Fun
+                | TokenType::Type(_)
+        )
+    }
+
     pub fn which_binary_op(&self) -> BinaryOp {
         match self {
             TokenType::Plus => BinaryOp::Add,
             TokenType::Minus => BinaryOp::Subtract,
             TokenType::Star => BinaryOp::Multiply,
+            TokenType::StarStar => BinaryOp::Power,
             TokenType::Slash => BinaryOp::Divide,
             TokenType::Equal => BinaryOp::Equal,
             TokenType::NotEqual => BinaryOp::NotEqual,
@@ -85,6 +149,11 @@ impl TokenType {
             TokenType::LessEqual => BinaryOp::LessEqual,
             TokenType::And => BinaryOp::And,
             TokenType::Or => BinaryOp::Or,
+            TokenType::BitAnd => BinaryOp::BitAnd,
+            TokenType::BitOr => BinaryOp::BitOr,
+            TokenType::Caret => BinaryOp::BitXor,
+            TokenType::Shl => BinaryOp::ShiftLeft,
+            TokenType::Shr => BinaryOp::ShiftRight,
             _ => unreachable!(),
         }
     }
@@ -103,15 +172,24 @@ impl fmt::Display for TokenType {
             TokenType::This => write!(f, "this"),
             TokenType::Super => write!(f, "super"),
             TokenType::While => write!(f, "while"),
+            TokenType::Do => write!(f, "do"),
+            TokenType::For => write!(f, "for"),
+            TokenType::In => write!(f, "in"),
             TokenType::Break => write!(f, "break"),
+            TokenType::Continue => write!(f, "continue"),
             TokenType::Return => write!(f, "return"),
             TokenType::If => write!(f, "if"),
             TokenType::Else => write!(f, "else"),
             TokenType::New => write!(f, "new"),
             TokenType::True => write!(f, "true"),
             TokenType::False => write!(f, "false"),
+            TokenType::Nil => write!(f, "nil"),
             TokenType::Print => write!(f, "print"),
             TokenType::Println => write!(f, "println"),
+            TokenType::Assert => write!(f, "assert"),
+            TokenType::Len => write!(f, "len"),
+            TokenType::TypeNameOf => write!(f, "typeName"),
+            TokenType::Match => write!(f, "match"),
             TokenType::Const => write!(f, "const"),
             TokenType::Fun => write!(f, "fun"),
 
@@ -122,13 +200,17 @@ impl fmt::Display for TokenType {
             TokenType::Identifier(s) => write!(f, "Identifier({})", s),
             TokenType::IntegerLiteral(i) => write!(f, "IntegerLiteral({})", i),
             TokenType::StringLiteral(s) => write!(f, "StringLiteral({})", s),
+            TokenType::CharLiteral(c) => write!(f, "CharLiteral({})", c),
+            TokenType::Comment(s) => write!(f, "Comment({})", s),
 
             // operators
             TokenType::Plus => write!(f, "+"),
             TokenType::Minus => write!(f, "-"),
             TokenType::Star => write!(f, "*"),
+            TokenType::StarStar => write!(f, "**"),
             TokenType::Slash => write!(f, "/"),
             TokenType::Assign => write!(f, "="),
+            TokenType::FatArrow => write!(f, "=>"),
 
             // boolean operators
             TokenType::Greater => write!(f, ">"),
@@ -143,6 +225,14 @@ impl fmt::Display for TokenType {
             TokenType::Or => write!(f, "||"),
             TokenType::And => write!(f, "&&"),
 
+            // bitwise operators
+            TokenType::BitAnd => write!(f, "&"),
+            TokenType::BitOr => write!(f, "|"),
+            TokenType::Caret => write!(f, "^"),
+            TokenType::Tilde => write!(f, "~"),
+            TokenType::Shl => write!(f, "<<"),
+            TokenType::Shr => write!(f, ">>"),
+
             // punctuation
             TokenType::LeftParen => write!(f, "("),
             TokenType::RightParen => write!(f, ")"),
@@ -152,16 +242,18 @@ impl fmt::Display for TokenType {
             TokenType::Colon => write!(f, ":"),
             TokenType::Comma => write!(f, ","),
             TokenType::Dot => write!(f, "."),
+            TokenType::DotDot => write!(f, ".."),
+            TokenType::DotDotEqual => write!(f, "..="),
             TokenType::LeftBracket => write!(f, "["),
             TokenType::RightBracket => write!(f, "]"),
 
             // special
-            TokenType::EOF => write!(f, "EOF"),
+            TokenType::EOF => write!(f, "end of file"),
         }
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
     pub span: Span,
@@ -183,6 +275,8 @@ impl Token {
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum TypeName {
     Int,
     Str,
@@ -190,6 +284,11 @@ pub enum TypeName {
     #[default]
     Void,
     Class(String),
+    /// A function value, written `(Int, Str) -> Int`.
+    Function {
+        params: Vec<TypeName>,
+        ret: Box<TypeName>,
+    },
 }
 
 impl fmt::Display for TypeName {
@@ -200,6 +299,10 @@ impl fmt::Display for TypeName {
             TypeName::Boolean => write!(f, "Boolean"),
             TypeName::Void => write!(f, "Void"),
             TypeName::Class(name) => write!(f, "{}", name),
+            TypeName::Function { params, ret } => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "({}) -> {}", params.join(", "), ret)
+            }
         }
     }
 }
@@ -210,6 +313,7 @@ fn test_all_bin_ops() {
     assert_eq!(Plus.which_binary_op(), BinaryOp::Add);
     assert_eq!(Minus.which_binary_op(), BinaryOp::Subtract);
     assert_eq!(Star.which_binary_op(), BinaryOp::Multiply);
+    assert_eq!(StarStar.which_binary_op(), BinaryOp::Power);
     assert_eq!(Slash.which_binary_op(), BinaryOp::Divide);
     assert_eq!(Equal.which_binary_op(), BinaryOp::Equal);
     assert_eq!(NotEqual.which_binary_op(), BinaryOp::NotEqual);
@@ -219,6 +323,11 @@ fn test_all_bin_ops() {
     assert_eq!(LessEqual.which_binary_op(), BinaryOp::LessEqual);
     assert_eq!(And.which_binary_op(), BinaryOp::And);
     assert_eq!(Or.which_binary_op(), BinaryOp::Or);
+    assert_eq!(BitAnd.which_binary_op(), BinaryOp::BitAnd);
+    assert_eq!(BitOr.which_binary_op(), BinaryOp::BitOr);
+    assert_eq!(Caret.which_binary_op(), BinaryOp::BitXor);
+    assert_eq!(Shl.which_binary_op(), BinaryOp::ShiftLeft);
+    assert_eq!(Shr.which_binary_op(), BinaryOp::ShiftRight);
 }
 
 
@@ -247,6 +356,11 @@ fn test_type_name_display() {
     assert_eq!(TypeName::Boolean.to_string(), "Boolean");
     assert_eq!(TypeName::Void.to_string(), "Void");
     assert_eq!(TypeName::Class("MyClass".to_string()).to_string(), "MyClass");
+    let function = TypeName::Function {
+        params: vec![TypeName::Int, TypeName::Str],
+        ret: Box::new(TypeName::Boolean),
+    };
+    assert_eq!(function.to_string(), "(Int, Str) -> Boolean");
 }
 
 #[test]
@@ -261,26 +375,38 @@ fn test_all_token_types() {
         TokenType::This,
         TokenType::Super,
         TokenType::While,
+        TokenType::Do,
+        TokenType::For,
+        TokenType::In,
         TokenType::Break,
+        TokenType::Continue,
         TokenType::Return,
         TokenType::If,
         TokenType::Else,
         TokenType::New,
         TokenType::True,
         TokenType::False,
+        TokenType::Nil,
         TokenType::Print,
         TokenType::Println,
+        TokenType::Assert,
+        TokenType::Len,
+        TokenType::TypeNameOf,
+        TokenType::Match,
         TokenType::Const,
         TokenType::Fun,
         TokenType::Type(TypeName::Int),
         TokenType::Identifier("id".into()),
         TokenType::IntegerLiteral(42),
         TokenType::StringLiteral("string".into()),
+        TokenType::CharLiteral('c'),
         TokenType::Plus,
         TokenType::Minus,
         TokenType::Star,
+        TokenType::StarStar,
         TokenType::Slash,
         TokenType::Assign,
+        TokenType::FatArrow,
         TokenType::Greater,
         TokenType::GreaterEqual,
         TokenType::Less,
@@ -290,6 +416,12 @@ fn test_all_token_types() {
         TokenType::Not,
         TokenType::Or,
         TokenType::And,
+        TokenType::BitAnd,
+        TokenType::BitOr,
+        TokenType::Caret,
+        TokenType::Tilde,
+        TokenType::Shl,
+        TokenType::Shr,
         TokenType::LeftParen,
         TokenType::RightParen,
         TokenType::LeftBrace,
@@ -298,6 +430,8 @@ fn test_all_token_types() {
         TokenType::Colon,
         TokenType::Comma,
         TokenType::Dot,
+        TokenType::DotDot,
+        TokenType::DotDotEqual,
         TokenType::LeftBracket,
         TokenType::RightBracket,
         TokenType::EOF,
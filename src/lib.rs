@@ -0,0 +1,219 @@
+#![allow(dead_code)]
+pub mod ast;
+pub mod codegen;
+pub mod error;
+pub mod format;
+pub mod lexer;
+pub mod optimize;
+pub mod parser;
+pub mod sema;
+
+pub use error::CompileError;
+
+use codegen::{CodeGenOptions, CodeGenerator};
+use lexer::Lexer;
+use parser::Parser;
+use sema::{Analyzer, SemanticWarning};
+
+/// Output of a successful compilation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compiled {
+    pub code: String,
+    pub warnings: Vec<SemanticWarning>,
+}
+
+/// Stack size for [`with_stack`]. Parsing and every pass after it recurse
+/// once per level of the tree, so the parser's nesting limits are sized for
+/// this rather than for a default thread.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Runs `f` on a thread with a [`STACK_SIZE`] stack and waits for its result.
+pub fn with_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("failed to spawn the compiler thread")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// Runs the full lexer → parser → semantic analysis → codegen pipeline over `source` and returns
+/// the generated JavaScript.
+pub fn compile_str(source: &str) -> Result<String, CompileError> {
+    compile(source).map(|compiled| compiled.code)
+}
+
+/// Like [`compile_str`], but also hands back any warnings found along the way.
+pub fn compile(source: &str) -> Result<Compiled, CompileError> {
+    with_stack(|| {
+        let (ast, analyzer) = check(source)?;
+        Ok(Compiled {
+            code: CodeGenerator::new(ast)
+                .with_type_names(analyzer.get_type_names().clone())
+                .generate(),
+            warnings: analyzer.get_warnings().to_vec(),
+        })
+    })
+}
+
+/// Like [`compile`], but also returns a version 3 source map for the
+/// generated code that names `source_name` as its source.
+pub fn compile_with_sourcemap(source: &str, source_name: &str) -> Result<(Compiled, String), CompileError> {
+    with_stack(|| {
+        let (ast, analyzer) = check(source)?;
+        let options = CodeGenOptions {
+            source_name: Some(source_name.to_string()),
+            ..CodeGenOptions::default()
+        };
+        let (code, map) = CodeGenerator::with_options(ast, options)
+            .with_type_names(analyzer.get_type_names().clone())
+            .generate_with_sourcemap();
+        let warnings = analyzer.get_warnings().to_vec();
+        Ok((Compiled { code, warnings }, map))
+    })
+}
+
+/// Runs everything before codegen: lexing, parsing and semantic analysis.
+/// Hands back the analyzer too, for the warnings and types it found.
+fn check(source: &str) -> Result<(ast::Entry, Analyzer), CompileError> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokenize()?;
+
+    let mut parser = Parser::new(tokens);
+    let ast = parser.parse();
+    if parser.has_errors() {
+        return Err(CompileError::Parse(parser.get_errors().to_vec()));
+    }
+
+    let ast = ast.ok_or_else(|| CompileError::Parse(vec![]))?;
+
+    let mut analyzer = Analyzer::new();
+    analyzer.analyze(&ast);
+    if analyzer.has_errors() {
+        return Err(CompileError::Semantic(analyzer.get_errors().to_vec()));
+    }
+
+    Ok((ast, analyzer))
+}
+
+/// Serializes a parsed program as pretty-printed JSON, for tooling and golden
+/// tests. Enum nodes are written as `{ "kind": ..., "value": ... }`.
+#[cfg(feature = "serde")]
+pub fn ast_to_json(entry: &ast::Entry) -> String {
+    serde_json::to_string_pretty(entry).expect("AST always serializes to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::LexicalError;
+    use crate::parser::ParseError;
+    use crate::sema::SemanticError;
+
+    #[test]
+    fn test_compile_str() {
+        let code = compile_str("let x: Int = 5;").unwrap();
+        assert_eq!(code.trim(), "let x = 5;");
+    }
+
+    #[test]
+    fn test_compile_string_concatenation() {
+        let code = compile_str(r#"let s: Str = "n=" + 5;"#).unwrap();
+        assert_eq!(code.trim(), r#"let s = "n=" + 5;"#);
+    }
+
+    #[test]
+    fn test_compile_str_lexical_error() {
+        let result = compile_str("let @x = 5;");
+        assert!(matches!(
+            result,
+            Err(CompileError::Lexical(LexicalError::InvalidChar { character: '@', .. }))
+        ));
+    }
+
+    #[test]
+    fn test_compile_str_parse_error() {
+        let result = compile_str("let x: Int = ;");
+        assert!(matches!(
+            result,
+            Err(CompileError::Parse(errors))
+            if errors.iter().any(|e| matches!(e, ParseError::ExpectedButFound { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_compile_str_at_the_nesting_limits() {
+        // every pass over the tree recurses, so the deepest input the parser
+        // accepts has to make it through all of them
+        let chain = vec!["1"; crate::parser::DEFAULT_MAX_CHAIN].join(" + ");
+        let unary = "-".repeat(crate::parser::DEFAULT_MAX_DEPTH - 1);
+        let code = compile_str(&format!("let x: Int = {unary}({chain});")).unwrap();
+        assert!(code.trim_start().starts_with("let x = -(-("));
+
+        let parens = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+        let concat = vec![r#""a""#; 1000].join(" + ");
+        assert!(compile_str(&format!("let x: Int = {parens}; let s: Str = {concat};")).is_ok());
+    }
+
+    #[test]
+    fn test_compile_str_semantic_error() {
+        let result = compile_str("println(y);");
+        assert!(matches!(
+            result,
+            Err(CompileError::Semantic(errors))
+            if matches!(errors.as_slice(), [SemanticError::UndefinedVariable { .. }])
+        ));
+    }
+
+    #[test]
+    fn test_numeric_literals_stay_integers() {
+        // there is no Float type yet: integers print as written and a
+        // fractional literal can't reach an `Int`
+        assert_eq!(compile_str("let x: Int = 5;").unwrap().trim(), "let x = 5;");
+        assert!(matches!(compile_str("let x: Int = 1.5;"), Err(CompileError::Parse(_))));
+    }
+
+    #[test]
+    fn test_type_name_is_resolved_at_compile_time() {
+        assert_eq!(compile_str("println(typeName(5));").unwrap().trim(), r#"console.log("Int");"#);
+        assert_eq!(
+            compile_str(r#"println(typeName("s"), typeName(true), typeName(1 < 2));"#).unwrap().trim(),
+            r#"console.log("Str", "Boolean", "Boolean");"#
+        );
+
+        let code = compile_str("class Cat { init() {} } let c: Cat = new Cat(); let t: Str = typeName(c);").unwrap();
+        assert!(code.contains(r#"let t = "Cat";"#), "{}", code);
+
+        // the argument is never evaluated, so its side effects don't happen
+        let code = compile_str("fun f() -> Int { println(1); return 1; } println(typeName(f()));").unwrap();
+        assert!(code.ends_with(r#"console.log("Int");"#), "{}", code);
+    }
+
+    #[test]
+    fn test_compile_reports_warnings() {
+        let compiled = compile("while (true) { break; println(1); }").unwrap();
+        assert!(matches!(
+            compiled.warnings.as_slice(),
+            [SemanticWarning::UnreachableCode { .. }]
+        ));
+        assert!(compiled.code.contains("console.log(1);"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_ast_to_json() {
+        let tokens = Lexer::new("let x: Int = 5;").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&ast_to_json(&ast)).unwrap();
+
+        let stmt = &json["statements"][0];
+        assert_eq!(stmt["kind"], "VarDeclWithAssign");
+        assert_eq!(stmt["value"]["name"], "x");
+        assert_eq!(stmt["value"]["var_type"]["kind"], "Int");
+        assert_eq!(stmt["value"]["expr"]["kind"], "IntegerLiteral");
+        assert_eq!(stmt["value"]["expr"]["value"]["value"], 5);
+        assert_eq!(stmt["value"]["span"]["line"], 1);
+    }
+}
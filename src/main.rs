@@ -1,48 +1,285 @@
-#![allow(dead_code)]
-mod ast;
-mod lexer;
-mod parser;
-mod codegen;
-
-use codegen::CodeGenerator;
-use lexer::Lexer;
-use parser::Parser;
+use lavascript::ast::PrettyPrint;
+use lavascript::{compile as compile_source, compile_with_sourcemap};
+use lavascript::format::format_program;
+use lavascript::sema::print_warnings;
+use lavascript::lexer::Lexer;
+use lavascript::parser::Parser;
 use std::env;
+use std::ffi::OsStr;
 use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
 use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 || args.len() > 3 {
-        eprintln!("Usage: {} <source_file> <optional_output_file>", args[0]);
-        process::exit(1);
+const USAGE: &str = "Usage: lavascript <source_file> [output_file] [--emit=js|tokens|ast|fmt] [--sourcemap] [--watch] [--diagnostics=human|json] [--no-color]
+
+Pass - as the source file to read from stdin; without an output file the
+JavaScript is then written to stdout.
+
+Options:
+  --emit=js       generate JavaScript into the output file (default)
+  --emit=tokens   print the token stream produced by the lexer
+  --emit=ast      print the parsed syntax tree
+  --emit=fmt      print the source reformatted in canonical style, with
+                  classes first, then functions, then top-level statements
+  --sourcemap     also write a source map next to the generated JavaScript
+  --watch         rebuild whenever the source file changes, until interrupted
+  --diagnostics=json
+                  report compile errors and warnings on stdout as one JSON
+                  object per line, with code, message, line, column and severity
+  --no-color      disable colored output (also honors the NO_COLOR env var)";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Emit {
+    #[default]
+    Js,
+    Tokens,
+    Ast,
+    Fmt,
+}
+
+/// How compile errors and warnings are reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Diagnostics {
+    /// Colored, with the offending source lines, on stderr.
+    #[default]
+    Human,
+    /// One JSON object per line on stdout, for editors.
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    source: String,
+    output: Option<String>,
+    emit: Emit,
+    sourcemap: bool,
+    watch: bool,
+    diagnostics: Diagnostics,
+    no_color: bool,
+}
+
+/// The source path that means "read the program from stdin".
+const STDIN: &str = "-";
+
+/// How often `--watch` checks the source file's modification time.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut emit = Emit::default();
+    let mut sourcemap = false;
+    let mut watch = false;
+    let mut diagnostics = Diagnostics::default();
+    let mut no_color = false;
+
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--emit=") {
+            emit = match value {
+                "js" => Emit::Js,
+                "tokens" => Emit::Tokens,
+                "ast" => Emit::Ast,
+                "fmt" => Emit::Fmt,
+                _ => {
+                    return Err(format!(
+                        "unknown --emit value '{}' (expected js, tokens, ast or fmt)",
+                        value
+                    ))
+                }
+            };
+        } else if let Some(value) = arg.strip_prefix("--diagnostics=") {
+            diagnostics = match value {
+                "human" => Diagnostics::Human,
+                "json" => Diagnostics::Json,
+                _ => {
+                    return Err(format!(
+                        "unknown --diagnostics value '{}' (expected human or json)",
+                        value
+                    ))
+                }
+            };
+        } else if arg == "--sourcemap" {
+            sourcemap = true;
+        } else if arg == "--watch" {
+            watch = true;
+        } else if arg == "--no-color" {
+            no_color = true;
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option '{}'", arg));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    if positional.is_empty() || positional.len() > 2 {
+        return Err("expected a source file and an optional output file".to_string());
     }
 
-    let source_path = &args[1];
-    let source = match fs::read_to_string(source_path) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Error reading file {}: {}", source_path, e);
+    let mut positional = positional.into_iter();
+    let source = positional.next().unwrap_or_default();
+    let output = positional.next();
+    if source == STDIN {
+        if watch {
+            return Err("--watch needs a source file, not stdin".to_string());
+        }
+        if sourcemap && output.is_none() {
+            return Err("--sourcemap needs an output file when reading from stdin".to_string());
+        }
+        if diagnostics == Diagnostics::Json && output.is_none() {
+            return Err("--diagnostics=json needs an output file when reading from stdin".to_string());
+        }
+    }
+
+    Ok(Args {
+        source,
+        output,
+        emit,
+        sourcemap,
+        watch,
+        diagnostics,
+        no_color,
+    })
+}
+
+/// Colors are on unless `--no-color` was passed or `no_color_env`, the value
+/// of `NO_COLOR`, is set and non-empty (see https://no-color.org).
+fn color_enabled(args: &Args, no_color_env: Option<&OsStr>) -> bool {
+    let no_color_env = no_color_env.is_some_and(|value| !value.is_empty());
+    !(args.no_color || no_color_env)
+}
+
+fn main() {
+    // the emit modes run the parser themselves, so all of it gets the stack
+    // the nesting limits were sized for
+    lavascript::with_stack(run)
+}
+
+fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
             process::exit(1);
         }
     };
 
-    let output =if args.len() == 3 {
-        &args[2]
+    if !color_enabled(&args, env::var_os("NO_COLOR").as_deref()) {
+        colored::control::set_override(false);
+    }
+
+    if args.watch {
+        watch(&args);
+    }
+
+    if let Err(message) = build(&args) {
+        eprintln!("{}", message);
+        process::exit(1);
     }
-    else {
-        let charlength = &args[1].len();
-        &[&source_path[0..(charlength-4)],"js"].join("")
+}
+
+/// Reads the source file once and emits whatever `args` asked for.
+/// Diagnostics are printed rather than returned; only a file that can't be
+/// read is an error.
+fn build(args: &Args) -> Result<(), String> {
+    let source = if args.source == STDIN {
+        read_source(io::stdin().lock()).map_err(|e| format!("Error reading stdin: {}", e))?
+    } else {
+        fs::read_to_string(&args.source).map_err(|e| format!("Error reading file {}: {}", args.source, e))?
     };
 
-    println!("{}",output);
+    match args.emit {
+        Emit::Js => {
+            // stdin has no file name to derive an output path from
+            let output = args
+                .output
+                .clone()
+                .or_else(|| (args.source != STDIN).then(|| default_output_path(&args.source)));
+
+            match output {
+                Some(output) => {
+                    if args.diagnostics == Diagnostics::Human {
+                        println!("{}", output);
+                    }
+                    let source_name = args.sourcemap.then_some(args.source.as_str());
+                    compile(&source, &output, source_name, args.diagnostics);
+                }
+                None => compile_to_stdout(&source),
+            }
+        }
+        Emit::Tokens => emit_tokens(&source),
+        Emit::Ast => emit_ast(&source),
+        Emit::Fmt => emit_fmt(&source),
+    }
+    Ok(())
+}
+
+fn read_source(mut reader: impl Read) -> io::Result<String> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+    Ok(source)
+}
 
-    compile(&source, &output);
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Builds once, then polls the source file and rebuilds each time its
+/// modification time changes. Failed builds are reported and the watcher
+/// keeps going.
+fn watch(args: &Args) -> ! {
+    let mut last_modified = modified_time(&args.source);
+    loop {
+        if let Err(message) = build(args) {
+            eprintln!("{}", message);
+        }
+        println!("Watching {} for changes...", args.source);
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let modified = modified_time(&args.source);
+            if modified != last_modified {
+                last_modified = modified;
+                break;
+            }
+        }
+    }
+}
+
+fn emit_tokens(source: &str) {
+    match Lexer::new(source).tokenize() {
+        Ok(tokens) => {
+            for token in tokens {
+                println!("{:?}", token);
+            }
+        }
+        Err(error) => eprintln!("Lexical error: {}", error),
+    }
+}
+
+fn emit_ast(source: &str) {
+    let tokens = match Lexer::new(source).tokenize() {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            eprintln!("Lexical error: {}", error);
+            return;
+        }
+    };
+
+    let mut parser = Parser::new(tokens);
+    let ast = parser.parse();
+    if parser.has_errors() {
+        parser.print_errors(source);
+    }
+    if let Some(ast) = ast {
+        ast.print();
+    }
 }
 
-fn compile(source: &str, output: &str) {
-    let mut lexer = Lexer::new(source);
-    let tokens = match lexer.tokenize() {
+fn emit_fmt(source: &str) {
+    let tokens = match Lexer::with_comments(source).tokenize() {
         Ok(tokens) => tokens,
         Err(error) => {
             eprintln!("Lexical error: {}", error);
@@ -50,22 +287,84 @@ fn compile(source: &str, output: &str) {
         }
     };
 
+    // refuse to format a file that doesn't parse, rather than print a mangled copy
     let mut parser = Parser::new(tokens);
-    let ast = match parser.parse() {
-        Some(ast) => {
-            if parser.has_errors() {
-                parser.print_errors(source);
+    let ast = parser.parse();
+    if parser.has_errors() {
+        parser.print_errors(source);
+        return;
+    }
+    // only doc comments have a place in the AST; dropping any other comment
+    // would lose part of the file
+    if let Some(span) = parser.dropped_comments.first() {
+        eprintln!(
+            "Cannot format: the comment at {} would be lost. Only comments directly above a class, fun or meth are kept.",
+            span
+        );
+        return;
+    }
+    if let Some(ast) = ast {
+        print!("{}", format_program(&ast));
+    }
+}
+
+fn default_output_path(source_path: &str) -> String {
+    Path::new(source_path)
+        .with_extension("js")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Compiles `source` into `output`. With a `source_name`, also writes a
+/// source map to `<output>.map` and links it from the generated code.
+/// With JSON diagnostics, stdout carries nothing but the diagnostics.
+fn compile(source: &str, output: &str, source_name: Option<&str>, diagnostics: Diagnostics) {
+    let result = match source_name {
+        Some(source_name) => compile_with_sourcemap(source, source_name).map(|(compiled, map)| (compiled, Some(map))),
+        None => compile_source(source).map(|compiled| (compiled, None)),
+    };
+    let (mut code, map) = match (result, diagnostics) {
+        (Ok((compiled, map)), Diagnostics::Human) => {
+            print_warnings(&compiled.warnings, source);
+            (compiled.code, map)
+        }
+        (Ok((compiled, map)), Diagnostics::Json) => {
+            for warning in &compiled.warnings {
+                println!("{}", warning.to_json());
             }
-            ast
+            (compiled.code, map)
         }
-        None => {
-            println!("epic failure:");
+        (Err(error), Diagnostics::Human) => {
+            error.print_with_context(source);
+            return;
+        }
+        (Err(error), Diagnostics::Json) => {
+            for line in error.to_json() {
+                println!("{}", line);
+            }
             return;
         }
     };
 
-    let generator = CodeGenerator::new(ast);
-    let code = generator.generate();
+    if let Some(map) = map {
+        let map_path = format!("{}.map", output);
+        let map_name = Path::new(&map_path)
+            .file_name()
+            .map_or_else(|| map_path.clone(), |name| name.to_string_lossy().into_owned());
+        // only link a map that was actually written
+        match fs::write(&map_path, map) {
+            Ok(()) => code.push_str(&format!("\n//# sourceMappingURL={}", map_name)),
+            Err(e) => eprintln!("Error writing source map {}: {}", map_path, e),
+        }
+    }
+
+    if diagnostics == Diagnostics::Json {
+        if let Err(e) = fs::write(output, code) {
+            eprintln!("Error writing {}: {}", output, e);
+        }
+        return;
+    }
+
     println!();
     println!("{}",code);
     match fs::write(output, code) {
@@ -74,6 +373,18 @@ fn compile(source: &str, output: &str) {
     }
 }
 
+/// Writes just the generated code to stdout so it can be piped on;
+/// warnings and errors go to stderr.
+fn compile_to_stdout(source: &str) {
+    match compile_source(source) {
+        Ok(compiled) => {
+            print_warnings(&compiled.warnings, source);
+            print!("{}", compiled.code);
+        }
+        Err(error) => error.print_with_context(source),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,22 +393,154 @@ mod tests {
     fn test_compile() {
         let source = "let x = 5;";
         let output = "output.js";
-        compile(source, output);
+        compile(source, output, None, Diagnostics::Human);
         assert!(fs::metadata(output).is_ok());
         fs::remove_file(output).unwrap();
     }
 
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_defaults() {
+        let parsed = parse_args(&args(&["prog.lava"])).unwrap();
+        assert_eq!(
+            parsed,
+            Args {
+                source: "prog.lava".to_string(),
+                output: None,
+                emit: Emit::Js,
+                sourcemap: false,
+                watch: false,
+                diagnostics: Diagnostics::Human,
+                no_color: false,
+            }
+        );
+
+        let parsed = parse_args(&args(&["prog.lava", "out.js"])).unwrap();
+        assert_eq!(parsed.output, Some("out.js".to_string()));
+    }
+
+    #[test]
+    fn test_parse_args_emit() {
+        let parsed = parse_args(&args(&["prog.lava", "--emit=tokens"])).unwrap();
+        assert_eq!(parsed.emit, Emit::Tokens);
+
+        let parsed = parse_args(&args(&["--emit=ast", "prog.lava"])).unwrap();
+        assert_eq!(parsed.emit, Emit::Ast);
+
+        let parsed = parse_args(&args(&["prog.lava", "--emit=fmt"])).unwrap();
+        assert_eq!(parsed.emit, Emit::Fmt);
+        assert_eq!(parsed.source, "prog.lava");
+    }
+
+    #[test]
+    fn test_compile_with_sourcemap() {
+        let parsed = parse_args(&args(&["prog.lava", "--sourcemap"])).unwrap();
+        assert!(parsed.sourcemap);
+
+        let output = "sourcemap_output.js";
+        compile("let x: Int = 1;\nprintln(x);", output, Some("prog.lava"), Diagnostics::Human);
+        let code = fs::read_to_string(output).unwrap();
+        let map = fs::read_to_string("sourcemap_output.js.map").unwrap();
+        fs::remove_file(output).unwrap();
+        fs::remove_file("sourcemap_output.js.map").unwrap();
+
+        assert!(code.ends_with("\n//# sourceMappingURL=sourcemap_output.js.map"));
+        assert!(map.contains("\"sources\":[\"prog.lava\"]"));
+    }
+
+    #[test]
+    fn test_build_repeatedly() {
+        let parsed = parse_args(&args(&["watch_input.lava", "watch_output.js", "--watch"])).unwrap();
+        assert!(parsed.watch);
+
+        fs::write("watch_input.lava", "let x: Int = 1;\nprintln(x);").unwrap();
+        build(&parsed).unwrap();
+        let first = fs::read_to_string("watch_output.js").unwrap();
+        fs::write("watch_input.lava", "let x: Int = 2;\nprintln(x);").unwrap();
+        build(&parsed).unwrap();
+        let second = fs::read_to_string("watch_output.js").unwrap();
+        fs::remove_file("watch_input.lava").unwrap();
+        fs::remove_file("watch_output.js").unwrap();
+
+        assert!(first.contains("let x = 1;"));
+        assert!(second.contains("let x = 2;"));
+        // a missing file is reported, not fatal
+        assert!(build(&parsed).is_err());
+    }
+
+    #[test]
+    fn test_compile_from_stdin() {
+        let parsed = parse_args(&args(&["-"])).unwrap();
+        assert_eq!(parsed.source, STDIN);
+        assert_eq!(parsed.output, None);
+        assert!(parse_args(&args(&["-", "--watch"])).is_err());
+        assert!(parse_args(&args(&["-", "--sourcemap"])).is_err());
+        assert!(parse_args(&args(&["-", "out.js", "--sourcemap"])).is_ok());
+
+        let source = read_source("let x: Int = 5;\nprintln(x);".as_bytes()).unwrap();
+        let code = lavascript::compile_str(&source).unwrap();
+        assert_eq!(code.trim(), "let x = 5;\nconsole.log(x);");
+    }
+
+    #[test]
+    fn test_parse_args_diagnostics() {
+        let parsed = parse_args(&args(&["prog.lava", "--diagnostics=json"])).unwrap();
+        assert_eq!(parsed.diagnostics, Diagnostics::Json);
+        assert!(parse_args(&args(&["prog.lava", "--diagnostics=xml"])).is_err());
+        // stdout already carries the generated code
+        assert!(parse_args(&args(&["-", "--diagnostics=json"])).is_err());
+        assert!(parse_args(&args(&["-", "out.js", "--diagnostics=json"])).is_ok());
+    }
+
+    #[test]
+    fn test_parse_args_no_color() {
+        let parsed = parse_args(&args(&["--no-color", "prog.lava"])).unwrap();
+        assert!(parsed.no_color);
+        assert!(!color_enabled(&parsed, None));
+    }
+
+    #[test]
+    fn test_color_enabled() {
+        let plain = parse_args(&args(&["prog.lava"])).unwrap();
+        let no_color = parse_args(&args(&["--no-color", "prog.lava"])).unwrap();
+
+        assert!(color_enabled(&plain, None));
+        assert!(!color_enabled(&no_color, None));
+        assert!(!color_enabled(&plain, Some(OsStr::new("1"))));
+        // an empty NO_COLOR counts as unset
+        assert!(color_enabled(&plain, Some(OsStr::new(""))));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(&args(&["prog.lava", "--emit=bytecode"])).is_err());
+        assert!(parse_args(&args(&["prog.lava", "--verbose"])).is_err());
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["a.lava", "b.js", "c.js"])).is_err());
+    }
+
+    #[test]
+    fn test_default_output_path() {
+        assert_eq!(default_output_path("foo.lava"), "foo.js");
+        assert_eq!(default_output_path("foo"), "foo.js");
+        assert_eq!(default_output_path("a.b.c"), "a.b.js");
+        assert_eq!(default_output_path("dir/prog.ls"), "dir/prog.js");
+    }
+
     #[test]
     fn test_compile_lex_error() {
         let source = "let @x = 5;";
-        let result = std::panic::catch_unwind(|| compile(source, "lex_fail.js"));
+        let result = std::panic::catch_unwind(|| compile(source, "lex_fail.js", None, Diagnostics::Human));
         assert!(result.is_ok()); 
     }
 
     #[test]
     fn test_compile_parse_failure() {
         let source = "fun {"; 
-        let result = std::panic::catch_unwind(|| compile(source, "parse_fail.js"));
+        let result = std::panic::catch_unwind(|| compile(source, "parse_fail.js", None, Diagnostics::Human));
         assert!(result.is_ok()); 
     }
 
@@ -108,7 +551,7 @@ mod tests {
                 let x = ;
             }
         "#;
-        let result = std::panic::catch_unwind(|| compile(source, "errors.js"));
+        let result = std::panic::catch_unwind(|| compile(source, "errors.js", None, Diagnostics::Human));
         assert!(result.is_ok());
     }
 
@@ -116,7 +559,7 @@ mod tests {
     fn test_compile_write_fail() {
         let source = "let x = 5;";
         let output = "/root/protected_output.js";
-        let result = std::panic::catch_unwind(|| compile(source, output));
+        let result = std::panic::catch_unwind(|| compile(source, output, None, Diagnostics::Human));
         assert!(result.is_ok());
     }
 
@@ -125,7 +568,7 @@ mod tests {
         let bad_source = "let @x = 5;"; 
         let output = "lex_fail.js";
     
-        let result = std::panic::catch_unwind(|| compile(bad_source, output));
+        let result = std::panic::catch_unwind(|| compile(bad_source, output, None, Diagnostics::Human));
         assert!(result.is_ok());
 
         assert!(!std::path::Path::new(output).exists());
@@ -134,14 +577,14 @@ mod tests {
     #[test]
     fn test_compile_success_case() {
         let source = r#"
-            fun main() {
+            fun main() -> Void {
                 let x: Int = 10;
                 println(x);
             }
         "#;
         let output = "compiled_output.js";
 
-        compile(source, output);
+        compile(source, output, None, Diagnostics::Human);
         assert!(std::fs::metadata(output).is_ok());
         std::fs::remove_file(output).unwrap();
     }
@@ -0,0 +1,332 @@
+use crate::ast::*;
+use crate::lexer::Span;
+
+/// Largest integer a JS number holds exactly (`Number.MAX_SAFE_INTEGER`).
+/// Folding past it would print a value the runtime couldn't have produced.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// Folds literal-only arithmetic and boolean logic, e.g. `2 + 3 * 4` becomes
+/// `14`. Anything that would overflow, leave JS's safe integer range, or
+/// produce a non-integer (such as `7 / 2`) is left as written.
+pub fn optimize(entry: Entry) -> Entry {
+    Entry {
+        statements: fold_stmts(entry.statements),
+        class_defs: entry.class_defs.into_iter().map(fold_class).collect(),
+        fun_defs: entry.fun_defs.into_iter().map(fold_fun).collect(),
+    }
+}
+
+fn fold_fun(fun_def: FunDef) -> FunDef {
+    FunDef {
+        params: fold_params(fun_def.params),
+        statements: fold_stmts(fun_def.statements),
+        ..fun_def
+    }
+}
+
+fn fold_class(class_def: ClassDef) -> ClassDef {
+    let constructor = class_def.constructor;
+    let constructor = Constructor {
+        params: fold_params(constructor.params),
+        super_call: constructor.super_call.map(|super_call| SuperCall {
+            args: fold_exprs(super_call.args),
+            ..super_call
+        }),
+        statements: fold_stmts(constructor.statements),
+    };
+    let methods = class_def
+        .methods
+        .into_iter()
+        .map(|method| MethDef {
+            params: fold_params(method.params),
+            statements: fold_stmts(method.statements),
+            ..method
+        })
+        .collect();
+
+    ClassDef {
+        constructor,
+        methods,
+        ..class_def
+    }
+}
+
+fn fold_stmts(statements: Vec<Stmt>) -> Vec<Stmt> {
+    statements.into_iter().map(fold_stmt).collect()
+}
+
+fn fold_stmt(stmt: Stmt) -> Stmt {
+    match stmt {
+        Stmt::Expr(s) => Stmt::Expr(ExprStmt {
+            expr: fold_boxed(*s.expr),
+            ..s
+        }),
+        Stmt::Assign(s) => Stmt::Assign(AssignStmt {
+            expr: fold_boxed(*s.expr),
+            ..s
+        }),
+        Stmt::VarDeclWithAssign(s) => Stmt::VarDeclWithAssign(VarDeclWithAssign {
+            expr: fold_boxed(*s.expr),
+            ..s
+        }),
+        Stmt::MultiVarDecl(s) => Stmt::MultiVarDecl(MultiVarDeclStmt {
+            decls: s.decls.into_iter().map(fold_stmt).collect(),
+            ..s
+        }),
+        Stmt::While(s) => Stmt::While(WhileStmt {
+            condition: fold_boxed(*s.condition),
+            body: Box::new(fold_stmt(*s.body)),
+            ..s
+        }),
+        Stmt::ForRange(s) => Stmt::ForRange(ForRangeStmt {
+            start: fold_boxed(*s.start),
+            end: fold_boxed(*s.end),
+            body: Box::new(fold_stmt(*s.body)),
+            ..s
+        }),
+        Stmt::DoWhile(s) => Stmt::DoWhile(DoWhileStmt {
+            body: Box::new(fold_stmt(*s.body)),
+            condition: fold_boxed(*s.condition),
+            ..s
+        }),
+        Stmt::If(s) => Stmt::If(IfStmt {
+            condition: fold_boxed(*s.condition),
+            then_branch: Box::new(fold_stmt(*s.then_branch)),
+            else_branch: s.else_branch.map(|stmt| Box::new(fold_stmt(*stmt))),
+            ..s
+        }),
+        Stmt::Match(s) => Stmt::Match(MatchStmt {
+            scrutinee: fold_boxed(*s.scrutinee),
+            arms: s
+                .arms
+                .into_iter()
+                .map(|arm| MatchArm {
+                    body: Box::new(fold_stmt(*arm.body)),
+                    ..arm
+                })
+                .collect(),
+            default: s.default.map(|stmt| Box::new(fold_stmt(*stmt))),
+            ..s
+        }),
+        Stmt::Return(s) => Stmt::Return(ReturnStmt {
+            value: s.value.map(|expr| fold_boxed(*expr)),
+            ..s
+        }),
+        Stmt::Block(s) => Stmt::Block(BlockStmt {
+            statements: fold_stmts(s.statements),
+            ..s
+        }),
+        stmt @ (Stmt::VarDecl(_) | Stmt::Break(_) | Stmt::Continue(_) | Stmt::Empty) => stmt,
+    }
+}
+
+fn fold_boxed(expr: Expr) -> Box<Expr> {
+    Box::new(fold_expr(expr))
+}
+
+fn fold_params(params: Vec<ParamDecl>) -> Vec<ParamDecl> {
+    params
+        .into_iter()
+        .map(|param| ParamDecl {
+            default: param.default.map(fold_expr),
+            ..param
+        })
+        .collect()
+}
+
+fn fold_exprs(exprs: Vec<Expr>) -> Vec<Expr> {
+    exprs.into_iter().map(fold_expr).collect()
+}
+
+fn fold_expr(expr: Expr) -> Expr {
+    match expr {
+        Expr::Binary(binary) => {
+            let left = fold_expr(*binary.left);
+            let right = fold_expr(*binary.right);
+            match fold_binary(&left, &binary.operator, &right, binary.span) {
+                Some(folded) => folded,
+                None => Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    ..binary
+                }),
+            }
+        }
+        Expr::Unary(unary) => {
+            let operand = fold_expr(*unary.expr);
+            match fold_unary(&unary.operator, &operand, unary.span) {
+                Some(folded) => folded,
+                None => Expr::Unary(UnaryExpr {
+                    expr: Box::new(operand),
+                    ..unary
+                }),
+            }
+        }
+        Expr::Grouped(inner, span) => match fold_expr(*inner) {
+            // parentheses around a single literal are just noise, except for a
+            // negative one: `(-5) ** 2` must not become `-5 ** 2`
+            Expr::IntegerLiteral(i) if i.value >= 0 => Expr::IntegerLiteral(i),
+            literal @ (Expr::BooleanLiteral(_) | Expr::CharLiteral(_) | Expr::Nil(_)) => literal,
+            inner => Expr::Grouped(Box::new(inner), span),
+        },
+        Expr::FunCall(call) => Expr::FunCall(FunCall {
+            args: fold_exprs(call.args),
+            ..call
+        }),
+        Expr::MethCall(call) => Expr::MethCall(MethCall {
+            object: fold_boxed(*call.object),
+            args: fold_exprs(call.args),
+            ..call
+        }),
+        Expr::Field(field) => Expr::Field(Field {
+            object: fold_boxed(*field.object),
+            ..field
+        }),
+        Expr::New(new_expr) => Expr::New(NewExpr {
+            args: fold_exprs(new_expr.args),
+            ..new_expr
+        }),
+        Expr::Println(p) => Expr::Println(PrintlnExpr {
+            args: fold_exprs(p.args),
+            ..p
+        }),
+        Expr::Print(p) => Expr::Print(PrintExpr {
+            args: fold_exprs(p.args),
+            ..p
+        }),
+        Expr::Len(l) => Expr::Len(LenExpr { arg: fold_boxed(*l.arg), ..l }),
+        Expr::TypeNameOf(t) => Expr::TypeNameOf(TypeNameOfExpr { arg: fold_boxed(*t.arg), ..t }),
+        Expr::Lambda(lambda) => Expr::Lambda(LambdaExpr {
+            params: fold_params(lambda.params),
+            body: fold_stmts(lambda.body),
+            ..lambda
+        }),
+        Expr::Assert(a) => Expr::Assert(AssertExpr {
+            cond: fold_boxed(*a.cond),
+            message: a.message.map(|expr| fold_boxed(*expr)),
+            ..a
+        }),
+        expr => expr,
+    }
+}
+
+fn fold_binary(left: &Expr, operator: &BinaryOp, right: &Expr, span: Span) -> Option<Expr> {
+    match (left, right) {
+        (Expr::IntegerLiteral(l), Expr::IntegerLiteral(r)) => {
+            let (l, r) = (l.value, r.value);
+            let value = match operator {
+                BinaryOp::Add => l.checked_add(r),
+                BinaryOp::Subtract => l.checked_sub(r),
+                BinaryOp::Multiply => l.checked_mul(r),
+                // JS division is floating point, so only exact quotients fold
+                BinaryOp::Divide if r != 0 && l.checked_rem(r) == Some(0) => l.checked_div(r),
+                BinaryOp::Power => u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+                BinaryOp::Equal => return Some(bool_literal(l == r, span)),
+                BinaryOp::NotEqual => return Some(bool_literal(l != r, span)),
+                BinaryOp::Greater => return Some(bool_literal(l > r, span)),
+                BinaryOp::Less => return Some(bool_literal(l < r, span)),
+                BinaryOp::GreaterEqual => return Some(bool_literal(l >= r, span)),
+                BinaryOp::LessEqual => return Some(bool_literal(l <= r, span)),
+                _ => None,
+            }?;
+            int_literal(value, span)
+        }
+        (Expr::BooleanLiteral(l), Expr::BooleanLiteral(r)) => {
+            let (l, r) = (l.value, r.value);
+            let value = match operator {
+                BinaryOp::And => l && r,
+                BinaryOp::Or => l || r,
+                BinaryOp::Equal => l == r,
+                BinaryOp::NotEqual => l != r,
+                _ => return None,
+            };
+            Some(bool_literal(value, span))
+        }
+        _ => None,
+    }
+}
+
+fn fold_unary(operator: &UnaryOp, operand: &Expr, span: Span) -> Option<Expr> {
+    match (operator, operand) {
+        (UnaryOp::Not, Expr::BooleanLiteral(b)) => Some(bool_literal(!b.value, span)),
+        (UnaryOp::Negate, Expr::IntegerLiteral(i)) => int_literal(i.value.checked_neg()?, span),
+        (UnaryOp::Plus, Expr::IntegerLiteral(i)) => int_literal(i.value, span),
+        _ => None,
+    }
+}
+
+fn int_literal(value: i64, span: Span) -> Option<Expr> {
+    if value.unsigned_abs() > MAX_SAFE_INTEGER as u64 {
+        return None;
+    }
+    Some(Expr::IntegerLiteral(IntegerLiteral { value, span }))
+}
+
+fn bool_literal(value: bool, span: Span) -> Expr {
+    Expr::BooleanLiteral(BooleanLiteral { value, span })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codegen::CodeGenerator, lexer::Lexer, parser::Parser};
+
+    fn optimized(input: &str) -> String {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        CodeGenerator::new(optimize(ast)).generate().trim().to_string()
+    }
+
+    #[test]
+    fn test_fold_arithmetic() {
+        assert_eq!(optimized("let x: Int = 2 + 3;"), "let x = 5;");
+        assert_eq!(optimized("let x: Int = 2 + 3 * 4;"), "let x = 14;");
+        assert_eq!(optimized("let x: Int = (1 + 2) * -3;"), "let x = -9;");
+        assert_eq!(optimized("let x: Int = 2 ** 10;"), "let x = 1024;");
+    }
+
+    #[test]
+    fn test_fold_boolean() {
+        assert_eq!(optimized("let b: Boolean = !true;"), "let b = false;");
+        assert_eq!(optimized("let b: Boolean = true && !false;"), "let b = true;");
+        assert_eq!(optimized("let b: Boolean = 1 + 1 == 2;"), "let b = true;");
+    }
+
+    #[test]
+    fn test_variables_left_untouched() {
+        assert_eq!(optimized("let y: Int = x + 1;"), "let y = x + 1;");
+        assert_eq!(optimized("let y: Int = x + 2 * 3;"), "let y = x + 6;");
+    }
+
+    #[test]
+    fn test_negative_group_keeps_parens() {
+        let folded = fold_expr(Expr::Grouped(
+            Box::new(Expr::Unary(UnaryExpr {
+                operator: UnaryOp::Negate,
+                expr: Box::new(Expr::IntegerLiteral(IntegerLiteral { value: 5, span: Span::default() })),
+                span: Span::default(),
+            })),
+            Span::default(),
+        ));
+        assert!(matches!(folded, Expr::Grouped(inner, _) if matches!(*inner, Expr::IntegerLiteral(IntegerLiteral { value: -5, .. }))));
+    }
+
+    #[test]
+    fn test_unfoldable_left_alone() {
+        assert_eq!(optimized("let x: Int = 7 / 2;"), "let x = 7 / 2;");
+        assert_eq!(optimized("let x: Int = 8 / 2;"), "let x = 4;");
+        assert_eq!(optimized("let x: Int = 1 / 0;"), "let x = 1 / 0;");
+        assert_eq!(optimized("let x: Int = 2 ** 60;"), "let x = 2 ** 60;");
+        assert_eq!(
+            optimized("let x: Int = 9223372036854775807 + 1;"),
+            "let x = 9223372036854775807 + 1;"
+        );
+    }
+
+    #[test]
+    fn test_folds_inside_bodies() {
+        let program = optimized("fun f() -> Int { if (1 < 2) { return 3 * 3; } return 0; }");
+        assert!(program.contains("if (true)"));
+        assert!(program.contains("return 9;"));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use super::*;
 use crate::ast::Entry;
 use crate::lexer::{Span, Token, TokenType};
@@ -6,17 +8,158 @@ pub struct Parser {
     pub tokens: Vec<Token>,
     pub position: usize,
     pub errors: Vec<ParseError>,
+    /// Comments found directly above a `class`, `fun` or `meth`, keyed by
+    /// the position of that keyword in `tokens`.
+    pub docs: HashMap<usize, String>,
+    /// Comments that document no declaration, which the AST has no place for.
+    pub dropped_comments: Vec<Span>,
+    /// How many parentheses, unary operators, call argument lists, blocks and
+    /// statement bodies enclose the current position.
+    depth: usize,
+    /// The most of those that may nest before parsing gives up with
+    /// `NestingTooDeep` instead of overflowing the stack.
+    pub max_depth: usize,
+    /// How many operators the chains enclosing the current position hold.
+    chain: usize,
+    /// The most operators that may chain before parsing gives up with
+    /// `NestingTooDeep`, since each one nests the tree a level deeper.
+    pub max_chain: usize,
 }
 
+/// Default for [`Parser::max_depth`]. Each level costs a dozen or more parser
+/// frames, a nested call the most, so this needs the stack that
+/// [`crate::with_stack`] provides; a default 2 MiB thread only fits about 60.
+pub const DEFAULT_MAX_DEPTH: usize = 1024;
+
+/// Default for [`Parser::max_chain`]. Parsing a chain doesn't recurse, but
+/// every pass over the tree does, once per operator.
+pub const DEFAULT_MAX_CHAIN: usize = 16 * 1024;
+
 impl Parser {
+    /// Comment tokens are taken out of the stream here; the ones that
+    /// document a declaration are kept in `docs`, the rest are dropped.
     pub fn new(tokens: Vec<Token>) -> Self {
+        let mut kept = Vec::with_capacity(tokens.len());
+        let mut docs = HashMap::new();
+        let mut dropped_comments = vec![];
+        let mut comments: Vec<(String, Span)> = vec![];
+
+        for token in tokens {
+            if let TokenType::Comment(text) = token.token_type {
+                // a comment trailing code on the same line documents that code
+                let trailing = kept.last().is_some_and(|prev: &Token| prev.span.line == token.span.line);
+                if trailing {
+                    dropped_comments.push(token.span);
+                } else {
+                    comments.push((text, token.span));
+                }
+                continue;
+            }
+            let documented = matches!(token.token_type, TokenType::Class | TokenType::Fun | TokenType::Meth);
+            if documented && !comments.is_empty() {
+                let text: Vec<String> = comments.drain(..).map(|(text, _)| text).collect();
+                docs.insert(kept.len(), text.join("\n"));
+            }
+            dropped_comments.extend(comments.drain(..).map(|(_, span)| span));
+            kept.push(token);
+        }
+
         Self {
-            tokens,
+            tokens: kept,
             position: 0,
             errors: vec![],
+            docs,
+            dropped_comments,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            chain: 0,
+            max_chain: DEFAULT_MAX_CHAIN,
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Runs `f` one level of nesting deeper, or reports `NestingTooDeep` at
+    /// `span` without running it once the limit is reached. The construct
+    /// that was too deep is skipped, so its remaining levels don't each
+    /// report the same error again.
+    pub fn nested<T>(&mut self, span: Span, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= self.max_depth {
+            self.errors.push(ParseError::NestingTooDeep { span });
+            self.skip_nested();
+            return None;
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Checks that another operator may join a chain that already holds
+    /// `links`, reporting `NestingTooDeep` at `span` and skipping the rest of
+    /// the chain once the limit is reached.
+    pub fn check_chain(&mut self, span: Span, links: usize) -> Option<()> {
+        if self.chain + links >= self.max_chain {
+            self.errors.push(ParseError::NestingTooDeep { span });
+            self.skip_nested();
+            return None;
+        }
+        Some(())
+    }
+
+    /// Runs `f` on an operand that sits `links` operators down a chain, so
+    /// chains inside it count toward the same limit.
+    pub fn linked<T>(&mut self, links: usize, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.chain += links;
+        let result = f(self);
+        self.chain -= links;
+        result
+    }
+
+    /// Reports an operand or statement missing after an operator or keyword,
+    /// unless it was there but nested too deeply, which has been reported
+    /// already.
+    pub fn report_missing(&mut self, error: ParseError) {
+        if !matches!(self.errors.last(), Some(ParseError::NestingTooDeep { .. })) {
+            self.errors.push(error);
+        }
+    }
+
+    /// Skips ahead to the `;` or closing bracket that ends the construct at
+    /// the current position, stepping over any bracketed groups inside it and
+    /// any `else` branches that follow it.
+    fn skip_nested(&mut self) {
+        let mut open = 0usize;
+        while let Some(token) = self.peek_ref() {
+            match token.token_type {
+                TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => open += 1,
+                TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => {
+                    if open == 0 {
+                        break;
+                    }
+                    open -= 1;
+                }
+                TokenType::Semicolon if open == 0 => {
+                    let next = self.tokens.get(self.position + 1).map(|token| &token.token_type);
+                    if next != Some(&TokenType::Else) {
+                        break;
+                    }
+                }
+                TokenType::EOF => break,
+                _ => {}
+            }
+            self.advance();
         }
     }
 
+    /// The doc comment for the declaration starting at the current token.
+    pub fn take_doc(&mut self) -> Option<String> {
+        self.docs.remove(&self.position)
+    }
+
     pub fn parse(&mut self) -> Option<Entry> {
         let mut program = Entry::default();
 
@@ -36,13 +179,25 @@ impl Parser {
                     break;
                 }
                 _ => {
+                    let start = self.position;
+                    let reported = self.errors.len();
                     let stmt = self.parse_stmt();
                     match stmt {
                         Some(stmt) => {
                             program.statements.push(stmt);
                         }
                         None => {
-                            self.advance();
+                            // failing without a word means the token can't start anything,
+                            // so report it and step over just that token
+                            if self.errors.len() == reported && self.position == start {
+                                self.errors.push(ParseError::UnexpectedToken {
+                                    symbol: token.token_type.to_string(),
+                                    span: token.span,
+                                });
+                                self.advance();
+                            } else {
+                                self.recover_statement(start)
+                            }
                         }
                     }
                 }
@@ -64,10 +219,43 @@ impl Parser {
                     }
                 }
             }
+            SyncPoint::Statement => {
+                while let Some(token) = self.peek() {
+                    match token.token_type {
+                        TokenType::Semicolon => {
+                            self.advance();
+                            break;
+                        }
+                        TokenType::Let
+                        | TokenType::If
+                        | TokenType::While
+                        | TokenType::Do
+                        | TokenType::For
+                        | TokenType::Return
+                        | TokenType::LeftBrace
+                        // never skip past the end of the enclosing block or
+                        // into the next top-level declaration
+                        | TokenType::RightBrace
+                        | TokenType::Class
+                        | TokenType::Fun
+                        | TokenType::EOF => break,
+                        _ => self.advance(),
+                    }
+                }
+            }
             _ => {}
         }
     }
 
+    /// Skips the rest of a statement that failed to parse. `start` is where it
+    /// began, so a failure that consumed nothing still moves past one token.
+    pub fn recover_statement(&mut self, start: usize) {
+        if self.position == start {
+            self.advance();
+        }
+        self.synchronize(SyncPoint::Statement);
+    }
+
     pub fn has_errors(&self) -> bool {
         !self.errors.is_empty()
     }
@@ -84,6 +272,34 @@ impl Parser {
         self.position += 1;
     }
 
+    /// The current position, to hand back to [`Parser::restore`].
+    pub fn checkpoint(&self) -> usize {
+        self.position
+    }
+
+    pub fn restore(&mut self, checkpoint: usize) {
+        self.position = checkpoint;
+    }
+
+    /// Runs `f` speculatively: if it returns `None`, the position and any
+    /// errors it reported are rolled back so another parse can be tried.
+    pub fn try_parse<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let checkpoint = self.checkpoint();
+        let error_count = self.errors.len();
+        let result = f(self);
+        if result.is_none() {
+            self.restore(checkpoint);
+            self.errors.truncate(error_count);
+        }
+        result
+    }
+
+    /// Borrows the current token. Prefer this over [`Parser::peek`] on hot
+    /// paths, which clones the token (and any string it owns).
+    pub fn peek_ref(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
     pub fn peek(&mut self) -> Option<Token> {
         if self.position >= self.tokens.len() {
             return None;
@@ -114,8 +330,25 @@ impl Parser {
         }
     }
 
+    /// Where an error about something missing after the last consumed token
+    /// should point: just past that token, rather than at whatever follows,
+    /// which may be lines away or the end of the file.
+    pub fn span_after_previous(&self) -> Span {
+        match self.position.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(previous) => previous.span.after(),
+            None => self.peek_ref().map(|token| token.span).unwrap_or_default(),
+        }
+    }
+
     pub fn current_span(&mut self) -> Option<Span> {
-        self.peek().map(|token| token.span.clone())
+        self.peek_ref().map(|token| token.span)
+    }
+
+    /// What an error found at the current position: the token, or the end of
+    /// file once the input has run out.
+    pub fn found(&self) -> String {
+        self.peek_ref()
+            .map_or_else(|| TokenType::EOF.to_string(), |token| token.token_type.to_string())
     }
 
     pub fn consume_identifier(&mut self, ident: &str) -> Option<String> {
@@ -128,6 +361,13 @@ impl Parser {
                     self.advance();
                     return Some(name);
                 }
+                token_type if token_type.is_keyword() => {
+                    self.errors.push(ParseError::ReservedKeyword {
+                        keyword: token_type.to_string(),
+                        span: token.span,
+                    });
+                    return None;
+                }
                 _ => {
                     self.errors.push(ParseError::expected_but_found(
                         TokenType::Identifier(ident.to_string()).to_string(),
@@ -147,14 +387,16 @@ impl Parser {
     pub fn consume(&mut self, expected: TokenType) -> Option<Token> {
         let span = self.current_span();
 
-        if let Some(token) = self.peek() {
+        if let Some(token) = self.peek_ref() {
             if token.token_type == expected {
+                let token = token.clone();
                 self.advance();
                 return Some(token);
             }
+            let found = token.token_type.to_string();
             self.errors.push(ParseError::ExpectedButFound {
                 expected: expected.to_string(),
-                found: token.token_type.to_string(),
+                found,
                 span,
             });
             return None;
@@ -167,8 +409,9 @@ impl Parser {
     pub fn consume_optional(&mut self, expected: TokenType) -> Option<Token> {
         let span = self.current_span();
 
-        if let Some(token) = self.peek() {
+        if let Some(token) = self.peek_ref() {
             if token.token_type == expected {
+                let token = token.clone();
                 self.advance();
                 return Some(token);
             }
@@ -213,6 +456,7 @@ impl Parser {
                     self.advance();
                     Some(crate::lexer::TypeName::Class(ident.to_string()))
                 }
+                TokenType::LeftParen => self.consume_function_type(),
                 _ => {
                     self.errors.push(ParseError::expected_but_found(
                         "variable type".to_string(),
@@ -228,6 +472,27 @@ impl Parser {
             None
         }
     }
+
+    /// Parses a function type such as `(Int, Str) -> Int`, starting at the `(`.
+    fn consume_function_type(&mut self) -> Option<crate::lexer::TypeName> {
+        self.consume(TokenType::LeftParen)?;
+        let mut params = Vec::new();
+        if !matches!(self.peek_ref(), Some(token) if token.token_type == TokenType::RightParen) {
+            loop {
+                params.push(self.consume_type()?);
+                if self.consume_optional(TokenType::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen)?;
+        self.consume(TokenType::Arrow)?;
+        let ret = self.consume_type()?;
+        Some(crate::lexer::TypeName::Function {
+            params,
+            ret: Box::new(ret),
+        })
+    }
 }
 
 mod tests {
@@ -265,6 +530,19 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn test_stray_top_level_token() {
+        let tokens = Lexer::new("let x: Int = 5; )\nprintln(x);").tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let entry = parser.parse().unwrap();
+        assert!(matches!(
+            parser.get_errors(),
+            [crate::parser::ParseError::UnexpectedToken { symbol, span }] if symbol == ")" && span.column == 17
+        ));
+        // parsing picks up again after the stray token
+        assert_eq!(entry.statements.len(), 2);
+    }
+
     #[test]
     fn test_parse_no_errors_empty() {
         let has_error = parser_has_errors("");
@@ -372,4 +650,59 @@ mod tests {
             let two: Int = adder.add(1, 1);");
         assert!(has_errors == false)
     }
-}
\ No newline at end of file
+
+    fn parse_errors(input: &str) -> (Option<Entry>, usize) {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let entry = parser.parse();
+        (entry, parser.get_errors().len())
+    }
+
+    #[test]
+    fn test_recover_at_statement_boundary() {
+        let (entry, errors) = parse_errors("let x Int = 5 + 3; let y: Int = 2;");
+        assert_eq!(errors, 1);
+        assert_eq!(entry.unwrap().statements.len(), 1);
+
+        let (entry, errors) = parse_errors(
+            "fun main() -> Void { let x: Int = 5 counter(1, 2); let y: Int = 2; }",
+        );
+        assert_eq!(errors, 1);
+        assert_eq!(entry.unwrap().fun_defs[0].statements.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_statements() {
+        let (entry, errors) = parse_errors(";;");
+        assert_eq!(errors, 0);
+        assert!(matches!(entry.unwrap().statements.as_slice(), [Stmt::Empty, Stmt::Empty]));
+
+        // the `;` ending an expression statement belongs to it
+        let (entry, errors) = parse_errors("println(1);; x = 2;");
+        assert_eq!(errors, 0);
+        assert!(matches!(
+            entry.unwrap().statements.as_slice(),
+            [Stmt::Expr(_), Stmt::Empty, Stmt::Assign(_)]
+        ));
+    }
+
+    #[test]
+    fn test_failed_speculative_parse_leaves_state_unchanged() {
+        use crate::parser::ParserStmt;
+
+        let tokens = Lexer::new("obj.field; obj.field = 1;").tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let checkpoint = parser.checkpoint();
+
+        assert_eq!(parser.try_parse(|parser| parser.parse_field_target()), None);
+        assert_eq!(parser.checkpoint(), checkpoint);
+        assert!(!parser.has_errors());
+
+        let entry = parser.parse().unwrap();
+        assert!(!parser.has_errors(), "{:?}", parser.get_errors());
+        assert!(matches!(
+            entry.statements.as_slice(),
+            [Stmt::Expr(_), Stmt::Assign(AssignStmt { name, .. })] if name == "obj.field"
+        ));
+    }
+}
@@ -1,6 +1,5 @@
 use super::*;
-use crate::ast::BlockStmt;
-use crate::ast::{ClassDef, Constructor, Expr, FunDef, MethDef, ParamDecl, Stmt::VarDecl, VarDeclStmt};
+use crate::ast::{ClassDef, Constructor, FunDef, MethDef, ParamDecl, Stmt::MultiVarDecl, Stmt::VarDecl, SuperCall};
 use crate::lexer::{Span, TokenType};
 
 pub trait ParserDecl {
@@ -14,9 +13,13 @@ pub trait ParserDecl {
 
 impl ParserDecl for Parser {
     fn parse_class(&mut self) -> Option<ClassDef> {
-        let mut class = ClassDef::default();
+        let mut class = ClassDef {
+            doc: self.take_doc(),
+            ..ClassDef::default()
+        };
         self.consume(TokenType::Class)?;
 
+        class.span = self.current_span()?;
         class.name = self.consume_identifier("class name")?;
 
         if self.consume_optional(TokenType::Extends).is_some() {
@@ -27,12 +30,19 @@ impl ParserDecl for Parser {
 
         while self
             .peek()
-            .map_or(false, |token| token.token_type == TokenType::Let)
+            .map_or(false, |token| matches!(token.token_type, TokenType::Let | TokenType::Const))
         {
             match self.parse_var_decl() {
                 Some(stmt) => {
                                 match stmt {
-                                    VarDecl(VarDeclStmt { name, var_type, span }) => class.vars.push(VarDeclStmt { name, var_type, span }),
+                                    VarDecl(var_decl) => class.vars.push(var_decl),
+                                    MultiVarDecl(multi) => {
+                                        for decl in multi.decls {
+                                            if let VarDecl(var_decl) = decl {
+                                                class.vars.push(var_decl);
+                                            }
+                                        }
+                                    }
                                     _ => ()
                                 }
                             }
@@ -40,8 +50,24 @@ impl ParserDecl for Parser {
             }
         }
 
-        if let Some(constructor) = self.parse_constructor(&class.name) {
-            class.constructor = constructor;
+        match self.peek() {
+            Some(token) if token.token_type == TokenType::Init => {
+                if let Some(constructor) = self.parse_constructor(&class.name) {
+                    class.constructor = constructor;
+                }
+            }
+            Some(token) => {
+                self.errors.push(ParseError::MissingClassInit {
+                    symbol: class.name.clone(),
+                    span: token.span,
+                });
+                self.synchronize(SyncPoint::ClassBody);
+                return None;
+            }
+            None => {
+                self.errors.push(ParseError::UnexpectedEOF { span: None });
+                return None;
+            }
         }
 
         while self
@@ -76,31 +102,39 @@ impl ParserDecl for Parser {
 
         self.consume(TokenType::LeftBrace)?;
 
-        if let Some(_) = self.consume_optional(TokenType::Super) {
-            if let Some((_, _)) =
-                self.consume_two_optionals(TokenType::LeftParen, TokenType::RightParen)
-            {
-                constructor.super_call = Some(vec![Expr::Empty]);
-                self.consume(TokenType::Semicolon)?;
-            } else {
-                let super_expressions = self.parse_comma_expr();
-                constructor.super_call = Some(super_expressions);
-                self.consume(TokenType::Semicolon)?;
+        while let Some(token) = self.peek() {
+            if matches!(token.token_type, TokenType::RightBrace | TokenType::EOF) {
+                break;
             }
-        }
 
-        let mut block = BlockStmt{statements:vec![], span: self.current_span()? };
-
-        while let Some(stmt) = self.parse_stmt() {
-            block.statements.push(stmt);
-        }
-        
+            // the first `super(...)` is the parent constructor call, wherever
+            // it sits; sema checks that nothing comes before it
+            let is_super_call = token.token_type == TokenType::Super
+                && self.peek_ahead().is_some_and(|next| next.token_type == TokenType::LeftParen);
+            if is_super_call && constructor.super_call.is_none() {
+                self.advance();
+                let args = if let Some((_, _)) =
+                    self.consume_two_optionals(TokenType::LeftParen, TokenType::RightParen)
+                {
+                    vec![]
+                } else {
+                    self.parse_comma_expr()?
+                };
+                constructor.super_call = Some(SuperCall {
+                    args,
+                    span: token.span,
+                    position: constructor.statements.len(),
+                });
+                self.consume(TokenType::Semicolon)?;
+                continue;
+            }
 
-        if !block.statements.is_empty() {
-            constructor.statements = Some(crate::ast::Stmt::Block(block));
-        }
-        else {
-            constructor.statements = None;
+            if let Some(stmt) = self.parse_stmt() {
+                constructor.statements.push(stmt);
+            } else {
+                // if parsing stmt fails, advance to avoid infinite loop
+                self.advance();
+            }
         }
 
         self.consume(TokenType::RightBrace)?;
@@ -109,7 +143,10 @@ impl ParserDecl for Parser {
     }
 
     fn parse_method(&mut self) -> Option<MethDef> {
-        let mut method = MethDef::default();
+        let mut method = MethDef {
+            doc: self.take_doc(),
+            ..MethDef::default()
+        };
 
         self.consume(TokenType::Meth);
 
@@ -125,7 +162,9 @@ impl ParserDecl for Parser {
             method.return_type = return_type;
         }
 
-        method.statements = self.parse_stmt();
+        if let Some(statements) = self.parse_body() {
+            method.statements = statements;
+        }
 
         Some(method)
     }
@@ -161,21 +200,32 @@ impl ParserDecl for Parser {
     }
 
     fn parse_param(&mut self, _parent_name: &str, _parent_span: Span) -> Option<ParamDecl> {
-        let mut current_param = ParamDecl::default();
+        let mut current_param = ParamDecl {
+            span: self.current_span()?,
+            ..ParamDecl::default()
+        };
         let param_name = self.consume_identifier("Expected parameter name")?;
         current_param.name = param_name;
         self.consume(TokenType::Colon)?;
         let param_type = self.consume_type()?;
         current_param.param_type = param_type;
 
+        if self.consume_optional(TokenType::Assign).is_some() {
+            current_param.default = Some(self.parse_expr()?);
+        }
+
         Some(current_param)
     }
     
     fn parse_fun(&mut self) -> Option<FunDef> {
-        let mut fun = FunDef::default();
+        let mut fun = FunDef {
+            doc: self.take_doc(),
+            ..FunDef::default()
+        };
 
         self.consume(TokenType::Fun);
 
+        fun.span = self.current_span()?;
         if let Some(ident) = self.consume_identifier("function name") {
             fun.name = ident;
         }
@@ -189,7 +239,9 @@ impl ParserDecl for Parser {
             fun.return_type = return_type;
         }
 
-        fun.statements = self.parse_stmt();
+        if let Some(statements) = self.parse_body() {
+            fun.statements = statements;
+        }
 
         Some(fun)
     }
@@ -218,7 +270,8 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name == "Animal"
                 && extends == None
@@ -229,6 +282,18 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn test_super_call_after_statements() {
+        let class = parse_class("class Cat extends Animal { init() { let x: Int = 1; super(x); this.x = x; super.speak(); } }").unwrap();
+        let constructor = &class.constructor;
+        assert!(matches!(&constructor.super_call, Some(SuperCall { args, position: 1, .. }) if args.len() == 1));
+        assert_eq!(constructor.statements.len(), 3);
+
+        let (before, after) = constructor.split_at_super();
+        assert!(matches!(before, [Stmt::VarDeclWithAssign(_)]));
+        assert!(matches!(after, [Stmt::Assign(_), Stmt::Expr(_)]));
+    }
+
     #[test]
     fn test_minimal_inherited_class_decl() {
         let class = parse_class("class Cat extends Animal { init() {super();} }").unwrap();
@@ -239,7 +304,8 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name == "Cat"
                 && extends == Some("Animal".to_string())
@@ -268,7 +334,8 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name =="Rectangle"
                 &&extends == None
@@ -278,6 +345,44 @@ mod tests {
         ))
     }
 
+    fn get_class_errors(input: &str) -> Vec<ParseError> {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse();
+        parser.get_errors().to_vec()
+    }
+
+    #[test]
+    fn test_class_missing_init() {
+        let errors = get_class_errors("class Animal { meth speak() -> Int { return 0; } }");
+        assert!(errors.iter().any(|e| matches!(
+            e, ParseError::MissingClassInit { symbol, .. }
+            if symbol == "Animal"
+        )))
+    }
+
+    #[test]
+    fn test_class_missing_init_empty_body() {
+        let errors = get_class_errors("class Animal { let age: Int; }");
+        assert!(errors.iter().any(|e| matches!(
+            e, ParseError::MissingClassInit { symbol, .. }
+            if symbol == "Animal"
+        )))
+    }
+
+    #[test]
+    fn test_class_missing_init_recovers() {
+        let source = "class Animal { } class Cat { init() {} }";
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let entry = parser.parse().unwrap();
+        assert_eq!(parser.get_errors().len(), 1);
+        assert_eq!(entry.class_defs.len(), 1);
+        assert_eq!(entry.class_defs[0].name, "Cat");
+    }
+
     #[test]
     fn test_class_decl_with_params() {
         let class = parse_class("class Animal { init(voice: Str) {} }").unwrap();
@@ -288,7 +393,8 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name == "Animal"
                 && extends == None
@@ -320,13 +426,14 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name =="Rectangle"
                 &&extends == None
                 &&vars.len() == 2
                 && methods.len() == 0
-                &&matches!(&constructor, Constructor {params, statements, ..} if params.len() == 2 &&statements.is_some()) 
+                &&matches!(&constructor, Constructor {params, statements, ..} if params.len() == 2 &&!statements.is_empty()) 
         ))
     }
 
@@ -340,7 +447,8 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name == "Animal"
                 && extends == None
@@ -363,7 +471,8 @@ mod tests {
                 extends,
                 vars,
                 constructor,
-                methods
+                methods,
+                ..
             }
             if name == "Animal"
                 && extends == None
@@ -552,6 +661,18 @@ mod tests {
         parser.parse_fun()
     }
 
+    #[test]
+    fn test_function_default_params() {
+        let function = parse_function("f(x: Int, y: Int = 5 + 1) -> Int { return x + y; }").unwrap();
+        assert!(matches!(
+            function.params.as_slice(),
+            [
+                ParamDecl { name: x, default: None, .. },
+                ParamDecl { name: y, default: Some(Expr::Binary(_)), .. },
+            ] if x == "x" && y == "y"
+        ));
+    }
+
     #[test]
     fn test_minimal_function() {
         let function = parse_function("functionName() -> Void {}").unwrap();
@@ -1,5 +1,5 @@
+use crate::error::{diagnostic_json, print_diagnostic, print_error_list, Severity};
 use crate::lexer::Span;
-use colored::*;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Clone)]
@@ -7,6 +7,9 @@ pub enum ParseError {
     #[error("Expected method name for class '{symbol}' at {span}")]
     ExpectedMethName { symbol: String, span: Span },
 
+    #[error("Expected init constructor for class '{symbol}' at {span}")]
+    MissingClassInit { symbol: String, span: Span },
+
     #[error("Expected '{expected}' but found '{found}' at {}",  
     .span.map_or("unknown location".to_string(), |s| s.to_string()))]
     ExpectedButFound {
@@ -24,17 +27,38 @@ pub enum ParseError {
     #[error("Unexpected token at {symbol} at {span}")]
     UnexpectedToken { symbol: String, span: Span },
 
-    #[error("Unexpected EOF at {span:?}")]
+    #[error("Unexpected EOF at {}", .span.map_or("unknown location".to_string(), |s| s.to_string()))]
     UnexpectedEOF { span: Option<Span> },
+
+    #[error("'{keyword}' is a reserved keyword and can't be used as a name at {span}")]
+    ReservedKeyword { keyword: String, span: Span },
+
+    #[error("Comparisons can't be chained; add parentheses to say which comes first at {span}")]
+    ChainedComparison { span: Span },
+
+    #[error("Integer literal is too large (the maximum is {}) at {span}", i64::MAX)]
+    IntegerOverflow { span: Span },
+
+    #[error("A unary operator can't appear directly on the left of '**'; add parentheses to say which comes first at {span}")]
+    UnaryBeforePower { span: Span },
+
+    #[error("Expression is nested too deeply at {span}")]
+    NestingTooDeep { span: Span },
 }
 
 impl ParseError {
     pub fn get_span(&self) -> Option<&Span> {
         match self {
             Self::ExpectedMethName { span, .. }
+            | Self::MissingClassInit { span, .. }
             | Self::UnexpectedToken { span, .. }
             | Self::ExpectedExpressionAfterComma { span, .. }
-            | Self::ExpectedExpression { span, .. } => Some(span),
+            | Self::ExpectedExpression { span, .. }
+            | Self::ReservedKeyword { span, .. }
+            | Self::ChainedComparison { span }
+            | Self::IntegerOverflow { span }
+            | Self::UnaryBeforePower { span }
+            | Self::NestingTooDeep { span } => Some(span),
 
             Self::ExpectedButFound { span, .. } => span.as_ref(),
             Self::UnexpectedEOF { span } => span.as_ref(),
@@ -45,59 +69,25 @@ impl ParseError {
         match self {
             Self::ExpectedMethName { .. } => "E011",
             Self::ExpectedButFound { .. } => "E012",
+            Self::MissingClassInit { .. } => "E013",
             Self::UnexpectedEOF { .. } => "E009",
             Self::UnexpectedToken { .. } => "E020",
             Self::ExpectedExpressionAfterComma { .. } => "E024",
             Self::ExpectedExpression { .. } => "E025",
+            Self::ReservedKeyword { .. } => "E026",
+            Self::ChainedComparison { .. } => "E027",
+            Self::IntegerOverflow { .. } => "E028",
+            Self::UnaryBeforePower { .. } => "E029",
+            Self::NestingTooDeep { .. } => "E030",
         }
     }
 
     pub fn print_with_context(&self, source: &str) {
-        let error_code = self.get_code();
-
-        eprintln!(
-            "{}: {} {}",
-            "error".red().bold(),
-            error_code.yellow(),
-            self.to_string().white().bold()
-        );
-
-        // print source context if we have a valid span
-        if let Some(span) = self.get_span() {
-            let lines: Vec<&str> = source.lines().collect();
-
-            if span.line > 0 && span.line <= lines.len() {
-                let start_line = span.line.saturating_sub(1);
-                let end_line = std::cmp::min(span.line + 1, lines.len());
-
-                // print file location
-                eprintln!(
-                    "{} {}:{}:{}",
-                    "-->".blue().bold(),
-                    "input".cyan(),
-                    span.line,
-                    span.column
-                );
-
-                eprintln!("{}", "    |".blue().bold());
-
-                if start_line < span.line {
-                    print_context_line(start_line, lines[start_line.saturating_sub(1)]);
-                }
-
-                print_context_line(span.line, lines[span.line.saturating_sub(1)]);
-
-                let indicator = " ".repeat(span.column.saturating_sub(1)) + "^";
-
-                eprintln!("{} {}", "    |".blue().bold(), indicator.red().bold(),);
-
-                if end_line > span.line {
-                    print_context_line(end_line, lines[end_line.saturating_sub(1)]);
-                }
-            }
-        }
+        print_diagnostic(Severity::Error, self.get_code(), &self.to_string(), self.get_span(), source);
+    }
 
-        eprintln!();
+    pub fn to_json(&self) -> String {
+        diagnostic_json(Severity::Error, self.get_code(), &self.to_string(), self.get_span())
     }
 
     pub fn expected_but_found(expected: String, found: Option<String>, span: Option<Span>) -> Self {
@@ -109,69 +99,45 @@ impl ParseError {
     }
 }
 
-fn print_context_line(line_num: usize, content: &str) {
-    eprintln!("{} {}", format!("{:3} |", line_num).blue().bold(), content);
-}
-
 pub fn print_errors(errors: &[ParseError], source: &str) {
-    if errors.is_empty() {
-        return;
-    }
-
-    let error_count = errors.len();
-    let error_text = if error_count == 1 { "error" } else { "errors" };
-
-    eprintln!(
-        "\n{}",
-        format!("Found {} {}", error_count, error_text).red().bold()
-    );
-
-    for err in errors {
-        err.print_with_context(source);
-    }
-
-    eprintln!(
-        "{}: {} {}",
-        "error".red().bold(),
-        format!("aborting due to {} {}", error_count, error_text).bold(),
-        "😞".yellow()
-    );
-
-    eprintln!();
+    print_error_list(errors, source, ParseError::print_with_context);
 }
 
 #[cfg(test)]
 mod tests {
     use crate::lexer::Span;
 
-    use super::{print_context_line, print_errors, ParseError};
+    use super::{print_errors, ParseError};
+    use crate::error::print_context_line;
     
     #[test]
     fn test_error_codes() {
-        assert!(ParseError::UnexpectedEOF  {span: Some(Span{line:0, column:0}) }.get_code() == "E009");
-        assert!(ParseError::ExpectedMethName { symbol: "".to_string(), span: Span{line:0, column:0} }.get_code() == "E011");
-        assert!(ParseError::ExpectedButFound { expected: "".to_string(), found: "".to_string(), span: Some(Span{line:0, column:0}) }.get_code() == "E012");
-        assert!(ParseError::UnexpectedToken  { symbol: "".to_string(), span: Span{line:0, column:0} }.get_code() == "E020");
-        assert!(ParseError::ExpectedExpressionAfterComma { symbol: "".to_string(), span: Span{line:0, column:0} }.get_code() == "E024");
+        assert!(ParseError::UnexpectedEOF  {span: Some(Span::new(0, 0, 0, 0)) }.get_code() == "E009");
+        assert!(ParseError::ExpectedMethName { symbol: "".to_string(), span: Span::new(0, 0, 0, 0) }.get_code() == "E011");
+        assert!(ParseError::ExpectedButFound { expected: "".to_string(), found: "".to_string(), span: Some(Span::new(0, 0, 0, 0)) }.get_code() == "E012");
+        assert!(ParseError::MissingClassInit { symbol: "".to_string(), span: Span::new(0, 0, 0, 0) }.get_code() == "E013");
+        assert!(ParseError::UnexpectedToken  { symbol: "".to_string(), span: Span::new(0, 0, 0, 0) }.get_code() == "E020");
+        assert!(ParseError::ExpectedExpressionAfterComma { symbol: "".to_string(), span: Span::new(0, 0, 0, 0) }.get_code() == "E024");
     } 
 
+    #[test]
+    fn test_unexpected_eof_message() {
+        let error = ParseError::UnexpectedEOF { span: Some(Span::new(31, 31, 2, 17)) };
+        assert_eq!(error.to_string(), "Unexpected EOF at line 2, column 17");
+        assert_eq!(ParseError::UnexpectedEOF { span: None }.to_string(), "Unexpected EOF at unknown location");
+    }
+
     #[test]
     fn test_get_span() {
-        let binding = ParseError::ExpectedMethName { symbol: "".to_string(), span: Span{line:0, column:0} };
+        let binding = ParseError::ExpectedMethName { symbol: "".to_string(), span: Span::new(0, 0, 0, 0) };
         let span = binding.get_span().unwrap();
-        assert!(matches!(
-            span,
-            Span { line:0, column:0 }
-        ))
+        assert_eq!(*span, Span::new(0, 0, 0, 0))
     }
 
     #[test]
     fn test_get_span_ref() {
-        let span = ParseError::UnexpectedEOF  {span: Some(Span{line:0, column:0}) }.get_span().unwrap();
-        assert!(matches!(
-            span,
-            Span { line:0, column:0 }
-        ))
+        let span = ParseError::UnexpectedEOF  {span: Some(Span::new(0, 0, 0, 0)) }.get_span().copied().unwrap();
+        assert_eq!(span, Span::new(0, 0, 0, 0))
     }
 
     #[test]
@@ -179,7 +145,7 @@ mod tests {
         let error = ParseError::ExpectedButFound { 
             expected: "expected".to_string(), 
             found: "found".to_string(), 
-            span: Some(Span{line:2, column:0}) };
+            span: Some(Span::new(0, 0, 2, 0)) };
         error.print_with_context("test\nmulti-line\nerror");
     }
 
@@ -202,7 +168,7 @@ mod tests {
         let error1 = ParseError::ExpectedButFound { 
             expected: "expected".to_string(), 
             found: "found".to_string(), 
-            span: Some(Span{line:0, column:0}) };
+            span: Some(Span::new(0, 0, 0, 0)) };
         let error2 = ParseError::UnexpectedEOF { span: None };
 
         print_errors(&[error1,error2], "test");
@@ -213,13 +179,13 @@ mod tests {
 fn test_get_span_case() {
     let err = ParseError::UnexpectedToken {
         symbol: "foo".to_string(),
-        span: Span { line: 1, column: 1 }
+        span: Span::new(0, 0, 1, 1)
     };
     assert!(err.get_span().is_some());
 
     let err = ParseError::ExpectedExpressionAfterComma {
         symbol: ",".to_string(),
-        span: Span { line: 1, column: 1 }
+        span: Span::new(0, 0, 1, 1)
     };
     assert!(err.get_span().is_some());
 }
@@ -227,7 +193,7 @@ fn test_get_span_case() {
 #[test]
 fn test_expected_expression() {
     let err = ParseError::ExpectedExpression {
-        span: Span { line: 1, column: 1 }
+        span: Span::new(0, 0, 1, 1)
     };
     assert_eq!(err.get_code(), "E025");
 }
\ No newline at end of file
@@ -1,21 +1,27 @@
 use super::*;
 use crate::{
     ast::{
-        BinaryExpr, BooleanLiteral, Expr, Field, FunCall, IntegerLiteral, MethCall, NewExpr, PrintExpr, PrintlnExpr, StringLiteral, ThisExpr, UnaryExpr, Variable
+        AssertExpr, BinaryExpr, BinaryOp, BooleanLiteral, CharLiteral, Expr, Field, FunCall, IntegerLiteral, LambdaExpr, LenExpr, MethCall, NewExpr, NilExpr, PrintExpr, PrintlnExpr, StringLiteral, SuperExpr, ThisExpr, TypeNameOfExpr, UnaryExpr, Variable
     },
     lexer::TokenType,
 };
 
 pub trait ParserExpr {
     fn parse_expr(&mut self) -> Option<Expr>;
-    fn parse_comma_expr(&mut self) -> Vec<Expr>;
+    /// Parses `(exp, ...)`. Reports a missing `(` and returns `None`.
+    fn parse_comma_expr(&mut self) -> Option<Vec<Expr>>;
     fn parse_mult_expr(&mut self) -> Option<Expr>;
+    fn parse_power_expr(&mut self) -> Option<Expr>;
     fn parse_add_expr(&mut self) -> Option<Expr>;
     fn parse_call_expr(&mut self) -> Option<Expr>;
     fn parse_primary_expr(&mut self) -> Option<Expr>;
     fn parse_or_expr(&mut self) -> Option<Expr>;
     fn parse_and_expr(&mut self) -> Option<Expr>;
     fn parse_comparison_expr(&mut self) -> Option<Expr>;
+    fn parse_bit_or_expr(&mut self) -> Option<Expr>;
+    fn parse_bit_xor_expr(&mut self) -> Option<Expr>;
+    fn parse_bit_and_expr(&mut self) -> Option<Expr>;
+    fn parse_shift_expr(&mut self) -> Option<Expr>;
     fn parse_unary_expr(&mut self) -> Option<Expr>;
     fn parse_full_field_expr_name(&mut self, expr: Expr) -> String;
 }
@@ -26,61 +32,73 @@ impl ParserExpr for Parser {
     }
 
     fn parse_unary_expr(&mut self) -> Option<Expr> {
-        let token = self.peek()?;
-        let span = self.current_span()?;
-
-        match token.token_type {
-            TokenType::Not => {
+        let token = self.peek_ref()?;
+        let span = token.span;
+
+        let operator = match token.token_type {
+            TokenType::Not => crate::ast::UnaryOp::Not,
+            TokenType::Minus => crate::ast::UnaryOp::Negate,
+            TokenType::Plus => crate::ast::UnaryOp::Plus,
+            TokenType::Tilde => crate::ast::UnaryOp::BitNot,
+            _ => return self.parse_call_expr(),
+        };
+        self.advance();
+
+        // fold `-<literal>` straight into the literal: it's the only way to
+        // write `i64::MIN`, whose magnitude doesn't fit in an `i64`
+        if operator == crate::ast::UnaryOp::Negate {
+            if let Some(TokenType::IntegerLiteral(value)) = self.peek_ref().map(|token| &token.token_type) {
+                let value = value.wrapping_neg();
                 self.advance();
-                let unary_expr = self.parse_unary_expr()?;
-
-                Some(Expr::Unary(UnaryExpr {
-                    operator: crate::ast::UnaryOp::Not,
-                    expr: Box::new(unary_expr),
-                    span,
-                }))
+                return Some(Expr::IntegerLiteral(IntegerLiteral { value, span }));
             }
-            TokenType::Minus => {
-                self.advance();
-                let unary_expr = self.parse_unary_expr()?;
+        }
 
-                Some(Expr::Unary(UnaryExpr {
-                    operator: crate::ast::UnaryOp::Negate,
-                    expr: Box::new(unary_expr),
-                    span,
-                }))
-            }
-            TokenType::Plus => {
-                self.advance();
-                let unary_expr = self.parse_unary_expr()?;
+        let unary_expr = self.nested(span, |parser| parser.parse_unary_expr())?;
 
-                Some(Expr::Unary(UnaryExpr {
-                    operator: crate::ast::UnaryOp::Plus,
-                    expr: Box::new(unary_expr),
-                    span,
-                }))
-            }
-            _ => self.parse_call_expr(),
-        }
+        Some(Expr::Unary(UnaryExpr {
+            operator,
+            expr: Box::new(unary_expr),
+            span,
+        }))
     }
 
-    fn parse_comma_expr(&mut self) -> Vec<Expr> {
+    fn parse_comma_expr(&mut self) -> Option<Vec<Expr>> {
         let mut exprs = Vec::<Expr>::new();
-        self.consume(TokenType::LeftParen);
+        self.consume(TokenType::LeftParen)?;
 
-        if let Some(expr) = self.parse_expr() {
-            exprs.push(expr);
+        let start = self.checkpoint();
+        let reported = self.errors.len();
+        let span = self.current_span()?;
+        match self.nested(span, |parser| parser.parse_expr()) {
+            Some(expr) => exprs.push(expr),
+            // step over a token no argument can start with, so `foo(])` still
+            // reaches its `)`
+            None if self.position == start => {
+                if let Some(token) = self.peek().filter(|token| {
+                    !matches!(token.token_type, TokenType::Comma | TokenType::RightParen | TokenType::EOF)
+                }) {
+                    if self.errors.len() == reported {
+                        self.errors.push(ParseError::UnexpectedToken {
+                            symbol: token.token_type.to_string(),
+                            span: token.span,
+                        });
+                    }
+                    self.advance();
+                }
+            }
+            None => {}
         }
 
         while let Some(token) = self.peek() {
             match token.token_type {
                 TokenType::RightParen => {
                     self.advance();
-                    return exprs;
+                    return Some(exprs);
                 }
                 TokenType::Comma => {
                     self.advance();
-                    if let Some(expr) = self.parse_expr() {
+                    if let Some(expr) = self.nested(token.span, |parser| parser.parse_expr()) {
                         exprs.push(expr);
                     } else {
                         self.errors.push(ParseError::ExpectedExpressionAfterComma {
@@ -98,33 +116,35 @@ impl ParserExpr for Parser {
                 }
             }
         }
-        exprs
+        Some(exprs)
     }
 
     fn parse_add_expr(&mut self) -> Option<Expr> {
         let mut left = self.parse_mult_expr()?;
+        let mut links = 0;
 
-        while let Some(token) = self.peek() {
-            match token.token_type {
-                TokenType::Plus | TokenType::Minus => {
-                    self.advance();
-
-                    if let Some(right) = self.parse_mult_expr() {
-                        let span = self.current_span()?;
-                        left = Expr::Binary(BinaryExpr {
-                            left: Box::new(left),
-                            operator: token.token_type.which_binary_op(),
-                            right: Box::new(right),
-                            span,
-                        })
-                    } else {
-                        let span = self.current_span()?;
-                        self.errors
-                            .push(ParseError::UnexpectedEOF { span: Some(span) });
-                        return None;
-                    }
-                }
+        while let Some(token) = self.peek_ref() {
+            let span = token.span;
+            let operator = match token.token_type {
+                TokenType::Plus | TokenType::Minus => token.token_type.which_binary_op(),
                 _ => break,
+            };
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_mult_expr()) {
+                let span = self.current_span()?;
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::UnexpectedEOF { span: Some(operand) });
+                return None;
             }
         }
 
@@ -132,33 +152,42 @@ impl ParserExpr for Parser {
     }
 
     fn parse_comparison_expr(&mut self) -> Option<Expr> {
-        let mut left = self.parse_add_expr()?;
+        let mut left = self.parse_shift_expr()?;
+        let mut links = 0;
+        let mut chained = false;
 
-        while let Some(token) = self.peek() {
-            match token.token_type {
+        while let Some(token) = self.peek_ref() {
+            let operator = match token.token_type {
                 TokenType::Less
                 | TokenType::LessEqual
                 | TokenType::Greater
                 | TokenType::GreaterEqual
                 | TokenType::Equal
-                | TokenType::NotEqual => {
-                    self.advance();
-                    if let Some(right) = self.parse_add_expr() {
-                        let span = self.current_span()?;
-                        left = Expr::Binary(BinaryExpr {
-                            left: Box::new(left),
-                            operator: token.token_type.which_binary_op(),
-                            right: Box::new(right),
-                            span,
-                        })
-                    } else {
-                        let span = self.current_span()?;
-                        self.errors
-                            .push(ParseError::UnexpectedEOF { span: Some(span) });
-                        return None;
-                    }
-                }
+                | TokenType::NotEqual => token.token_type.which_binary_op(),
                 _ => break,
+            };
+            // `1 < 2 < 3` would compare a boolean against 3; the tree is still
+            // built so parsing carries on past the mistake
+            let span = token.span;
+            if std::mem::replace(&mut chained, true) {
+                self.errors.push(ParseError::ChainedComparison { span });
+            }
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_shift_expr()) {
+                let span = self.current_span()?;
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::UnexpectedEOF { span: Some(operand) });
+                return None;
             }
         }
 
@@ -166,26 +195,28 @@ impl ParserExpr for Parser {
     }
 
     fn parse_and_expr(&mut self) -> Option<Expr> {
-        let mut left = self.parse_comparison_expr()?;
+        let mut left = self.parse_bit_or_expr()?;
+        let mut links = 0;
 
-        while let Some(token) = self.peek() {
-            match token.token_type {
-                TokenType::And => {
-                    self.advance();
-                    if let Some(right) = self.parse_comparison_expr() {
-                        let span = token.span.clone();
-                        left = Expr::Binary(BinaryExpr {
-                            left: Box::new(left),
-                            operator: token.token_type.which_binary_op(),
-                            right: Box::new(right),
-                            span,
-                        })
-                    } else {
-                        let span = self.current_span()?;
-                        self.errors.push(ParseError::ExpectedExpression { span });
-                    }
-                }
-                _ => break,
+        while let Some(token) = self.peek_ref() {
+            if token.token_type != TokenType::And {
+                break;
+            }
+            let span = token.span;
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_bit_or_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator: BinaryOp::And,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::ExpectedExpression { span: operand });
             }
         }
 
@@ -194,73 +225,238 @@ impl ParserExpr for Parser {
 
     fn parse_or_expr(&mut self) -> Option<Expr> {
         let mut left = self.parse_and_expr()?;
+        let mut links = 0;
 
-        while let Some(token) = self.peek() {
-            match token.token_type {
-                TokenType::Or => {
-                    self.advance();
-                    if let Some(right) = self.parse_and_expr() {
-                        let span = token.span.clone();
-                        left = Expr::Binary(BinaryExpr {
-                            left: Box::new(left),
-                            operator: token.token_type.which_binary_op(),
-                            right: Box::new(right),
-                            span,
-                        })
-                    } else {
-                        let span = self.current_span()?;
-                        self.errors.push(ParseError::ExpectedExpression { span });
-                    }
-                }
+        while let Some(token) = self.peek_ref() {
+            if token.token_type != TokenType::Or {
+                break;
+            }
+            let span = token.span;
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_and_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator: BinaryOp::Or,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::ExpectedExpression { span: operand });
+            }
+        }
+
+        Some(left)
+    }
+
+    // bitwise operators bind like C's: looser than comparisons, tighter
+    // than `&&`, with `|` loosest and `&` tightest
+    fn parse_bit_or_expr(&mut self) -> Option<Expr> {
+        let mut left = self.parse_bit_xor_expr()?;
+        let mut links = 0;
+
+        while let Some(token) = self.peek_ref() {
+            let span = token.span;
+            let operator = match token.token_type {
+                TokenType::BitOr => token.token_type.which_binary_op(),
                 _ => break,
+            };
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_bit_xor_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::ExpectedExpression { span: operand });
+                return None;
             }
         }
+        Some(left)
+    }
 
+    fn parse_bit_xor_expr(&mut self) -> Option<Expr> {
+        let mut left = self.parse_bit_and_expr()?;
+        let mut links = 0;
+
+        while let Some(token) = self.peek_ref() {
+            let span = token.span;
+            let operator = match token.token_type {
+                TokenType::Caret => token.token_type.which_binary_op(),
+                _ => break,
+            };
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_bit_and_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::ExpectedExpression { span: operand });
+                return None;
+            }
+        }
         Some(left)
     }
 
-    fn parse_mult_expr(&mut self) -> Option<Expr> {
-        let mut left = self.parse_unary_expr()?;
+    fn parse_bit_and_expr(&mut self) -> Option<Expr> {
+        let mut left = self.parse_comparison_expr()?;
+        let mut links = 0;
 
-        while let Some(token) = self.peek() {
-            let span = token.span.clone();
-            match token.token_type {
-                TokenType::Star | TokenType::Slash => {
-                    self.advance();
+        while let Some(token) = self.peek_ref() {
+            let span = token.span;
+            let operator = match token.token_type {
+                TokenType::BitAnd => token.token_type.which_binary_op(),
+                _ => break,
+            };
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_comparison_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::ExpectedExpression { span: operand });
+                return None;
+            }
+        }
+        Some(left)
+    }
 
-                    if let Some(right) = self.parse_unary_expr() {
-                        left = Expr::Binary(BinaryExpr {
-                            left: Box::new(left),
-                            operator: token.token_type.which_binary_op(),
-                            right: Box::new(right),
-                            span,
-                        })
-                    } else {
-                        self.errors
-                            .push(ParseError::UnexpectedEOF { span: Some(span) });
-                        return None;
-                    }
-                }
+    // shifts sit between addition and comparison, so `1 << n + 1` shifts by `n + 1`
+    fn parse_shift_expr(&mut self) -> Option<Expr> {
+        let mut left = self.parse_add_expr()?;
+        let mut links = 0;
+
+        while let Some(token) = self.peek_ref() {
+            let span = token.span;
+            let operator = match token.token_type {
+                TokenType::Shl | TokenType::Shr => token.token_type.which_binary_op(),
+                _ => break,
+            };
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_add_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::ExpectedExpression { span: operand });
+                return None;
+            }
+        }
+        Some(left)
+    }
+
+    fn parse_mult_expr(&mut self) -> Option<Expr> {
+        let mut left = self.parse_power_expr()?;
+        let mut links = 0;
+
+        while let Some(token) = self.peek_ref() {
+            let span = token.span;
+            let operator = match token.token_type {
+                TokenType::Star | TokenType::Slash => token.token_type.which_binary_op(),
                 _ => break,
+            };
+            self.check_chain(span, links)?;
+            self.advance();
+            let operand = self.span_after_previous();
+
+            if let Some(right) = self.linked(links + 1, |parser| parser.parse_power_expr()) {
+                left = Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                });
+                links += 1;
+            } else {
+                self.report_missing(ParseError::UnexpectedEOF { span: Some(operand) });
+                return None;
             }
         }
         Some(left)
     }
 
+    fn parse_power_expr(&mut self) -> Option<Expr> {
+        let left = self.parse_unary_expr()?;
+
+        let span = match self.peek_ref() {
+            Some(token) if token.token_type == TokenType::StarStar => token.span,
+            _ => return Some(left),
+        };
+        // `-2 ** 2` reads as either `(-2) ** 2` or `-(2 ** 2)`, so JS rejects
+        // it; a negative literal can only come from a folded `-`
+        let unary = match &left {
+            Expr::Unary(_) => true,
+            Expr::IntegerLiteral(literal) => literal.value < 0,
+            _ => false,
+        };
+        if unary {
+            self.errors.push(ParseError::UnaryBeforePower { span });
+        }
+        self.check_chain(span, 0)?;
+        self.advance();
+        let operand = self.span_after_previous();
+
+        // recursing on the right operand makes `**` right-associative
+        if let Some(right) = self.linked(1, |parser| parser.parse_power_expr()) {
+            Some(Expr::Binary(BinaryExpr {
+                left: Box::new(left),
+                operator: BinaryOp::Power,
+                right: Box::new(right),
+                span,
+            }))
+        } else {
+            self.report_missing(ParseError::UnexpectedEOF { span: Some(operand) });
+            None
+        }
+    }
+
     fn parse_call_expr(&mut self) -> Option<Expr> {
         let mut expr = self.parse_primary_expr()?;
+        let mut links = 0;
 
         while let Some(token) = self.peek() {
             let span = self.current_span()?;
             match token.token_type {
                 TokenType::Dot => {
+                    self.check_chain(span, links)?;
+                    links += 1;
                     self.advance();
                     let ident = self.consume_identifier("method name")?;
                     match self.peek() {
                         Some(token) => {
                             match token.token_type {
                                 TokenType::LeftParen => {
-                                    let args = self.parse_comma_expr();
+                                    let args = self.linked(links, |parser| parser.parse_comma_expr())?;
                                     expr = Expr::MethCall(MethCall {
                                         object: Box::new(expr),
                                         meth: ident,
@@ -300,6 +496,15 @@ impl ParserExpr for Parser {
                         span,
                     }));
                 }
+                TokenType::CharLiteral(value) => {
+                    self.advance();
+                    return Some(Expr::CharLiteral(CharLiteral { value, span }));
+                }
+                TokenType::IntegerLiteral(i64::MIN) => {
+                    self.advance();
+                    self.errors.push(ParseError::IntegerOverflow { span });
+                    return None;
+                }
                 TokenType::IntegerLiteral(int_literal) => {
                     self.advance();
                     return Some(Expr::IntegerLiteral(IntegerLiteral {
@@ -311,6 +516,37 @@ impl ParserExpr for Parser {
                     self.advance();
                     return Some(Expr::This(ThisExpr { span }));
                 }
+                TokenType::Super => {
+                    self.advance();
+
+                    match self.peek() {
+                        // `super.meth(...)` is handled by the call expression loop
+                        Some(next) if next.token_type == TokenType::Dot => {
+                            return Some(Expr::Super(SuperExpr { span }));
+                        }
+                        Some(next) if next.token_type == TokenType::LeftParen => {
+                            let args = self.parse_comma_expr()?;
+                            return Some(Expr::FunCall(FunCall {
+                                callee: "super".to_string(),
+                                args,
+                                span,
+                            }));
+                        }
+                        Some(next) => {
+                            self.errors.push(ParseError::ExpectedButFound {
+                                expected: "'.' or '(' after super".to_string(),
+                                found: next.token_type.to_string(),
+                                span: Some(next.span),
+                            });
+                            return None;
+                        }
+                        None => {
+                            self.errors
+                                .push(ParseError::UnexpectedEOF { span: Some(span) });
+                            return None;
+                        }
+                    }
+                }
                 TokenType::True => {
                     self.advance();
                     return Some(Expr::BooleanLiteral(BooleanLiteral { value: true, span }));
@@ -319,25 +555,73 @@ impl ParserExpr for Parser {
                     self.advance();
                     return Some(Expr::BooleanLiteral(BooleanLiteral { value: false, span }));
                 }
+                TokenType::Nil => {
+                    self.advance();
+                    return Some(Expr::Nil(NilExpr { span }));
+                }
                 TokenType::Println => {
                     self.advance();
-                    self.consume(TokenType::LeftParen)?;
-                    let expr = self.parse_expr()?;
-                    self.consume(TokenType::RightParen)?;
-                    return Some(Expr::Println(PrintlnExpr {
-                        arg: Box::new(expr),
+                    let args = self.parse_comma_expr()?;
+                    return Some(Expr::Println(PrintlnExpr { args, span }));
+                }
+                TokenType::Print => {
+                    self.advance();
+                    let args = self.parse_comma_expr()?;
+                    return Some(Expr::Print(PrintExpr { args, span }));
+                }
+                TokenType::Assert => {
+                    self.advance();
+                    let mut args = self.parse_comma_expr()?.into_iter();
+                    let (Some(cond), message, None) = (args.next(), args.next(), args.next()) else {
+                        self.errors.push(ParseError::ExpectedButFound {
+                            expected: "a condition and an optional message".to_string(),
+                            found: "a different number of arguments to 'assert'".to_string(),
+                            span: Some(span),
+                        });
+                        return None;
+                    };
+                    return Some(Expr::Assert(AssertExpr {
+                        cond: Box::new(cond),
+                        message: message.map(Box::new),
                         span,
                     }));
                 }
-                TokenType::Print => {
+                TokenType::Len => {
                     self.advance();
-                    self.consume(TokenType::LeftParen)?;
-                    let expr = self.parse_expr()?;
-                    self.consume(TokenType::RightParen)?;
-
-                    // return print with expr
-                    return Some(Expr::Print(PrintExpr {
-                        arg: Box::new(expr),
+                    let mut args = self.parse_comma_expr()?.into_iter();
+                    let (Some(arg), None) = (args.next(), args.next()) else {
+                        self.errors.push(ParseError::ExpectedButFound {
+                            expected: "a single argument".to_string(),
+                            found: "a different number of arguments to 'len'".to_string(),
+                            span: Some(span),
+                        });
+                        return None;
+                    };
+                    return Some(Expr::Len(LenExpr { arg: Box::new(arg), span }));
+                }
+                TokenType::TypeNameOf => {
+                    self.advance();
+                    let mut args = self.parse_comma_expr()?.into_iter();
+                    let (Some(arg), None) = (args.next(), args.next()) else {
+                        self.errors.push(ParseError::ExpectedButFound {
+                            expected: "a single argument".to_string(),
+                            found: "a different number of arguments to 'typeName'".to_string(),
+                            span: Some(span),
+                        });
+                        return None;
+                    };
+                    return Some(Expr::TypeNameOf(TypeNameOfExpr { arg: Box::new(arg), span }));
+                }
+                TokenType::Fun => {
+                    self.advance();
+                    let params = self.parse_comma_param_decl("lambda")?;
+                    self.consume(TokenType::Arrow)?;
+                    let return_type = self.consume_type()?;
+                    let body = self.nested(span, |parser| parser.parse_body())?;
+                    return Some(Expr::Lambda(LambdaExpr {
+                        params,
+                        return_type,
+                        body,
                         span,
                     }));
                 }
@@ -347,7 +631,7 @@ impl ParserExpr for Parser {
                     if let Some(token) = self.peek() {
                         if let TokenType::Identifier(class_name) = token.token_type.clone() {
                             self.advance(); // Consume the identifier
-                            let args = self.parse_comma_expr();
+                            let args = self.parse_comma_expr()?;
                             return Some(Expr::New(NewExpr {
                                 class_name,
                                 args,
@@ -373,8 +657,7 @@ impl ParserExpr for Parser {
                     // check if a function call
                     if let Some(token) = self.peek() {
                         if token.token_type == TokenType::LeftParen {
-                            let args = self.parse_comma_expr();
-                            let span = self.current_span()?;
+                            let args = self.parse_comma_expr()?;
                             return Some(Expr::FunCall(FunCall {
                                 callee: name,
                                 args,
@@ -388,7 +671,7 @@ impl ParserExpr for Parser {
                 }
                 TokenType::LeftParen => {
                     self.advance();
-                    let expr = self.parse_expr()?;
+                    let expr = self.nested(span, |parser| parser.parse_expr())?;
                     let span = token.span.clone();
 
                     if let Some(token) = self.peek() {
@@ -416,6 +699,7 @@ impl ParserExpr for Parser {
             Expr::Variable(variable) => variable.name,
             Expr::Field(field) => [self.parse_full_field_expr_name(*field.object),field.field].join("."),
             Expr::This(_) => "this".to_string(),
+            Expr::Super(_) => "super".to_string(),
             _ => "".to_string()
         }
     }
@@ -438,10 +722,32 @@ mod tests {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize().unwrap();
         let mut parser = Parser::new(tokens);
-        parser.parse_expr();
+        crate::with_stack(|| {
+            parser.parse_expr();
+        });
         parser.get_errors().to_vec()
     }
 
+    #[test]
+    fn test_negative_literals() {
+        assert!(matches!(
+            parse_expr("-9223372036854775808").unwrap(),
+            Expr::IntegerLiteral(IntegerLiteral { value: i64::MIN, .. })
+        ));
+        assert!(matches!(
+            parse_expr("-5").unwrap(),
+            Expr::IntegerLiteral(IntegerLiteral { value: -5, .. })
+        ));
+        assert!(matches!(
+            parse_expr("-x").unwrap(),
+            Expr::Unary(UnaryExpr { operator: UnaryOp::Negate, .. })
+        ));
+
+        // the magnitude alone only lexes after a `-`, and here that's a subtraction
+        let errors = get_expression_errors("1 - 9223372036854775808");
+        assert!(matches!(errors.as_slice(), [ParseError::IntegerOverflow { .. }, ..]));
+    }
+
     #[test]
     fn test_primary_expressions() {
         // Test literals
@@ -465,6 +771,8 @@ mod tests {
             Expr::BooleanLiteral(BooleanLiteral { value: false, .. })
         ));
 
+        assert!(matches!(parse_expr("nil").unwrap(), Expr::Nil(NilExpr { .. })));
+
         // Test this
         assert!(matches!(
             parse_expr("this").unwrap(),
@@ -527,6 +835,25 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn test_super_method_calls() {
+        let expr = parse_expr("super.speak()").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::MethCall(MethCall { object, meth, args, .. })
+            if meth == "speak"
+                && args.is_empty()
+                && matches!(&*object, Expr::Super(SuperExpr { .. }))
+        ));
+
+        let expr = parse_expr("super.move(1, 2)").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::MethCall(MethCall { object, args, .. })
+            if args.len() == 2 && matches!(&*object, Expr::Super(SuperExpr { .. }))
+        ));
+    }
+
     #[test]
     fn test_binary_expressions() {
         // Test addition
@@ -564,23 +891,322 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_power_expressions() {
+        // Test power binds tighter than multiplication
+        let expr = parse_expr("2 * 3 ** 2").unwrap();
+        if let Expr::Binary(BinaryExpr {
+            operator: BinaryOp::Multiply,
+            right,
+            ..
+        }) = expr
+        {
+            assert!(matches!(
+                *right,
+                Expr::Binary(BinaryExpr {
+                    operator: BinaryOp::Power,
+                    ..
+                })
+            ));
+        } else {
+            panic!("Expected multiplication expression");
+        }
+
+        // Test power is right-associative
+        let expr = parse_expr("2 ** 3 ** 2").unwrap();
+        if let Expr::Binary(BinaryExpr {
+            operator: BinaryOp::Power,
+            left,
+            right,
+            ..
+        }) = expr
+        {
+            assert!(matches!(
+                *left,
+                Expr::IntegerLiteral(IntegerLiteral { value: 2, .. })
+            ));
+            assert!(matches!(
+                *right,
+                Expr::Binary(BinaryExpr {
+                    operator: BinaryOp::Power,
+                    ..
+                })
+            ));
+        } else {
+            panic!("Expected power expression");
+        }
+    }
+
+    #[test]
+    fn test_bitwise_expressions() {
+        for (source, operator) in [
+            ("a & b", BinaryOp::BitAnd),
+            ("a | b", BinaryOp::BitOr),
+            ("a ^ b", BinaryOp::BitXor),
+            ("a << b", BinaryOp::ShiftLeft),
+            ("a >> b", BinaryOp::ShiftRight),
+        ] {
+            let expr = parse_expr(source).unwrap();
+            assert!(matches!(expr, Expr::Binary(BinaryExpr { operator: op, .. }) if op == operator), "{}", source);
+        }
+
+        let expr = parse_expr("~a").unwrap();
+        assert!(matches!(expr, Expr::Unary(UnaryExpr { operator: UnaryOp::BitNot, .. })));
+    }
+
+    #[test]
+    fn test_bitwise_precedence() {
+        // `|` binds loosest, then `^`, then `&`
+        let expr = parse_expr("a | b ^ c & d").unwrap();
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::BitOr, right, .. }) = expr else {
+            panic!("Expected bitwise or at the root");
+        };
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::BitXor, right, .. }) = *right else {
+            panic!("Expected bitwise xor under the or");
+        };
+        assert!(matches!(*right, Expr::Binary(BinaryExpr { operator: BinaryOp::BitAnd, .. })));
+
+        // comparisons bind tighter than `&`, and `&&` looser
+        let expr = parse_expr("a & b == c && d").unwrap();
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::And, left, .. }) = expr else {
+            panic!("Expected logical and at the root");
+        };
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::BitAnd, right, .. }) = *left else {
+            panic!("Expected bitwise and under the logical and");
+        };
+        assert!(matches!(*right, Expr::Binary(BinaryExpr { operator: BinaryOp::Equal, .. })));
+
+        // shifts bind looser than addition but tighter than comparisons
+        let expr = parse_expr("1 << n + 1 < m").unwrap();
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::Less, left, .. }) = expr else {
+            panic!("Expected comparison at the root");
+        };
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::ShiftLeft, right, .. }) = *left else {
+            panic!("Expected shift under the comparison");
+        };
+        assert!(matches!(*right, Expr::Binary(BinaryExpr { operator: BinaryOp::Add, .. })));
+    }
+
+    #[test]
+    fn test_unary_precedence() {
+        // unary operators bind tighter than any binary operator
+        let expr = parse_expr("-a * b").unwrap();
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::Multiply, left, .. }) = expr else {
+            panic!("Expected multiplication at the root");
+        };
+        assert!(matches!(*left, Expr::Unary(UnaryExpr { operator: UnaryOp::Negate, .. })));
+
+        let expr = parse_expr("-a + b").unwrap();
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::Add, left, .. }) = expr else {
+            panic!("Expected addition at the root");
+        };
+        assert!(matches!(*left, Expr::Unary(UnaryExpr { operator: UnaryOp::Negate, .. })));
+
+        let expr = parse_expr("!a && b").unwrap();
+        let Expr::Binary(BinaryExpr { operator: BinaryOp::And, left, .. }) = expr else {
+            panic!("Expected logical and at the root");
+        };
+        assert!(matches!(*left, Expr::Unary(UnaryExpr { operator: UnaryOp::Not, .. })));
+    }
+
+    #[test]
+    fn test_unary_before_power() {
+        for input in ["-2 ** 2", "-a ** 2", "!a ** 2", "2 ** -2 ** 2"] {
+            assert!(
+                matches!(get_expression_errors(input).as_slice(), [ParseError::UnaryBeforePower { .. }]),
+                "{input}"
+            );
+        }
+
+        for input in ["(-2) ** 2", "-(2 ** 2)", "2 ** -2", "-a * 2 ** 2"] {
+            assert!(get_expression_errors(input).is_empty(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_nesting_too_deep() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(get_expression_errors(&nested(DEFAULT_MAX_DEPTH)).is_empty());
+
+        let errors = get_expression_errors(&nested(50_000));
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::NestingTooDeep { span }] if span.column == DEFAULT_MAX_DEPTH + 1
+        ));
+
+        let tokens = Lexer::new(&nested(4)).tokenize().unwrap();
+        let mut parser = Parser::new(tokens).with_max_depth(3);
+        assert!(parser.parse_expr().is_none());
+        assert!(matches!(parser.get_errors(), [ParseError::NestingTooDeep { .. }]));
+    }
+
+    #[test]
+    fn test_nesting_too_deep_without_parentheses() {
+        let unary = |depth: usize| format!("{}1", "-!".repeat(depth / 2));
+        let calls = |depth: usize| format!("{}1{}", "f(".repeat(depth), ")".repeat(depth));
+        let chain = |length: usize| vec!["1"; length].join(" + ");
+        let powers = |length: usize| vec!["2"; length].join(" ** ");
+        let meths = |length: usize| format!("a{}", ".b()".repeat(length));
+
+        assert!(get_expression_errors(&unary(DEFAULT_MAX_DEPTH)).is_empty());
+        assert!(get_expression_errors(&calls(DEFAULT_MAX_DEPTH)).is_empty());
+        for input in [chain(DEFAULT_MAX_CHAIN), powers(DEFAULT_MAX_CHAIN), meths(DEFAULT_MAX_CHAIN)] {
+            assert!(get_expression_errors(&input).is_empty());
+        }
+
+        // one error each, however long the rest of the input runs on
+        for input in [
+            unary(50_000),
+            calls(50_000),
+            chain(50_000),
+            powers(50_000),
+            meths(50_000),
+            format!("1 + 1 + ({})", chain(DEFAULT_MAX_CHAIN)),
+        ] {
+            assert!(
+                matches!(get_expression_errors(&input).as_slice(), [ParseError::NestingTooDeep { .. }]),
+                "{}",
+                &input[..20]
+            );
+        }
+    }
+
+    #[test]
+    fn test_chained_comparison() {
+        let errors = get_expression_errors("1 < 2 < 3");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::ChainedComparison { span }] if span.column == 7
+        ));
+
+        assert!(get_expression_errors("(1 < 2) == true").is_empty());
+        assert!(get_expression_errors("a == b && b != c").is_empty());
+    }
+
     #[test]
     fn test_print_expressions() {
         // Test println
         let expr = parse_expr("println(42)").unwrap();
         assert!(matches!(
             expr,
-            Expr::Println(PrintlnExpr { arg, .. })
-            if matches!(&*arg, Expr::IntegerLiteral(IntegerLiteral { value: 42, .. }))
+            Expr::Println(PrintlnExpr { args, .. })
+            if matches!(args.as_slice(), [Expr::IntegerLiteral(IntegerLiteral { value: 42, .. })])
         ));
 
         // Test print
         let expr = parse_expr("print(\"hello\")").unwrap();
         assert!(matches!(
             expr,
-            Expr::Print(PrintExpr { arg, .. })
-            if matches!(&*arg, Expr::StringLiteral(StringLiteral { value, .. }) if value == "hello")
+            Expr::Print(PrintExpr { args, .. })
+            if matches!(args.as_slice(), [Expr::StringLiteral(StringLiteral { value, .. })] if value == "hello")
+        ));
+
+        let expr = parse_expr("println()").unwrap();
+        assert!(matches!(expr, Expr::Println(PrintlnExpr { args, .. }) if args.is_empty()));
+
+        let expr = parse_expr("println(\"x =\", x)").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Println(PrintlnExpr { args, .. })
+            if matches!(args.as_slice(), [Expr::StringLiteral(_), Expr::Variable(_)])
+        ));
+    }
+
+    #[test]
+    fn test_assert_expressions() {
+        let expr = parse_expr("assert(x > 0)").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Assert(AssertExpr { cond, message: None, .. })
+            if matches!(*cond, Expr::Binary(BinaryExpr { operator: BinaryOp::Greater, .. }))
+        ));
+
+        let expr = parse_expr("assert(ok, \"not ok\")").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Assert(AssertExpr { cond, message: Some(message), .. })
+            if matches!(*cond, Expr::Variable(_))
+                && matches!(*message, Expr::StringLiteral(StringLiteral { ref value, .. }) if value == "not ok")
+        ));
+
+        assert!(matches!(
+            get_expression_errors("assert()").as_slice(),
+            [ParseError::ExpectedButFound { .. }]
         ));
+        assert!(!get_expression_errors("assert(a, b, c)").is_empty());
+    }
+
+    #[test]
+    fn test_stray_token_in_arguments() {
+        let errors = get_expression_errors("foo(])");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::UnexpectedToken { symbol, .. }] if symbol == "]"
+        ));
+
+        let tokens = Lexer::new("foo(]);\nprintln(1);").tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let entry = parser.parse().unwrap();
+        assert_eq!(parser.get_errors().len(), 1);
+        assert_eq!(entry.statements.len(), 2);
+    }
+
+    #[test]
+    fn test_call_without_parens() {
+        // a method name with no `(` is a field access, not a call with no arguments
+        let expr = parse_expr("obj.method").unwrap();
+        assert!(matches!(expr, Expr::Field(Field { ref field, .. }) if field == "method"));
+        assert!(get_expression_errors("obj.method").is_empty());
+
+        for source in ["new Point", "println 5", "len s"] {
+            assert!(
+                matches!(
+                    get_expression_errors(source).as_slice(),
+                    [ParseError::ExpectedButFound { expected, .. }] if expected == "("
+                ),
+                "{}",
+                source
+            );
+        }
+        assert!(parse_expr("new Point;").is_none());
+    }
+
+    #[test]
+    fn test_len_expressions() {
+        let expr = parse_expr("len(a + b) * 2").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Binary(BinaryExpr { left, operator: BinaryOp::Multiply, .. })
+            if matches!(&*left, Expr::Len(LenExpr { arg, .. }) if matches!(**arg, Expr::Binary(_)))
+        ));
+
+        assert!(matches!(
+            get_expression_errors("len()").as_slice(),
+            [ParseError::ExpectedButFound { .. }]
+        ));
+        assert!(!get_expression_errors("len(a, b)").is_empty());
+    }
+
+    #[test]
+    fn test_lambda_expressions() {
+        let expr = parse_expr("fun (x: Int) -> Int { return x + 1; }").unwrap();
+        let Expr::Lambda(LambdaExpr { params, return_type, body, .. }) = expr else {
+            panic!("expected a lambda, got {:?}", expr);
+        };
+        assert!(matches!(params.as_slice(), [ParamDecl { name, param_type: TypeName::Int, .. }] if name == "x"));
+        assert_eq!(return_type, TypeName::Int);
+        assert!(matches!(body.as_slice(), [Stmt::Return(_)]));
+
+        // passed straight to a function as a callback
+        let expr = parse_expr("apply(fun () -> Void {}, 3)").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::FunCall(FunCall { callee, args, .. })
+            if callee == "apply" && matches!(args.as_slice(), [Expr::Lambda(_), Expr::IntegerLiteral(_)])
+        ));
+
+        assert!(!get_expression_errors("fun (x: Int) { return x; }").is_empty());
     }
 
     #[test]
@@ -700,18 +1326,52 @@ mod tests {
         )))
     }
 
+    #[test]
+    fn test_bare_super_error() {
+        let errors = get_expression_errors("super;");
+        assert!(errors.iter().any(|e| matches!(
+            e, ParseError::ExpectedButFound { expected, found, .. }
+            if expected.contains("super") && found == ";"
+        )));
+
+        let errors = get_expression_errors("super");
+        assert!(!errors.is_empty());
+    }
+
     #[test]
     fn test_add_expr() {
         let errors = get_expression_errors("5 +");
         assert!(errors.iter().any(|e| matches!(e, ParseError::UnexpectedEOF { .. })));
     }
 
+    #[test]
+    fn test_report_missing_points_past_operator() {
+        // just after the `+`, not at the end of file
+        let errors = get_expression_errors("5 +");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::UnexpectedEOF { span: Some(Span { line: 1, column: 4, start: 3, end: 3 }) }]
+        ));
+
+        let errors = get_expression_errors("a &&\n\n  ");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::ExpectedExpression { span: Span { line: 1, column: 5, .. } }]
+        ));
+    }
+
     #[test]
     fn test_mult_expr() {
         let errors = get_expression_errors("4 *");
         assert!(errors.iter().any(|e| matches!(e, ParseError::UnexpectedEOF { .. })));
     }
 
+    #[test]
+    fn test_power_expr() {
+        let errors = get_expression_errors("2 **");
+        assert!(errors.iter().any(|e| matches!(e, ParseError::UnexpectedEOF { .. })));
+    }
+
     #[test]
     fn test_comparison_expr() {
         let errors = get_expression_errors("5 <");
@@ -5,9 +5,9 @@ mod expr;
 mod stmt;
 mod types;
 
-pub use core::Parser;
+pub use core::{Parser, DEFAULT_MAX_CHAIN, DEFAULT_MAX_DEPTH};
 use decl::ParserDecl;
-use error::*;
+pub use error::{print_errors, ParseError};
 use expr::ParserExpr;
 use stmt::ParserStmt;
 use types::*;
@@ -1,21 +1,33 @@
 use super::*;
 use crate::ast::{
-    AssignStmt, BlockStmt, BreakStmt, ExprStmt, IfStmt, ReturnStmt, Stmt, VarDeclStmt, VarDeclWithAssign, WhileStmt
+    AssignStmt, BlockStmt, BreakStmt, ContinueStmt, DoWhileStmt, ForRangeStmt, ExprStmt, IfStmt, MatchArm, MatchStmt, MultiVarDeclStmt, ReturnStmt, Stmt, VarDeclStmt, VarDeclWithAssign, WhileStmt
 };
-use crate::lexer::TokenType;
+use crate::ast::Expr;
+use crate::lexer::{Span, TokenType};
 use crate::parser::types::expected;
 
 pub trait ParserStmt {
-    fn parse_field_assign(&mut self) -> Option<Stmt>;
+    /// Parses the `a.b =` of a field assignment and returns the field's
+    /// dotted name.
+    fn parse_field_target(&mut self) -> Option<String>;
+    fn parse_field_assign(&mut self, name: String, span: Span) -> Option<Stmt>;
     fn parse_var_decl(&mut self) -> Option<Stmt>;
+    /// Parses one `name[: type][= expr]` of a declaration, stopping before
+    /// the `,` or `;` after it.
+    fn parse_declarator(&mut self, mutable: bool, span: Span) -> Option<Stmt>;
     fn parse_var_assign(&mut self) -> Option<Stmt>;
     fn parse_stmt(&mut self) -> Option<Stmt>;
     fn parse_break(&mut self) -> Option<Stmt>;
+    fn parse_continue(&mut self) -> Option<Stmt>;
     fn parse_return(&mut self) -> Option<Stmt>;
     fn parse_if(&mut self) -> Option<Stmt>;
     fn parse_while(&mut self) -> Option<Stmt>;
+    fn parse_do_while(&mut self) -> Option<Stmt>;
+    fn parse_for_range(&mut self) -> Option<Stmt>;
+    fn parse_match(&mut self) -> Option<Stmt>;
     fn parse_expr_stmt(&mut self) -> Option<Stmt>;
     fn parse_block(&mut self) -> Option<Stmt>;
+    fn parse_body(&mut self) -> Option<Vec<Stmt>>;
 }
 
 impl ParserStmt for Parser {
@@ -25,39 +37,62 @@ impl ParserStmt for Parser {
 
         match token.token_type {
             TokenType::Let => self.parse_var_decl(),
+            TokenType::Const => {
+                let stmt = self.parse_var_decl()?;
+                let decls = match &stmt {
+                    Stmt::MultiVarDecl(multi) => multi.decls.as_slice(),
+                    stmt => std::slice::from_ref(stmt),
+                };
+                // JS rejects `const x;`; only class fields may leave a
+                // `const` for the constructor to assign
+                let uninitialized: Vec<Span> = decls
+                    .iter()
+                    .filter_map(|decl| match decl {
+                        Stmt::VarDecl(var_decl) => Some(var_decl.span),
+                        _ => None,
+                    })
+                    .collect();
+                for span in &uninitialized {
+                    self.errors.push(ParseError::expected_but_found(
+                        "assign".to_string(),
+                        Some("semicolon".to_string()),
+                        Some(*span),
+                    ));
+                }
+                uninitialized.is_empty().then_some(stmt)
+            }
             TokenType::Break => self.parse_break(),
+            TokenType::Continue => self.parse_continue(),
             TokenType::Return => self.parse_return(),
             TokenType::If => self.parse_if(),
             TokenType::While => self.parse_while(),
+            TokenType::Do => self.parse_do_while(),
+            TokenType::For => self.parse_for_range(),
+            TokenType::Match => self.parse_match(),
             TokenType::LeftBrace => self.parse_block(),
-            TokenType::Identifier(_) => {
-                if matches!(next_token.token_type, TokenType::Assign) {
-                    return self.parse_var_assign();
-                }
-                else if matches!(next_token.token_type, TokenType::Dot) {
-                    let nexter_token = self.peek_ahead_amount(3)?;
-                    if !matches!(nexter_token.token_type, TokenType::LeftParen) {
-                        return self.parse_field_assign();
-                    }
-                }
-                return self.parse_expr_stmt();
+            TokenType::Semicolon => {
+                self.advance();
+                Some(Stmt::Empty)
             }
-            TokenType::This => {
-                if matches!(next_token.token_type, TokenType::Dot) {
-                    let nexter_token = self.peek_ahead_amount(3)?;
-                    if !matches!(nexter_token.token_type, TokenType::LeftParen) {
-                        return self.parse_field_assign();
-                    }
+            TokenType::Identifier(_) if matches!(next_token.token_type, TokenType::Assign) => {
+                self.parse_var_assign()
+            }
+            // `a.b = ...` and `a.b;` share a prefix; only a field followed
+            // by `=` is an assignment
+            TokenType::Identifier(_) | TokenType::This if matches!(next_token.token_type, TokenType::Dot) => {
+                match self.try_parse(|parser| parser.parse_field_target()) {
+                    Some(target) => self.parse_field_assign(target, token.span),
+                    None => self.parse_expr_stmt(),
                 }
-                return self.parse_expr_stmt();
             }
             _ => self.parse_expr_stmt(),
         }
     }
 
     fn parse_expr_stmt(&mut self) -> Option<Stmt> {
-        let expr = self.parse_expr()?;
         let span = self.current_span()?;
+        let expr = self.parse_expr()?;
+        self.consume_optional(TokenType::Semicolon);
         Some(Stmt::Expr(ExprStmt {
             expr: Box::new(expr),
             span,
@@ -65,6 +100,7 @@ impl ParserStmt for Parser {
     }
 
     fn parse_while(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
         self.consume(TokenType::While)?;
         self.consume(TokenType::LeftParen)?;
 
@@ -74,7 +110,7 @@ impl ParserStmt for Parser {
                 let span = self.current_span();
                 self.errors.push(ParseError::expected_but_found(
                     expected::EXPRESSION.to_string(),
-                    None,
+                    Some(self.found()),
                     span,
                 ));
                 return None;
@@ -83,8 +119,7 @@ impl ParserStmt for Parser {
 
         self.consume(TokenType::RightParen)?;
 
-        if let Some(stmt) = self.parse_stmt() {
-            let span = self.current_span()?;
+        if let Some(stmt) = self.nested(span, |parser| parser.parse_stmt()) {
             return Some(Stmt::While(WhileStmt {
                 condition: Box::new(expr),
                 body: Box::new(stmt),
@@ -93,15 +128,138 @@ impl ParserStmt for Parser {
         }
 
         let span = self.current_span();
-        self.errors.push(ParseError::expected_but_found(
+        self.report_missing(ParseError::expected_but_found(
             expected::EXPRESSION.to_string(),
-            None,
+            Some(self.found()),
             span,
         ));
         None
     }
 
+    fn parse_do_while(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
+        self.consume(TokenType::Do)?;
+        let body = self.parse_block()?;
+
+        self.consume(TokenType::While)?;
+        self.consume(TokenType::LeftParen)?;
+
+        let condition = match self.parse_expr() {
+            Some(expr) => expr,
+            None => {
+                let span = self.current_span();
+                self.errors.push(ParseError::expected_but_found(
+                    expected::EXPRESSION.to_string(),
+                    Some(self.found()),
+                    span,
+                ));
+                return None;
+            }
+        };
+
+        self.consume(TokenType::RightParen)?;
+        self.consume(TokenType::Semicolon)?;
+
+        Some(Stmt::DoWhile(DoWhileStmt {
+            body: Box::new(body),
+            condition: Box::new(condition),
+            span,
+        }))
+    }
+
+    fn parse_for_range(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
+        self.consume(TokenType::For)?;
+        self.consume(TokenType::LeftParen)?;
+        let var = self.consume_identifier("loop variable")?;
+        self.consume(TokenType::In)?;
+
+        let start = self.parse_expr()?;
+        let inclusive = match self.peek_ref().map(|token| &token.token_type) {
+            Some(TokenType::DotDot) => false,
+            Some(TokenType::DotDotEqual) => true,
+            _ => {
+                let found = self.peek_ref().map(|token| token.token_type.to_string());
+                let span = self.current_span();
+                self.errors.push(ParseError::expected_but_found("..".to_string(), found, span));
+                return None;
+            }
+        };
+        self.advance();
+        let end = self.parse_expr()?;
+        self.consume(TokenType::RightParen)?;
+
+        let body = self.nested(span, |parser| parser.parse_stmt())?;
+        Some(Stmt::ForRange(ForRangeStmt {
+            var,
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+            body: Box::new(body),
+            span,
+        }))
+    }
+
+    fn parse_match(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
+        self.consume(TokenType::Match)?;
+        self.consume(TokenType::LeftParen)?;
+        let scrutinee = self.parse_expr()?;
+        self.consume(TokenType::RightParen)?;
+        self.consume(TokenType::LeftBrace)?;
+
+        let mut arms = Vec::new();
+        let mut default = None;
+        while let Some(token) = self.peek() {
+            match &token.token_type {
+                TokenType::RightBrace => break,
+                TokenType::Identifier(name) if name == "_" => {
+                    self.advance();
+                    self.consume(TokenType::FatArrow)?;
+                    let body = self.nested(token.span, |parser| parser.parse_stmt())?;
+                    if default.replace(Box::new(body)).is_some() {
+                        self.errors.push(ParseError::UnexpectedToken {
+                            symbol: "_".to_string(),
+                            span: token.span,
+                        });
+                    }
+                }
+                _ => {
+                    // `parse_unary_expr` folds `-1` into a literal
+                    let pattern = self.parse_unary_expr()?;
+                    if !matches!(
+                        pattern,
+                        Expr::IntegerLiteral(_) | Expr::StringLiteral(_) | Expr::BooleanLiteral(_)
+                    ) {
+                        self.errors.push(ParseError::expected_but_found(
+                            "integer, string or boolean literal".to_string(),
+                            Some(token.token_type.to_string()),
+                            Some(token.span),
+                        ));
+                        return None;
+                    }
+                    self.consume(TokenType::FatArrow)?;
+                    let body = self.nested(token.span, |parser| parser.parse_stmt())?;
+                    arms.push(MatchArm {
+                        pattern,
+                        body: Box::new(body),
+                        span: token.span,
+                    });
+                }
+            }
+        }
+        self.consume(TokenType::RightBrace)?;
+
+        Some(Stmt::Match(MatchStmt {
+            scrutinee: Box::new(scrutinee),
+            arms,
+            default,
+            span,
+        }))
+    }
+
     fn parse_if(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
         self.consume(TokenType::If)?;
         self.consume(TokenType::LeftParen)?;
 
@@ -111,56 +269,50 @@ impl ParserStmt for Parser {
                 let span = self.current_span();
                 self.errors.push(ParseError::expected_but_found(
                     expected::EXPRESSION.to_string(),
-                    None,
+                    Some(self.found()),
                     span,
                 ));
                 return None;
             }
         };
 
+        // a `{` makes the body a block; anything else is a single statement
         self.consume(TokenType::RightParen)?;
-        self.consume(TokenType::LeftBrace)?;
 
-        let then_stmt = match self.parse_stmt() {
+        let then_stmt = match self.nested(span, |parser| parser.parse_stmt()) {
             Some(stmt) => stmt,
             None => {
                 let span = self.current_span();
-                self.errors.push(ParseError::expected_but_found(
+                self.report_missing(ParseError::expected_but_found(
                     expected::STATEMENT.to_string(),
-                    None,
+                    Some(self.found()),
                     span,
                 ));
                 return None;
             }
         };
 
-        self.consume(TokenType::RightBrace)?;
-
         let mut else_branch = None;
 
         if let Some(token) = self.peek() {
             if token.token_type == TokenType::Else {
                 self.consume(TokenType::Else)?;
-                self.consume(TokenType::LeftBrace)?;
 
-                else_branch = match self.parse_stmt() {
+                else_branch = match self.nested(span, |parser| parser.parse_stmt()) {
                     Some(else_stmt) => Some(Box::new(else_stmt)),
                     None => {
                         let span = self.current_span();
-                        self.errors.push(ParseError::expected_but_found(
+                        self.report_missing(ParseError::expected_but_found(
                             expected::STATEMENT.to_string(),
-                            None,
+                            Some(self.found()),
                             span,
                         ));
                         return None;
                     }
                 };
-
-                self.consume(TokenType::RightBrace)?;
             }
         }
 
-        let span = self.current_span()?;
         Some(Stmt::If(IfStmt {
             condition: Box::new(condition),
             then_branch: Box::new(then_stmt),
@@ -177,62 +329,66 @@ impl ParserStmt for Parser {
         self.consume(TokenType::Assign)?;
 
         if let Some(expr) = self.parse_expr() {
-            let span = self.current_span()?;
+            self.consume_optional(TokenType::Semicolon);
             return Some(Stmt::Assign(AssignStmt {
                 name: var_name,
                 expr: Box::new(expr),
-                span,
+                span: token.span,
             }));
         }
         self.errors.push(ParseError::expected_but_found(
             "expression".to_string(),
-            None,
+            Some(self.found()),
             Some(token.span),
         ));
         return None;
     }
 
-    fn parse_field_assign(&mut self) -> Option<Stmt> {
-        let token = self.peek()?;
-
-        match self.parse_call_expr(){
-            Some(left_expr) => match left_expr{
-                crate::ast::Expr::Field(field) => {
-                    let full_name= self.parse_full_field_expr_name(crate::ast::Expr::Field(field));
-
-                    self.consume(TokenType::Assign)?;
-
-                    if let Some(expr) = self.parse_expr() {
-                        let span = self.current_span()?;
-                        return Some(Stmt::Assign(AssignStmt {
-                            name: full_name,
-                            expr: Box::new(expr),
-                            span,
-                        }));
-                    }
-                    self.errors.push(ParseError::expected_but_found(
-                        "expression".to_string(),
-                        None,
-                        Some(token.span),
-                    ));
-                    return None;
+    /// Reads the `a.b.c` of a field assignment up to and including its `=`,
+    /// looking only at names and dots so a statement that turns out to be an
+    /// expression isn't parsed twice.
+    fn parse_field_target(&mut self) -> Option<String> {
+        let mut full_name = match &self.peek_ref()?.token_type {
+            TokenType::Identifier(name) => name.clone(),
+            TokenType::This => "this".to_string(),
+            _ => return None,
+        };
+        self.advance();
+        while self.consume_optional(TokenType::Dot).is_some() {
+            let TokenType::Identifier(field) = &self.peek_ref()?.token_type else {
+                return None;
+            };
+            full_name.push('.');
+            full_name.push_str(field);
+            self.advance();
+        }
+        self.consume_optional(TokenType::Assign)?;
+        Some(full_name)
+    }
 
-                }
-                _ => None,
-            }
-            None => None,
+    fn parse_field_assign(&mut self, name: String, span: Span) -> Option<Stmt> {
+        if let Some(expr) = self.parse_expr() {
+            self.consume_optional(TokenType::Semicolon);
+            return Some(Stmt::Assign(AssignStmt {
+                name,
+                expr: Box::new(expr),
+                span,
+            }));
         }
+        self.errors.push(ParseError::expected_but_found(
+            "expression".to_string(),
+            Some(self.found()),
+            Some(span),
+        ));
+        None
     }
 
     fn parse_return(&mut self) -> Option<Stmt> {
         let span = self.current_span()?;
         self.consume(TokenType::Return)?;
 
-        if let Some(token) = self.consume_optional(TokenType::Semicolon) {
-            return Some(Stmt::Return(ReturnStmt {
-                value: None,
-                span: token.span,
-            }));
+        if self.consume_optional(TokenType::Semicolon).is_some() {
+            return Some(Stmt::Return(ReturnStmt { value: None, span }));
         }
 
         let expr = self.parse_expr()?;
@@ -247,29 +403,55 @@ impl ParserStmt for Parser {
     fn parse_break(&mut self) -> Option<Stmt> {
         let span = self.current_span()?;
         self.advance();
+        self.consume_optional(TokenType::Semicolon);
         Some(Stmt::Break(BreakStmt { span }))
     }
 
+    fn parse_continue(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
+        self.advance();
+        self.consume_optional(TokenType::Semicolon);
+        Some(Stmt::Continue(ContinueStmt { span }))
+    }
+
     fn parse_var_decl(&mut self) -> Option<Stmt> {
-        self.consume(TokenType::Let)?;
-        let var_name = self.consume_identifier("var_name")?;
-        self.consume(TokenType::Colon)?;
+        let span = self.current_span()?;
+        let mutable = !matches!(self.peek_ref(), Some(token) if token.token_type == TokenType::Const);
+        self.consume(if mutable { TokenType::Let } else { TokenType::Const })?;
 
-        let var_type = self.consume_type()?;
+        let mut decls = vec![self.parse_declarator(mutable, span)?];
+        while self.consume_optional(TokenType::Comma).is_some() {
+            let span = self.current_span()?;
+            decls.push(self.parse_declarator(mutable, span)?);
+        }
+        self.consume(TokenType::Semicolon)?;
 
-        let token = self.peek()?;
-        let current_span = token.span.clone();
+        if decls.len() == 1 {
+            return decls.pop();
+        }
+        Some(Stmt::MultiVarDecl(MultiVarDeclStmt { decls, mutable, span }))
+    }
 
-        match token.token_type {
-            TokenType::Semicolon => {
-                self.consume(TokenType::Semicolon);
+    fn parse_declarator(&mut self, mutable: bool, span: Span) -> Option<Stmt> {
+        let var_name = self.consume_identifier("var_name")?;
 
-                Some(Stmt::VarDecl(VarDeclStmt {
-                    name: var_name,
-                    var_type,
-                    span: current_span,
-                }))
+        // the type annotation may only be omitted when an initializer follows
+        let var_type = match self.peek() {
+            Some(token) if token.token_type == TokenType::Assign => None,
+            _ => {
+                self.consume(TokenType::Colon)?;
+                Some(self.consume_type()?)
             }
+        };
+
+        let token = self.peek()?;
+        match token.token_type {
+            TokenType::Semicolon | TokenType::Comma => Some(Stmt::VarDecl(VarDeclStmt {
+                name: var_name,
+                var_type: var_type.unwrap_or_default(),
+                mutable,
+                span,
+            })),
             TokenType::Assign => {
                 self.consume(TokenType::Assign)?;
 
@@ -284,13 +466,13 @@ impl ParserStmt for Parser {
                 }
 
                 let expr = self.parse_expr()?;
-                self.consume(TokenType::Semicolon)?;
 
                 Some(Stmt::VarDeclWithAssign(VarDeclWithAssign {
                     name: var_name,
                     var_type,
                     expr: Box::new(expr),
-                    span: current_span,
+                    mutable,
+                    span,
                 }))
             }
             _ => {
@@ -305,6 +487,14 @@ impl ParserStmt for Parser {
     }
 
     fn parse_block(&mut self) -> Option<Stmt> {
+        let span = self.current_span()?;
+        let statements = self.nested(span, |parser| parser.parse_body())?;
+        Some(Stmt::Block(BlockStmt { statements, span }))
+    }
+
+    /// Parses `{ stmt* }` into its statements, recovering at statement
+    /// boundaries. Shared by blocks and function and method bodies.
+    fn parse_body(&mut self) -> Option<Vec<Stmt>> {
         self.consume(TokenType::LeftBrace)?;
 
         let mut statements = Vec::<Stmt>::new();
@@ -314,18 +504,17 @@ impl ParserStmt for Parser {
                 break;
             }
 
+            let start = self.position;
             if let Some(stmt) = self.parse_stmt() {
                 statements.push(stmt);
             } else {
-                // if parsing stmt fails, advance to avoid infinite loop
-                self.advance();
+                self.recover_statement(start);
             }
         }
 
         self.consume(TokenType::RightBrace)?;
 
-        let span = self.current_span()?;
-        Some(Stmt::Block(BlockStmt { statements, span }))
+        Some(statements)
     }
 }
 
@@ -346,7 +535,9 @@ mod tests {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.tokenize().unwrap();
         let mut parser = Parser::new(tokens);
-        parser.parse_stmt();
+        crate::with_stack(|| {
+            parser.parse_stmt();
+        });
         parser.get_errors().to_vec()
     }
 
@@ -362,11 +553,45 @@ mod tests {
                 ..
             })
             if name == "myNum"
-                && var_type == TypeName::Int
+                && var_type == Some(TypeName::Int)
+                && matches!(&*expr, Expr::IntegerLiteral(IntegerLiteral { value, .. }) if *value == 5)
+        ));
+    }
+
+    #[test]
+    fn test_var_decl_inferred_int() {
+        let stmt = parse_stmt("let x = 5;").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::VarDeclWithAssign(VarDeclWithAssign {
+                name,
+                var_type,
+                expr,
+                ..
+            })
+            if name == "x"
+                && var_type.is_none()
                 && matches!(&*expr, Expr::IntegerLiteral(IntegerLiteral { value, .. }) if *value == 5)
         ));
     }
 
+    #[test]
+    fn test_var_decl_inferred_string() {
+        let stmt = parse_stmt("let s = \"hi\";").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::VarDeclWithAssign(VarDeclWithAssign {
+                name,
+                var_type,
+                expr,
+                ..
+            })
+            if name == "s"
+                && var_type.is_none()
+                && matches!(&*expr, Expr::StringLiteral(StringLiteral { value, .. }) if value == "hi")
+        ));
+    }
+
     #[test]
     fn test_var_decl_without_assign() {
         let stmt = parse_stmt("let myStr: Str;").unwrap();
@@ -377,6 +602,74 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_var_decl_function_type() {
+        let stmt = parse_stmt("let f: (Int, Str) -> Int = g;").unwrap();
+        let Stmt::VarDeclWithAssign(VarDeclWithAssign { var_type: Some(var_type), .. }) = stmt else {
+            panic!("expected a typed declaration, got {:?}", stmt);
+        };
+        assert_eq!(
+            var_type,
+            TypeName::Function {
+                params: vec![TypeName::Int, TypeName::Str],
+                ret: Box::new(TypeName::Int),
+            }
+        );
+
+        // the return type may itself be a function type
+        let stmt = parse_stmt("let h: () -> (Int) -> Boolean;").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::VarDecl(VarDeclStmt { var_type: TypeName::Function { params, ret }, .. })
+            if params.is_empty() && matches!(&*ret, TypeName::Function { .. })
+        ));
+
+        assert!(!get_parse_errors("let f: (Int) Int;").is_empty());
+    }
+
+    #[test]
+    fn test_const_decl() {
+        let stmt = parse_stmt("const pi: Int = 3;").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::VarDeclWithAssign(VarDeclWithAssign { name, mutable: false, .. }) if name == "pi"
+        ));
+
+        let stmt = parse_stmt("let r: Int = 3;").unwrap();
+        assert!(matches!(stmt, Stmt::VarDeclWithAssign(VarDeclWithAssign { mutable: true, .. })));
+    }
+
+    #[test]
+    fn test_const_decl_requires_initializer() {
+        let errors = get_parse_errors("const pi: Int;");
+        assert!(matches!(errors.as_slice(), [ParseError::ExpectedButFound { expected, .. }] if expected == "assign"));
+
+        let errors = get_parse_errors("const a: Int = 1, b: Int;");
+        assert!(matches!(errors.as_slice(), [ParseError::ExpectedButFound { expected, .. }] if expected == "assign"));
+    }
+
+    #[test]
+    fn test_multi_var_decl() {
+        let stmt = parse_stmt("let a: Int, b: Str = \"x\", c = 3;").unwrap();
+        let Stmt::MultiVarDecl(MultiVarDeclStmt { decls, mutable: true, .. }) = stmt else {
+            panic!("expected a multi-variable declaration, got {:?}", stmt);
+        };
+        assert!(matches!(
+            decls.as_slice(),
+            [
+                Stmt::VarDecl(VarDeclStmt { name: a, var_type: TypeName::Int, .. }),
+                Stmt::VarDeclWithAssign(VarDeclWithAssign { name: b, var_type: Some(TypeName::Str), .. }),
+                Stmt::VarDeclWithAssign(VarDeclWithAssign { name: c, var_type: None, .. }),
+            ] if a == "a" && b == "b" && c == "c"
+        ));
+
+        // a single name stays a plain declaration
+        assert!(matches!(parse_stmt("let a: Int;"), Some(Stmt::VarDecl(_))));
+
+        assert!(!get_parse_errors("let a: Int, ;").is_empty());
+        assert!(!get_parse_errors("let a: Int, b;").is_empty());
+    }
+
     #[test]
     fn test_var_decl_missing_type() {
         let errors = get_parse_errors("let myVar;");
@@ -393,6 +686,16 @@ mod tests {
         assert!(errors
             .iter()
             .any(|e| matches!(e, ParseError::ExpectedButFound { .. })));
+
+        let errors = get_parse_errors("let x: Int = 5");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().starts_with("Expected ';' but found 'end of file'"));
+
+        let errors = get_parse_errors("while (");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::ExpectedButFound { found, .. }] if found == "end of file"
+        ));
     }
 
     #[test]
@@ -405,6 +708,22 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_var_decl_reserved_keyword() {
+        let errors = get_parse_errors("let new: Int = 5;");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::ReservedKeyword { keyword, span }]
+            if keyword == "new" && span.column == 5
+        ));
+
+        let errors = get_parse_errors("let if: Int;");
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::ReservedKeyword { keyword, .. }] if keyword == "if"
+        ));
+    }
+
     #[test]
     fn test_var_decl_missing_expression() {
         let errors = get_parse_errors("let myNum: Int =;");
@@ -425,7 +744,7 @@ mod tests {
                 ..
             })
             if name == "isValid"
-                && var_type == TypeName::Boolean
+                && var_type == Some(TypeName::Boolean)
                 && matches!(&*expr, Expr::BooleanLiteral(BooleanLiteral { value, .. }) if *value == true)
         ));
     }
@@ -442,7 +761,7 @@ mod tests {
                 ..
             })
             if name == "message"
-                && var_type == TypeName::Str
+                && var_type == Some(TypeName::Str)
                 && matches!(&*expr, Expr::StringLiteral(StringLiteral { value, .. }) if value == "Hello")
         ));
     }
@@ -489,6 +808,14 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_field_prefix_expression() {
+        // only plain field names ahead of `=` make an assignment; the rest is
+        // parsed once, as an expression
+        assert!(matches!(parse_stmt("object.field == 5;"), Some(Stmt::Expr(_))));
+        assert!(matches!(parse_stmt("object.method(fun () -> Void { a.b = 1; }).field;"), Some(Stmt::Expr(_))));
+    }
+
     #[test]
     fn test_var_assign_with_string() {
         let stmt = parse_stmt("myStr = \"hello world\";").unwrap();
@@ -513,6 +840,23 @@ mod tests {
         assert!(matches!(stmt, Stmt::Break(BreakStmt { .. })))
     }
 
+    #[test]
+    fn test_continue_stmt() {
+        let stmt = parse_stmt("continue;").unwrap();
+        assert!(matches!(stmt, Stmt::Continue(ContinueStmt { .. })))
+    }
+
+    #[test]
+    fn test_break_inside_braced_if() {
+        let stmt = parse_stmt("while (true) { if (done) { break; } continue; }").unwrap();
+        let Stmt::While(WhileStmt { body, .. }) = stmt else { panic!("expected while") };
+        assert!(matches!(
+            *body,
+            Stmt::Block(BlockStmt { ref statements, .. })
+            if matches!(statements.as_slice(), [Stmt::If(_), Stmt::Continue(_)])
+        ));
+    }
+
     #[test]
     fn test_return_stmt() {
         let stmt = parse_stmt("return;").unwrap();
@@ -538,10 +882,11 @@ mod tests {
                 then_branch,
                 ..
             }) if matches!(&*condition, Expr::IntegerLiteral(IntegerLiteral { value, .. }) if *value == 5)
-                && matches!(&*then_branch, Stmt::Expr(ExprStmt { expr, .. })
-                    if matches!(&**expr, Expr::Println(PrintlnExpr { arg, .. })
-                        if matches!(&**arg, Expr::StringLiteral(StringLiteral { value, .. })
-                            if value == "hello")))
+                && matches!(&*then_branch, Stmt::Block(BlockStmt { statements, .. })
+                    if matches!(statements.as_slice(), [Stmt::Expr(ExprStmt { expr, .. })]
+                        if matches!(&**expr, Expr::Println(PrintlnExpr { args, .. })
+                            if matches!(args.as_slice(), [Expr::StringLiteral(StringLiteral { value, .. })]
+                                if value == "hello"))))
         ));
     }
 
@@ -556,15 +901,100 @@ mod tests {
                 else_branch,
                 ..
             }) if matches!(&*condition, Expr::IntegerLiteral(IntegerLiteral { value, .. }) if *value == 5)
-                && matches!(&*then_branch, Stmt::Expr(ExprStmt { expr, .. })
-                    if matches!(&**expr, Expr::Println(PrintlnExpr { arg, .. })
-                        if matches!(&**arg, Expr::StringLiteral(StringLiteral { value, .. })
-                            if value == "hello")))
+                && matches!(&*then_branch, Stmt::Block(BlockStmt { statements, .. })
+                    if matches!(statements.as_slice(), [Stmt::Expr(ExprStmt { expr, .. })]
+                        if matches!(&**expr, Expr::Println(PrintlnExpr { args, .. })
+                            if matches!(args.as_slice(), [Expr::StringLiteral(StringLiteral { value, .. })]
+                                if value == "hello"))))
                 && matches!(&else_branch, Some(else_stmt)
-                    if matches!(&**else_stmt, Stmt::Expr(ExprStmt { expr, .. })
-                        if matches!(&**expr, Expr::StringLiteral(StringLiteral { value, .. })
-                            if value == "bye")))
+                    if matches!(&**else_stmt, Stmt::Block(BlockStmt { statements, .. })
+                        if matches!(statements.as_slice(), [Stmt::Expr(ExprStmt { expr, .. })]
+                            if matches!(&**expr, Expr::StringLiteral(StringLiteral { value, .. })
+                                if value == "bye"))))
+        ));
+    }
+
+    #[test]
+    fn test_if_without_braces() {
+        let stmt = parse_stmt("if (n <= 1) return 1; else return n;").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::If(IfStmt { then_branch, else_branch: Some(else_branch), .. })
+            if matches!(*then_branch, Stmt::Return(_)) && matches!(*else_branch, Stmt::Return(_))
+        ));
+
+        let stmt = parse_stmt("if (a) { x = 1; y = 2; } else if (b) x = 3;").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::If(IfStmt { then_branch, else_branch: Some(else_branch), .. })
+            if matches!(&*then_branch, Stmt::Block(BlockStmt { statements, .. }) if statements.len() == 2)
+                && matches!(*else_branch, Stmt::If(_))
+        ));
+    }
+
+    #[test]
+    fn test_while_with_and_without_braces() {
+        let stmt = parse_stmt("while (x > 0) x = x - 1;").unwrap();
+        assert!(matches!(stmt, Stmt::While(WhileStmt { body, .. }) if matches!(*body, Stmt::Assign(_))));
+
+        let stmt = parse_stmt("while (x > 0) { x = x - 1; }").unwrap();
+        assert!(matches!(stmt, Stmt::While(WhileStmt { body, .. }) if matches!(*body, Stmt::Block(_))));
+    }
+
+    #[test]
+    fn test_do_while() {
+        let stmt = parse_stmt("do { x = x - 1; } while (x > 0);").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::DoWhile(DoWhileStmt { body, condition, .. })
+            if matches!(&*body, Stmt::Block(BlockStmt { statements, .. }) if statements.len() == 1)
+                && matches!(*condition, Expr::Binary(_))
+        ));
+
+        assert!(get_parse_errors("do { x = x - 1; } while (x > 0);").is_empty());
+        assert!(!get_parse_errors("do { x = x - 1; } while (x > 0)").is_empty());
+        assert!(!get_parse_errors("do x = x - 1; while (x > 0);").is_empty());
+    }
+
+    #[test]
+    fn test_for_range() {
+        let stmt = parse_stmt("for (i in 0..n) { println(i); }").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::ForRange(ForRangeStmt { var, inclusive: false, end, body, .. })
+            if var == "i" && matches!(*end, Expr::Variable(_)) && matches!(*body, Stmt::Block(_))
+        ));
+
+        let stmt = parse_stmt("for (i in 1..=n + 1) println(i);").unwrap();
+        assert!(matches!(
+            stmt,
+            Stmt::ForRange(ForRangeStmt { inclusive: true, end, body, .. })
+            if matches!(*end, Expr::Binary(_)) && matches!(*body, Stmt::Expr(_))
         ));
+
+        assert!(!get_parse_errors("for (i in 0, 10) {}").is_empty());
+    }
+
+    #[test]
+    fn test_match() {
+        let stmt = parse_stmt("match (x) { 1 => { println(1); } \"two\" => println(2); _ => {} }").unwrap();
+        let Stmt::Match(MatchStmt { scrutinee, arms, default: Some(default), .. }) = stmt else {
+            panic!("Expected a match with a default arm");
+        };
+        assert!(matches!(*scrutinee, Expr::Variable(_)));
+        assert!(matches!(
+            arms.as_slice(),
+            [
+                MatchArm { pattern: Expr::IntegerLiteral(IntegerLiteral { value: 1, .. }), .. },
+                MatchArm { pattern: Expr::StringLiteral(_), body, .. },
+            ] if matches!(**body, Stmt::Expr(_))
+        ));
+        assert!(matches!(*default, Stmt::Block(BlockStmt { ref statements, .. }) if statements.is_empty()));
+
+        assert!(get_parse_errors("match (x) { -1 => {} true => {} }").is_empty());
+        assert!(!get_parse_errors("match (x) { y => {} }").is_empty());
+        assert!(!get_parse_errors("match (x) { 1 {} }").is_empty());
+        assert!(!get_parse_errors("match (x) { _ => {} _ => {} }").is_empty());
     }
 
     #[test]
@@ -705,8 +1135,8 @@ mod tests {
         assert!(matches!(
             stmt,
             Stmt::Expr(ExprStmt { expr, .. })
-            if matches!(&*expr, Expr::Println(PrintlnExpr { arg, .. })
-                if matches!(&**arg, Expr::StringLiteral(StringLiteral { value, .. }) if value == "hello"))
+            if matches!(&*expr, Expr::Println(PrintlnExpr { args, .. })
+                if matches!(args.as_slice(), [Expr::StringLiteral(StringLiteral { value, .. })] if value == "hello"))
         ));
     }
 
@@ -736,7 +1166,7 @@ mod tests {
                 expr,
                 ..
             }) if name == "complexCalc"
-                && var_type == TypeName::Int
+                && var_type == Some(TypeName::Int)
                 && matches!(&*expr, Expr::Binary(BinaryExpr { .. }))
         ));
     }
@@ -764,7 +1194,7 @@ mod tests {
                 expr,
                 ..
             }) if name == "result"
-                && var_type == TypeName::Int
+                && var_type == Some(TypeName::Int)
                 && matches!(&*expr, Expr::Binary(BinaryExpr { operator: BinaryOp::Multiply, .. }))
         ));
     }
@@ -838,5 +1268,43 @@ mod tests {
         assert!(errors.iter().any(|e| matches!(e, ParseError::ExpectedButFound { expected, .. } if expected == "(")));
     }
 
-    
+    #[test]
+    fn test_blocks_nested_too_deep() {
+        let nested = |depth: usize| format!("{}let x: Int = 1;{}", "{".repeat(depth), "}".repeat(depth));
+        assert!(get_parse_errors(&nested(DEFAULT_MAX_DEPTH)).is_empty());
+
+        // the blocks past the limit are skipped whole, so the ones around
+        // them still close and the error isn't repeated
+        let errors = get_parse_errors(&nested(20_000));
+        assert!(matches!(
+            errors.as_slice(),
+            [ParseError::NestingTooDeep { span }] if span.column == DEFAULT_MAX_DEPTH + 1
+        ));
+    }
+
+    #[test]
+    fn test_unbraced_bodies_nested_too_deep() {
+        let nested = |(head, tail): (&str, &str), depth: usize| {
+            format!("{}println(1);{}", head.repeat(depth), tail.repeat(depth))
+        };
+        let else_ifs = |depth: usize| {
+            let branches = vec!["if (false) println(0);"; depth].join(" else ");
+            format!("{branches} else println(1);")
+        };
+
+        for body in [("if (true) ", ""), ("while (true) ", ""), ("for (i in 0..1) ", ""), ("match (1) { _ => ", "}")] {
+            // the body's call is one more level down
+            assert!(get_parse_errors(&nested(body, DEFAULT_MAX_DEPTH - 1)).is_empty(), "{body:?}");
+            assert!(
+                matches!(get_parse_errors(&nested(body, 20_000)).as_slice(), [ParseError::NestingTooDeep { .. }]),
+                "{body:?}"
+            );
+        }
+
+        // the branch at the limit may report its call as well as its `else`
+        assert!(get_parse_errors(&else_ifs(DEFAULT_MAX_DEPTH - 1)).is_empty());
+        let errors = get_parse_errors(&else_ifs(20_000));
+        assert!(!errors.is_empty() && errors.len() <= 2);
+        assert!(errors.iter().all(|error| matches!(error, ParseError::NestingTooDeep { .. })));
+    }
 }
\ No newline at end of file
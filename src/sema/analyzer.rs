@@ -0,0 +1,1444 @@
+use std::collections::HashMap;
+
+use crate::ast::*;
+use crate::lexer::{Span, TypeName};
+
+use super::*;
+
+/// Knobs for language rules that are a matter of taste rather than JS
+/// semantics, or that depend on how the output will be run.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnalyzerOptions {
+    /// Accept `Int + Str`. `Str + Int` is always allowed, since reading left
+    /// to right it is clearly building a string.
+    pub int_str_concat: bool,
+    /// The generated code will call `main()` with no arguments, as
+    /// [`CodeGenOptions::auto_run_main`](crate::codegen::CodeGenOptions::auto_run_main)
+    /// asks, so `main` can't take required parameters.
+    pub auto_run_main: bool,
+}
+
+/// Walks a parsed program and reports semantic errors the parser can't see:
+/// names used without being declared, redeclarations, and type mismatches.
+///
+/// Class fields are deliberately not put in scope: inside a method they are
+/// only reachable through `this.<field>`, exactly as in the generated JS.
+///
+/// Expressions whose type can't be determined (undefined names, `super`)
+/// infer to `None` and are never reported as mismatches, so one mistake
+/// doesn't cascade into several errors.
+#[derive(Debug, Default)]
+pub struct Analyzer {
+    scopes: ScopeStack,
+    symbols: SymbolTable,
+    errors: Vec<SemanticError>,
+    warnings: Vec<SemanticWarning>,
+    /// Class whose constructor or method is being checked.
+    current_class: Option<String>,
+    /// Declared return type of the enclosing function or method.
+    return_type: Option<TypeName>,
+    /// Number of loops enclosing the statement being checked.
+    loop_depth: usize,
+    /// `loop_depth` at the innermost enclosing `match` arm. A `break` at that
+    /// depth would leave the generated `switch` rather than the loop.
+    match_loop_depth: Option<usize>,
+    /// Whether a constructor is being checked; only it may set `const` fields.
+    in_constructor: bool,
+    /// The inferred type of each `typeName(...)` argument, keyed by the
+    /// call's span, for codegen to write out as a string.
+    type_names: HashMap<Span, TypeName>,
+    options: AnalyzerOptions,
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: AnalyzerOptions) -> Self {
+        Self { options, ..Self::default() }
+    }
+
+    pub fn analyze(&mut self, entry: &Entry) {
+        self.symbols = SymbolTable::collect(entry);
+        self.check_duplicate_definitions(entry);
+
+        // Top-level `let`s stay in scope while the functions and classes are
+        // checked, since those bodies only run once the program has started.
+        self.scopes.push();
+        self.analyze_statements(&entry.statements);
+
+        for fun_def in &entry.fun_defs {
+            self.analyze_function(fun_def);
+        }
+        for class_def in &entry.class_defs {
+            self.analyze_class(class_def);
+        }
+        self.pop_scope();
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    pub fn get_errors(&self) -> &[SemanticError] {
+        &self.errors
+    }
+
+    pub fn get_warnings(&self) -> &[SemanticWarning] {
+        &self.warnings
+    }
+
+    /// What each `typeName(...)` in the program resolved to. Calls whose
+    /// argument has no known type are left out.
+    pub fn get_type_names(&self) -> &HashMap<Span, TypeName> {
+        &self.type_names
+    }
+
+    /// Errors followed by warnings.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let errors = self.errors.iter().cloned().map(Diagnostic::Error);
+        let warnings = self.warnings.iter().cloned().map(Diagnostic::Warning);
+        errors.chain(warnings).collect()
+    }
+
+    /// Reports a second top-level function or class with the same name as an
+    /// earlier one. Both are declared in the same JS scope, so a function
+    /// can't share a name with a class either.
+    fn check_duplicate_definitions(&mut self, entry: &Entry) {
+        let functions = entry.fun_defs.iter().map(|fun_def| ("function", &fun_def.name, fun_def.span));
+        let classes = entry.class_defs.iter().map(|class_def| ("class", &class_def.name, class_def.span));
+        let mut definitions: Vec<_> = functions.chain(classes).collect();
+        definitions.sort_by_key(|&(_, _, span)| span.start);
+
+        let mut seen = HashMap::new();
+        for (kind, name, span) in definitions {
+            match seen.get(name) {
+                Some(&first_span) => self.errors.push(SemanticError::DuplicateDefinition {
+                    name: name.clone(),
+                    kind,
+                    first_span,
+                    second_span: span,
+                }),
+                None => {
+                    seen.insert(name, span);
+                }
+            }
+        }
+    }
+
+    fn analyze_function(&mut self, fun_def: &FunDef) {
+        // `main` may be called with no arguments by the generated entry point
+        if self.options.auto_run_main && fun_def.name == "main" && fun_def.params.iter().any(|param| param.default.is_none()) {
+            self.errors.push(SemanticError::MainHasParams { span: fun_def.span });
+        }
+        self.return_type = Some(fun_def.return_type.clone());
+        self.analyze_body(&fun_def.params, &fun_def.statements);
+        self.check_returns(&fun_def.name, &fun_def.statements);
+        self.return_type = None;
+    }
+
+    fn analyze_class(&mut self, class_def: &ClassDef) {
+        self.current_class = Some(class_def.name.clone());
+        if let Some(parent) = &class_def.extends {
+            self.check_class(parent, class_def.span);
+        }
+
+        let constructor = &class_def.constructor;
+        self.scopes.push();
+        self.in_constructor = true;
+        self.declare_params(&constructor.params);
+        let (before_super, after_super) = constructor.split_at_super();
+        self.analyze_statements(before_super);
+        if let Some(super_call) = &constructor.super_call {
+            self.check_super(super_call.span);
+            if super_call.position > 0 && class_def.extends.is_some() {
+                self.errors.push(SemanticError::SuperNotFirst { span: super_call.span });
+            }
+            self.analyze_exprs(&super_call.args);
+        } else if class_def.extends.is_some() && !constructor.statements.is_empty() {
+            // an empty one is left out of the output, and JS supplies a
+            // constructor that calls `super` itself
+            self.errors.push(SemanticError::MissingSuperCall {
+                class: class_def.name.clone(),
+                span: class_def.span,
+            });
+        }
+        self.analyze_statements(after_super);
+        self.in_constructor = false;
+        self.pop_scope();
+
+        for method in &class_def.methods {
+            self.return_type = Some(method.return_type.clone());
+            self.analyze_body(&method.params, &method.statements);
+            self.check_returns(&method.name, &method.statements);
+            self.return_type = None;
+        }
+
+        self.current_class = None;
+    }
+
+    /// Checks a function or method body. Parameters and the body's top-level
+    /// `let`s share one scope, matching JS where `let x` may not redeclare a
+    /// parameter `x`.
+    fn analyze_body(&mut self, params: &[ParamDecl], body: &[Stmt]) {
+        self.scopes.push();
+        self.declare_params(params);
+        self.analyze_statements(body);
+        self.pop_scope();
+    }
+
+    /// Reports a non-`Void` body that can fall off its end without a `return`.
+    fn check_returns(&mut self, name: &str, body: &[Stmt]) {
+        if matches!(self.return_type, None | Some(TypeName::Void)) {
+            return;
+        }
+        if !body.iter().any(always_returns) {
+            self.errors.push(SemanticError::MissingReturn {
+                name: name.to_string(),
+                span: body.iter().rev().find_map(Stmt::span).unwrap_or_default(),
+            });
+        }
+    }
+
+    /// Declares `params` in order, so a default may refer to the parameters
+    /// before it, and checks that no required parameter follows a defaulted one.
+    fn declare_params(&mut self, params: &[ParamDecl]) {
+        let mut defaulted = false;
+        for param in params {
+            match &param.default {
+                Some(default) => {
+                    defaulted = true;
+                    let found = self.analyze_expr(default);
+                    self.expect_type(&param.param_type, found.as_ref(), param.span);
+                }
+                None if defaulted => self.errors.push(SemanticError::MissingDefault {
+                    name: param.name.clone(),
+                    span: param.span,
+                }),
+                None => {}
+            }
+            self.declare(&param.name, param.span, Some(param.param_type.clone()), true);
+        }
+    }
+
+    fn declare(&mut self, name: &str, span: Span, var_type: Option<TypeName>, mutable: bool) {
+        self.declare_symbol(name, Symbol { span, var_type, mutable, used: true });
+    }
+
+    /// Declares a `let` or `const`, which is reported as unused if it is
+    /// never read inside a function, method or constructor body.
+    fn declare_local(&mut self, name: &str, span: Span, var_type: Option<TypeName>, mutable: bool) {
+        let used = self.return_type.is_none() && !self.in_constructor;
+        self.declare_symbol(name, Symbol { span, var_type, mutable, used });
+    }
+
+    fn declare_symbol(&mut self, name: &str, symbol: Symbol) {
+        let span = symbol.span;
+        if let Some(first) = self.scopes.declare(name, symbol) {
+            self.errors.push(SemanticError::DuplicateDeclaration {
+                name: name.to_string(),
+                first_span: first.span,
+                second_span: span,
+            });
+        }
+    }
+
+    /// Leaves the innermost scope, warning about its bindings that were
+    /// never read. Assigning to a binding doesn't count as reading it.
+    fn pop_scope(&mut self) {
+        let mut unused: Vec<_> = self.scopes.pop().into_iter().flatten().filter(|(_, symbol)| !symbol.used).collect();
+        unused.sort_by_key(|(_, symbol)| symbol.span.start);
+        self.warnings.extend(
+            unused
+                .into_iter()
+                .map(|(name, symbol)| SemanticWarning::UnusedVariable { name, span: symbol.span }),
+        );
+    }
+
+    fn analyze_statements(&mut self, statements: &[Stmt]) {
+        let mut terminated = false;
+        let mut reported = false;
+        for stmt in statements {
+            if terminated && !reported {
+                // only the first dead statement is reported per block
+                if let Some(span) = stmt.span() {
+                    self.warnings.push(SemanticWarning::UnreachableCode { span });
+                    reported = true;
+                }
+            }
+            self.analyze_stmt(stmt);
+            if matches!(stmt, Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_)) {
+                terminated = true;
+            }
+        }
+    }
+
+    fn analyze_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expr(expr_stmt) => {
+                self.analyze_expr(&expr_stmt.expr);
+            }
+            Stmt::VarDecl(var_decl) => {
+                self.declare_local(&var_decl.name, var_decl.span, Some(var_decl.var_type.clone()), var_decl.mutable)
+            }
+            Stmt::VarDeclWithAssign(var_decl) => {
+                // the initializer is checked first so `let x = x;` is rejected
+                let found = self.analyze_expr(&var_decl.expr);
+                let var_type = match &var_decl.var_type {
+                    Some(declared) => {
+                        self.expect_type(declared, found.as_ref(), var_decl.span);
+                        Some(declared.clone())
+                    }
+                    None => found,
+                };
+                self.declare_local(&var_decl.name, var_decl.span, var_type, var_decl.mutable);
+            }
+            // each binding is in scope for the initializers after it
+            Stmt::MultiVarDecl(multi) => {
+                for decl in &multi.decls {
+                    self.analyze_stmt(decl);
+                }
+            }
+            Stmt::Assign(assign) => {
+                let target = self.assign_target_type(assign);
+                let found = self.analyze_expr(&assign.expr);
+                if let Some(expected) = target {
+                    self.expect_type(&expected, found.as_ref(), assign.span);
+                }
+            }
+            Stmt::While(while_stmt) => {
+                self.analyze_expr(&while_stmt.condition);
+                self.loop_depth += 1;
+                self.analyze_stmt(&while_stmt.body);
+                self.loop_depth -= 1;
+            }
+            Stmt::ForRange(for_range) => {
+                for bound in [&for_range.start, &for_range.end] {
+                    let found = self.analyze_expr(bound);
+                    self.expect_type(&TypeName::Int, found.as_ref(), for_range.span);
+                }
+                self.scopes.push();
+                self.declare(&for_range.var, for_range.span, Some(TypeName::Int), true);
+                self.loop_depth += 1;
+                self.analyze_stmt(&for_range.body);
+                self.loop_depth -= 1;
+                self.pop_scope();
+            }
+            Stmt::DoWhile(do_while) => {
+                self.loop_depth += 1;
+                self.analyze_stmt(&do_while.body);
+                self.loop_depth -= 1;
+                self.analyze_expr(&do_while.condition);
+            }
+            Stmt::If(if_stmt) => {
+                self.analyze_expr(&if_stmt.condition);
+                self.analyze_stmt(&if_stmt.then_branch);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.analyze_stmt(else_branch);
+                }
+            }
+            Stmt::Match(match_stmt) => {
+                self.analyze_expr(&match_stmt.scrutinee);
+                let outer = self.match_loop_depth.replace(self.loop_depth);
+                for arm in &match_stmt.arms {
+                    self.analyze_expr(&arm.pattern);
+                    self.analyze_stmt(&arm.body);
+                }
+                if let Some(default) = &match_stmt.default {
+                    self.analyze_stmt(default);
+                }
+                self.match_loop_depth = outer;
+            }
+            Stmt::Return(return_stmt) => {
+                if let Some(value) = &return_stmt.value {
+                    let found = self.analyze_expr(value);
+                    match self.return_type.clone() {
+                        Some(TypeName::Void) => {
+                            self.errors.push(SemanticError::ReturnValueFromVoid { span: return_stmt.span });
+                        }
+                        Some(expected) => self.expect_type(&expected, found.as_ref(), return_stmt.span),
+                        None => {}
+                    }
+                }
+            }
+            Stmt::Block(block) => {
+                self.scopes.push();
+                self.analyze_statements(&block.statements);
+                self.pop_scope();
+            }
+            Stmt::Break(break_stmt) => {
+                if self.loop_depth == 0 {
+                    self.errors.push(SemanticError::BreakOutsideLoop { span: break_stmt.span });
+                } else if self.match_loop_depth == Some(self.loop_depth) {
+                    self.errors.push(SemanticError::BreakInMatch { span: break_stmt.span });
+                }
+            }
+            Stmt::Continue(continue_stmt) => {
+                if self.loop_depth == 0 {
+                    self.errors.push(SemanticError::ContinueOutsideLoop { span: continue_stmt.span });
+                }
+            }
+            Stmt::Empty => {}
+        }
+    }
+
+    /// Resolves the target of an assignment and returns its declared type.
+    fn assign_target_type(&mut self, assign: &AssignStmt) -> Option<TypeName> {
+        // field assignments arrive as dotted names such as `this.width` or
+        // `rect.width`; only the receiver has to be in scope
+        let mut path = assign.name.split('.');
+        let target = path.next().unwrap_or_default();
+        let field = path.next();
+
+        match (target, field) {
+            ("this", Some(field)) if path.next().is_none() => {
+                self.check_this(assign.span);
+                let class = self.current_class.as_deref()?;
+                if !self.in_constructor && self.symbols.is_const_field(class, field) {
+                    self.errors.push(SemanticError::AssignToConst {
+                        name: field.to_string(),
+                        span: assign.span,
+                    });
+                }
+                self.symbols.field_type(class, field).cloned()
+            }
+            ("this", _) => {
+                self.check_this(assign.span);
+                None
+            }
+            (name, None) => {
+                if self.scopes.lookup(name).is_some_and(|symbol| !symbol.mutable) {
+                    self.errors.push(SemanticError::AssignToConst {
+                        name: name.to_string(),
+                        span: assign.span,
+                    });
+                }
+                self.check_defined(name, assign.span)
+            }
+            (name, Some(_)) => {
+                self.check_used(name, assign.span);
+                None
+            }
+        }
+    }
+
+    /// Checks an expression and returns its inferred type, if known.
+    fn analyze_expr(&mut self, expr: &Expr) -> Option<TypeName> {
+        match expr {
+            Expr::IntegerLiteral(_) => Some(TypeName::Int),
+            // there is no character type; a char is a one-character string
+            Expr::StringLiteral(_) | Expr::CharLiteral(_) => Some(TypeName::Str),
+            Expr::BooleanLiteral(_) => Some(TypeName::Boolean),
+            // `nil` has no type of its own and is accepted anywhere
+            Expr::Nil(_) => None,
+            Expr::Variable(variable) => self.check_used(&variable.name, variable.span),
+            Expr::Binary(binary) => self.analyze_binary(binary),
+            Expr::Unary(unary) => {
+                let operand = self.analyze_expr(&unary.expr);
+                let expected = match unary.operator {
+                    UnaryOp::Not => TypeName::Boolean,
+                    UnaryOp::Negate | UnaryOp::Plus | UnaryOp::BitNot => TypeName::Int,
+                };
+                self.expect_type(&expected, operand.as_ref(), unary.span);
+                Some(expected)
+            }
+            Expr::FunCall(fun_call) => {
+                self.analyze_exprs(&fun_call.args);
+                // the constructor's own `super(...)` is kept apart as its
+                // `super_call`, so one that ends up here is misplaced
+                if fun_call.callee == "super" {
+                    self.errors.push(SemanticError::MisplacedSuperCall { span: fun_call.span });
+                    return None;
+                }
+                // a variable holding a function value, such as a callback parameter
+                if self.scopes.lookup(&fun_call.callee).is_some() {
+                    return match self.check_used(&fun_call.callee, fun_call.span) {
+                        Some(TypeName::Function { params, ret }) => {
+                            let arity = (params.len(), params.len());
+                            self.check_arity(&fun_call.callee, arity, fun_call.args.len(), fun_call.span);
+                            Some(*ret)
+                        }
+                        Some(found) => {
+                            self.errors.push(SemanticError::NotAFunction {
+                                name: fun_call.callee.clone(),
+                                found,
+                                span: fun_call.span,
+                            });
+                            None
+                        }
+                        None => None,
+                    };
+                }
+                let Some(signature) = self.symbols.functions.get(&fun_call.callee) else {
+                    self.errors.push(SemanticError::UndefinedFunction {
+                        name: fun_call.callee.clone(),
+                        span: fun_call.span,
+                    });
+                    return None;
+                };
+                let (arity, return_type) = ((signature.required, signature.params.len()), signature.return_type.clone());
+                self.check_arity(&fun_call.callee, arity, fun_call.args.len(), fun_call.span);
+                Some(return_type)
+            }
+            Expr::MethCall(meth_call) => {
+                let object = self.analyze_expr(&meth_call.object);
+                self.analyze_exprs(&meth_call.args);
+                let Some(TypeName::Class(class)) = object else {
+                    return None;
+                };
+                let Some(signature) = self.symbols.method(&class, &meth_call.meth) else {
+                    // an unknown class has already been reported where it was named
+                    if self.symbols.classes.contains_key(&class) {
+                        self.errors.push(SemanticError::UnknownMethod {
+                            class,
+                            method: meth_call.meth.clone(),
+                            span: meth_call.span,
+                        });
+                    }
+                    return None;
+                };
+                let (arity, return_type) = ((signature.required, signature.params.len()), signature.return_type.clone());
+                self.check_arity(&meth_call.meth, arity, meth_call.args.len(), meth_call.span);
+                Some(return_type)
+            }
+            Expr::Field(field) => match self.analyze_expr(&field.object) {
+                Some(TypeName::Class(class)) => self.symbols.field_type(&class, &field.field).cloned(),
+                _ => None,
+            },
+            Expr::New(new_expr) => {
+                self.analyze_exprs(&new_expr.args);
+                if !self.check_class(&new_expr.class_name, new_expr.span) {
+                    return None;
+                }
+                if let Some(class) = self.symbols.classes.get(&new_expr.class_name) {
+                    let arity = (class.constructor.required, class.constructor.params.len());
+                    self.check_arity(&new_expr.class_name, arity, new_expr.args.len(), new_expr.span);
+                }
+                Some(TypeName::Class(new_expr.class_name.clone()))
+            }
+            Expr::Println(println_expr) => {
+                self.analyze_exprs(&println_expr.args);
+                Some(TypeName::Void)
+            }
+            Expr::Print(print_expr) => {
+                self.analyze_exprs(&print_expr.args);
+                Some(TypeName::Void)
+            }
+            Expr::Len(len) => {
+                let found = self.analyze_expr(&len.arg);
+                self.expect_type(&TypeName::Str, found.as_ref(), len.span);
+                Some(TypeName::Int)
+            }
+            Expr::TypeNameOf(type_name_of) => {
+                if let Some(found) = self.analyze_expr(&type_name_of.arg) {
+                    self.type_names.insert(type_name_of.span, found);
+                }
+                Some(TypeName::Str)
+            }
+            Expr::Lambda(lambda) => {
+                // a `break` or `continue` in the body can't reach a loop around the lambda
+                let return_type = self.return_type.replace(lambda.return_type.clone());
+                let loop_depth = std::mem::take(&mut self.loop_depth);
+                let match_loop_depth = self.match_loop_depth.take();
+                self.analyze_body(&lambda.params, &lambda.body);
+                self.check_returns("lambda", &lambda.body);
+                self.return_type = return_type;
+                self.loop_depth = loop_depth;
+                self.match_loop_depth = match_loop_depth;
+                Some(TypeName::Function {
+                    params: lambda.params.iter().map(|param| param.param_type.clone()).collect(),
+                    ret: Box::new(lambda.return_type.clone()),
+                })
+            }
+            Expr::Assert(assert) => {
+                self.analyze_expr(&assert.cond);
+                if let Some(message) = &assert.message {
+                    self.analyze_expr(message);
+                }
+                Some(TypeName::Void)
+            }
+            Expr::Grouped(inner, _) => self.analyze_expr(inner),
+            Expr::This(this) => {
+                self.check_this(this.span);
+                self.current_class.clone().map(TypeName::Class)
+            }
+            Expr::Super(super_expr) => {
+                self.check_super(super_expr.span);
+                None
+            }
+            Expr::Empty => None,
+        }
+    }
+
+    fn analyze_binary(&mut self, binary: &BinaryExpr) -> Option<TypeName> {
+        let left = self.analyze_expr(&binary.left);
+        let right = self.analyze_expr(&binary.right);
+
+        // JS gives `Infinity` or `NaN` here rather than failing
+        if binary.operator == BinaryOp::Divide && is_zero_literal(&binary.right) {
+            self.warnings.push(SemanticWarning::DivisionByZero { span: binary.span });
+        }
+
+        match binary.operator {
+            // `+` on a string concatenates, coercing an `Int` operand
+            BinaryOp::Add if left == Some(TypeName::Str) => {
+                if !matches!(right, None | Some(TypeName::Int)) {
+                    self.expect_type(&TypeName::Str, right.as_ref(), binary.span);
+                }
+                Some(TypeName::Str)
+            }
+            BinaryOp::Add
+                if right == Some(TypeName::Str)
+                    && (left.is_none() || self.options.int_str_concat && left == Some(TypeName::Int)) =>
+            {
+                Some(TypeName::Str)
+            }
+            BinaryOp::Add
+            | BinaryOp::Subtract
+            | BinaryOp::Multiply
+            | BinaryOp::Divide
+            | BinaryOp::Power
+            | BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::ShiftLeft
+            | BinaryOp::ShiftRight => {
+                self.expect_type(&TypeName::Int, left.as_ref(), binary.span);
+                self.expect_type(&TypeName::Int, right.as_ref(), binary.span);
+                Some(TypeName::Int)
+            }
+            BinaryOp::And | BinaryOp::Or => {
+                self.expect_type(&TypeName::Boolean, left.as_ref(), binary.span);
+                self.expect_type(&TypeName::Boolean, right.as_ref(), binary.span);
+                Some(TypeName::Boolean)
+            }
+            BinaryOp::Equal
+            | BinaryOp::NotEqual
+            | BinaryOp::Greater
+            | BinaryOp::Less
+            | BinaryOp::GreaterEqual
+            | BinaryOp::LessEqual => {
+                if let Some(left) = &left {
+                    self.expect_type(left, right.as_ref(), binary.span);
+                }
+                Some(TypeName::Boolean)
+            }
+        }
+    }
+
+    fn analyze_exprs(&mut self, exprs: &[Expr]) {
+        for expr in exprs {
+            self.analyze_expr(expr);
+        }
+    }
+
+    /// Reports a mismatch unless `found` is unknown or assignable to `expected`.
+    fn expect_type(&mut self, expected: &TypeName, found: Option<&TypeName>, span: Span) {
+        if let Some(found) = found {
+            if !self.symbols.is_assignable(expected, found) {
+                self.errors.push(SemanticError::TypeMismatch {
+                    expected: expected.clone(),
+                    found: found.clone(),
+                    span,
+                });
+            }
+        }
+    }
+
+    /// `arity` is the range of accepted argument counts, from the required
+    /// parameters up to all of them.
+    fn check_arity(&mut self, name: &str, arity: (usize, usize), found: usize, span: Span) {
+        let (required, total) = arity;
+        if found < required || found > total {
+            let expected = if found < required { required } else { total };
+            self.errors.push(SemanticError::ArityMismatch {
+                name: name.to_string(),
+                expected,
+                found,
+                span,
+            });
+        }
+    }
+
+    /// Reports `name` unless some `class` declares it, in which case it
+    /// returns true.
+    fn check_class(&mut self, name: &str, span: Span) -> bool {
+        let known = self.symbols.classes.contains_key(name);
+        if !known {
+            self.errors.push(SemanticError::UnknownClass {
+                name: name.to_string(),
+                span,
+            });
+        }
+        known
+    }
+
+    fn check_this(&mut self, span: Span) {
+        if self.current_class.is_none() {
+            self.errors.push(SemanticError::ThisOutsideClass { span });
+        }
+    }
+
+    /// `super` only means something inside a class that extends another.
+    fn check_super(&mut self, span: Span) {
+        let has_parent = self
+            .current_class
+            .as_ref()
+            .and_then(|class| self.symbols.classes.get(class))
+            .is_some_and(|info| info.extends.is_some());
+        if !has_parent {
+            self.errors.push(SemanticError::SuperOutsideSubclass { span });
+        }
+    }
+
+    /// [`Analyzer::check_defined`] for a read of `name`, which counts as a use.
+    fn check_used(&mut self, name: &str, span: Span) -> Option<TypeName> {
+        match self.scopes.lookup_used(name) {
+            Some(symbol) => symbol.var_type.clone(),
+            None => self.check_defined(name, span),
+        }
+    }
+
+    /// Reports `name` if it isn't in scope, otherwise returns its type.
+    fn check_defined(&mut self, name: &str, span: Span) -> Option<TypeName> {
+        match self.scopes.lookup(name) {
+            Some(symbol) => symbol.var_type.clone(),
+            None => {
+                self.errors.push(SemanticError::UndefinedVariable {
+                    name: name.to_string(),
+                    span,
+                });
+                None
+            }
+        }
+    }
+}
+
+fn is_zero_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::IntegerLiteral(literal) => literal.value == 0,
+        Expr::Grouped(inner, _) => is_zero_literal(inner),
+        _ => false,
+    }
+}
+
+/// Whether every path through `stmt` ends in a `return`. Loops are assumed
+/// to be able to exit, so a `while` never counts on its own.
+fn always_returns(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_) => true,
+        Stmt::Block(block) => block.statements.iter().any(always_returns),
+        // the body of a `do`/`while` runs at least once, unless it jumps out
+        // of the loop before reaching its `return`
+        Stmt::DoWhile(do_while) => always_returns(&do_while.body) && !leaves_loop(&do_while.body),
+        Stmt::If(if_stmt) => {
+            always_returns(&if_stmt.then_branch)
+                && if_stmt.else_branch.as_deref().is_some_and(always_returns)
+        }
+        // without a `_` arm an unmatched value skips every body
+        Stmt::Match(match_stmt) => {
+            match_stmt.default.as_deref().is_some_and(always_returns)
+                && match_stmt.arms.iter().all(|arm| always_returns(&arm.body))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `stmt` can jump out of the loop whose body it is: a `break` ends
+/// the loop, and a `continue` goes back to a condition that may be false.
+/// Inner loops take their own jumps.
+fn leaves_loop(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Break(_) | Stmt::Continue(_) => true,
+        Stmt::Block(block) => block.statements.iter().any(leaves_loop),
+        Stmt::If(if_stmt) => {
+            leaves_loop(&if_stmt.then_branch) || if_stmt.else_branch.as_deref().is_some_and(leaves_loop)
+        }
+        Stmt::Match(match_stmt) => {
+            match_stmt.arms.iter().any(|arm| leaves_loop(&arm.body))
+                || match_stmt.default.as_deref().is_some_and(leaves_loop)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn analyzer_for(input: &str) -> Analyzer {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        assert!(!parser.has_errors(), "{:?}", parser.get_errors());
+
+        let mut analyzer = Analyzer::new();
+        analyzer.analyze(&ast);
+        analyzer
+    }
+
+    fn analyze(input: &str) -> Vec<SemanticError> {
+        analyzer_for(input).get_errors().to_vec()
+    }
+
+    fn warnings(input: &str) -> Vec<SemanticWarning> {
+        analyzer_for(input).get_warnings().to_vec()
+    }
+
+    #[test]
+    fn test_undefined_variable() {
+        let errors = analyze("println(y);");
+        assert_eq!(
+            errors,
+            vec![SemanticError::UndefinedVariable {
+                name: "y".to_string(),
+                span: Span::new(8, 9, 1, 9)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_undefined_function() {
+        let errors = analyze("println(undefinedFn());");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UndefinedFunction { name, span }] if name == "undefinedFn" && span.column == 9
+        ));
+
+        // functions are visible from top-level statements before and after their definition
+        assert!(analyze("println(fib(3)); fun fib(n: Int) -> Int { return n; } println(fib(4));").is_empty());
+        assert!(matches!(
+            analyze("fun f() -> Void { g(); }").as_slice(),
+            [SemanticError::UndefinedFunction { name, .. }] if name == "g"
+        ));
+    }
+
+    #[test]
+    fn test_declared_variable() {
+        assert!(analyze("let y: Int = 1; println(y);").is_empty());
+    }
+
+    #[test]
+    fn test_params_in_scope() {
+        assert!(analyze("fun square(x: Int) -> Int { return x * x; }").is_empty());
+
+        let errors = analyze("fun square(x: Int) -> Int { return y; }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UndefinedVariable { name, .. }] if name == "y"
+        ));
+    }
+
+    #[test]
+    fn test_block_scope_ends_with_block() {
+        let errors = analyze("{ let x: Int = 1; } println(x);");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_initializer_cannot_reference_itself() {
+        let errors = analyze("let x: Int = x + 1;");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_assign_to_undeclared() {
+        let errors = analyze("z = 5;");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UndefinedVariable { name, .. }] if name == "z"
+        ));
+    }
+
+    #[test]
+    fn test_fields_through_this() {
+        let source = "class Rect {
+            let width: Int;
+            init(width: Int) { this.width = width; }
+            meth area() -> Int { return this.width; }
+            meth broken() -> Int { return width; }
+        }";
+        let errors = analyze(source);
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UndefinedVariable { name, .. }] if name == "width"
+        ));
+    }
+
+    #[test]
+    fn test_field_assign_checks_receiver() {
+        assert!(analyze("class Rect { let width: Int; init() {} } let r: Rect = new Rect(); r.width = 2;").is_empty());
+        assert_eq!(analyze("q.width = 2;").len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_declaration_in_same_scope() {
+        let errors = analyze("let x: Int = 1;\nlet x: Int = 2;");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::DuplicateDeclaration { name, first_span, second_span }]
+            if name == "x" && first_span.line == 1 && second_span.line == 2
+        ));
+    }
+
+    #[test]
+    fn test_shadowing_in_nested_block() {
+        assert!(analyze("let x: Int = 1; { let x: Int = 2; println(x); } println(x);").is_empty());
+        assert!(analyze("fun f(x: Int) -> Int { while (x > 0) { let x: Int = 0; } return x; }").is_empty());
+    }
+
+    #[test]
+    fn test_let_redeclaring_param() {
+        let errors = analyze("fun f(x: Int) -> Int { let x: Int = 2; return x; }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::DuplicateDeclaration { name, .. }] if name == "x"
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic_on_mixed_types() {
+        let errors = analyze("let x: Int = 1 + \"x\";");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Int, found: TypeName::Str, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        assert!(analyze(r#"let s: Str = "x" + "y";"#).is_empty());
+        assert!(analyze(r#"let s: Str = "n=" + 5;"#).is_empty());
+
+        let errors = analyze(r#"let n: Int = 5 + "n";"#);
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Int, found: TypeName::Str, .. }]
+        ));
+
+        let errors = analyze(r#"let s: Str = "b=" + true;"#);
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Str, found: TypeName::Boolean, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_int_str_concat_option() {
+        let tokens = Lexer::new(r#"let s: Str = 5 + "n";"#).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let mut analyzer = Analyzer::with_options(AnalyzerOptions { int_str_concat: true, ..AnalyzerOptions::default() });
+        analyzer.analyze(&ast);
+        assert!(!analyzer.has_errors(), "{:?}", analyzer.get_errors());
+    }
+
+    #[test]
+    fn test_comparison_requires_matching_operands() {
+        assert!(analyze("let b: Boolean = 1 < 2;").is_empty());
+
+        let errors = analyze("let b: Boolean = 1 == true;");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Int, found: TypeName::Boolean, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_declared_type_mismatch() {
+        let errors = analyze("let s: Str = 5;");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Str, found: TypeName::Int, .. }]
+        ));
+
+        let errors = analyze("let n = 5; n = \"five\";");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_return_type_mismatch() {
+        let errors = analyze("fun name() -> Int { return \"lava\"; }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Int, found: TypeName::Str, .. }]
+        ));
+
+        assert!(analyze("fun double(x: Int) -> Int { return x * 2; }").is_empty());
+    }
+
+    #[test]
+    fn test_class_types_flow_through_members() {
+        let source = "class Rect {
+            let width: Int;
+            init(width: Int) { this.width = width; }
+            meth area() -> Int { return this.width * this.width; }
+            meth label() -> Str { return this.width; }
+        }
+        let r: Rect = new Rect(2);
+        let a: Int = r.area();
+        let w: Str = r.width;";
+        let errors = analyze(source);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_subclass_assignable_to_parent() {
+        let source = "class Animal { init() {} }
+            class Cat extends Animal { init() { super(); } }
+            let pet: Animal = new Cat();
+            let cat: Cat = new Animal();";
+        let errors = analyze(source);
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Class(cat), .. }] if cat == "Cat"
+        ));
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        let errors = analyze("break;");
+        assert!(matches!(errors.as_slice(), [SemanticError::BreakOutsideLoop { .. }]));
+
+        let errors = analyze("fun f(x: Int) -> Void { if (x > 0) { continue; } }");
+        assert!(matches!(errors.as_slice(), [SemanticError::ContinueOutsideLoop { .. }]));
+    }
+
+    #[test]
+    fn test_break_in_match() {
+        let errors = analyze("let x: Int = 1; while (true) { match (x) { 1 => break; _ => {} } }");
+        assert!(matches!(errors.as_slice(), [SemanticError::BreakInMatch { .. }]));
+
+        assert!(analyze("let x: Int = 1; while (true) { match (x) { 1 => continue; _ => {} } break; }").is_empty());
+        assert!(analyze("let x: Int = 1; match (x) { 1 => { while (true) { break; } } }").is_empty());
+    }
+
+    #[test]
+    fn test_match_always_returns() {
+        let source = "fun f(x: Int) -> Int { match (x) { 1 => return 1; _ => return 0; } }";
+        assert!(analyze(source).is_empty());
+        let source = "fun f(x: Int) -> Int { match (x) { 1 => return 1; } }";
+        assert!(matches!(analyze(source).as_slice(), [SemanticError::MissingReturn { .. }]));
+    }
+
+    #[test]
+    fn test_type_names_are_recorded() {
+        let tokens = Lexer::new("let n: Int = 1; let a: Str = typeName(n); let b: Str = typeName(nope);")
+            .tokenize()
+            .unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let mut analyzer = Analyzer::new();
+        analyzer.analyze(&ast);
+
+        // `nope` is undefined, so only the first call has a type
+        let types: Vec<&TypeName> = analyzer.get_type_names().values().collect();
+        assert_eq!(types, [&TypeName::Int]);
+        assert!(matches!(analyzer.get_errors(), [SemanticError::UndefinedVariable { .. }]));
+    }
+
+    #[test]
+    fn test_lambdas_as_values() {
+        assert!(analyze(
+            "fun apply(f: (Int) -> Int, x: Int) -> Int { return f(x); }
+             let inc: (Int) -> Int = fun (x: Int) -> Int { return x + 1; };
+             let n: Int = apply(inc, 1) + apply(fun (y: Int) -> Int { return y * 2; }, 3);"
+        )
+        .is_empty());
+
+        assert!(matches!(
+            analyze("let f: (Int) -> Str = fun (x: Int) -> Int { return x; };").as_slice(),
+            [SemanticError::TypeMismatch { .. }]
+        ));
+        assert!(matches!(
+            analyze("fun g(f: (Int) -> Int) -> Int { return f(1, 2); }").as_slice(),
+            [SemanticError::ArityMismatch { .. }]
+        ));
+        assert!(matches!(
+            analyze("let x: Int = 1; println(x(2));").as_slice(),
+            [SemanticError::NotAFunction { name, found: TypeName::Int, .. }] if name == "x"
+        ));
+        // a local shadows the global function of the same name
+        assert!(matches!(
+            analyze("fun f() -> Int { return 1; } fun g() -> Void { let f: Str = \"s\"; println(f()); }").as_slice(),
+            [SemanticError::NotAFunction { found: TypeName::Str, .. }]
+        ));
+        assert!(matches!(
+            analyze("let f: (Int) -> Int = fun (x: Int) -> Int { println(x); };").as_slice(),
+            [SemanticError::MissingReturn { name, .. }] if name == "lambda"
+        ));
+        // the loop is outside the lambda, so the `break` has nothing to leave
+        assert!(matches!(
+            analyze("while (true) { let f: () -> Void = fun () -> Void { break; }; }").as_slice(),
+            [SemanticError::BreakOutsideLoop { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_multi_var_decl_scope() {
+        assert!(analyze("let a: Int = 1, b: Int = a + 1; println(b);").is_empty());
+
+        let errors = analyze("let a: Int = b, b: Int = 1; println(a);");
+        assert!(matches!(errors.as_slice(), [SemanticError::UndefinedVariable { name, .. }] if name == "b"));
+        let errors = analyze("let a: Int = 1, b: Str = a; println(b);");
+        assert!(matches!(errors.as_slice(), [SemanticError::TypeMismatch { .. }]));
+    }
+
+    #[test]
+    fn test_for_range_scope() {
+        assert!(analyze("for (i in 0..10) { let x: Int = i; break; }").is_empty());
+
+        let errors = analyze("for (i in 0..\"ten\") {} println(i);");
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                SemanticError::TypeMismatch { expected: TypeName::Int, found: TypeName::Str, .. },
+                SemanticError::UndefinedVariable { name, .. },
+            ] if name == "i"
+        ));
+    }
+
+    #[test]
+    fn test_break_inside_loop() {
+        assert!(analyze("let i: Int = 0; while (true) { if (i > 3) { break; } i = i + 1; }").is_empty());
+        assert!(analyze("let i: Int = 0; if (i == 0) { while (true) { continue; } }").is_empty());
+    }
+
+    #[test]
+    fn test_this_at_top_level() {
+        let errors = analyze("println(this);");
+        assert!(matches!(errors.as_slice(), [SemanticError::ThisOutsideClass { .. }]));
+
+        let errors = analyze("fun f() -> Void { this.x = 1; }");
+        assert!(matches!(errors.as_slice(), [SemanticError::ThisOutsideClass { .. }]));
+    }
+
+    #[test]
+    fn test_this_in_method() {
+        let source = "class Counter {
+            let count: Int;
+            init() { this.count = 0; }
+            meth get() -> Int { return this.count; }
+        }";
+        assert!(analyze(source).is_empty());
+    }
+
+    #[test]
+    fn test_super_without_parent() {
+        let errors = analyze("class Animal { init() { super(); } }");
+        assert!(matches!(errors.as_slice(), [SemanticError::SuperOutsideSubclass { .. }]));
+
+        let errors = analyze("class Animal { init() {} meth speak() -> Int { return super.speak(); } }");
+        assert!(matches!(errors.as_slice(), [SemanticError::SuperOutsideSubclass { .. }]));
+
+        let source = "class Animal { init() {} meth speak() -> Int { return 0; } }
+            class Cat extends Animal { init() { super(); } meth speak() -> Int { return super.speak(); } }";
+        assert!(analyze(source).is_empty());
+        // JS only allows `super(...)` in a constructor
+        let source = "class Animal { init() {} }
+            class Cat extends Animal { init() { super(); } meth m() -> Void { super(); } }";
+        assert!(matches!(analyze(source).as_slice(), [SemanticError::MisplacedSuperCall { .. }]));
+        assert!(matches!(analyze("super();").as_slice(), [SemanticError::MisplacedSuperCall { .. }]));
+    }
+
+    #[test]
+    fn test_super_call_position() {
+        let parent = "class Animal { init(legs: Int) {} }";
+        let source = format!("{} class Cat extends Animal {{ init() {{ super(4); println(1); }} }}", parent);
+        assert!(analyze(&source).is_empty());
+
+        let source = format!("{} class Cat extends Animal {{ init() {{ println(1); super(4); }} }}", parent);
+        assert!(matches!(
+            analyze(&source).as_slice(),
+            [SemanticError::SuperNotFirst { span }] if span.column == 85
+        ));
+
+        let source = format!("{} class Cat extends Animal {{ init() {{ println(1); }} }}", parent);
+        assert!(matches!(
+            analyze(&source).as_slice(),
+            [SemanticError::MissingSuperCall { class, .. }] if class == "Cat"
+        ));
+        let source = format!("{} class Cat extends Animal {{ init() {{}} }}", parent);
+        assert!(analyze(&source).is_empty());
+
+        // only one `super(...)` runs, straight from the constructor body
+        let source = format!("{} class Cat extends Animal {{ init() {{ super(4); super(4); }} }}", parent);
+        assert!(matches!(analyze(&source).as_slice(), [SemanticError::MisplacedSuperCall { .. }]));
+        let source = format!("{} class Cat extends Animal {{ init() {{ if (true) {{ super(4); }} }} }}", parent);
+        assert!(matches!(
+            analyze(&source).as_slice(),
+            [SemanticError::MissingSuperCall { .. }, SemanticError::MisplacedSuperCall { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_unreachable_after_return() {
+        let warnings = warnings("fun f() -> Int {\n return 5;\n println(1);\n println(2);\n}");
+        assert!(matches!(
+            warnings.as_slice(),
+            [SemanticWarning::UnreachableCode { span }] if span.line == 3
+        ));
+    }
+
+    #[test]
+    fn test_unreachable_after_break() {
+        let analyzer = analyzer_for("while (true) { break; println(1); }");
+        assert!(!analyzer.has_errors());
+        assert!(matches!(
+            analyzer.diagnostics().as_slice(),
+            [Diagnostic::Warning(SemanticWarning::UnreachableCode { .. })]
+        ));
+    }
+
+    #[test]
+    fn test_trailing_return_is_reachable() {
+        assert!(warnings("fun f(x: Int) -> Int { if (x > 0) { return 1; } return 0; }").is_empty());
+    }
+
+    #[test]
+    fn test_functions_see_top_level_variables() {
+        assert!(analyze("fun show() -> Void { println(limit); } let limit: Int = 3;").is_empty());
+    }
+
+    #[test]
+    fn test_function_called_with_too_few_args() {
+        let errors = analyze("fun add(a: Int, b: Int) -> Int { return a + b; } let x: Int = add(1);");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::ArityMismatch { name, expected: 2, found: 1, .. }] if name == "add"
+        ));
+    }
+
+    #[test]
+    fn test_constructor_and_method_arity() {
+        let errors = analyze(
+            "class Point { let x: Int; init(x: Int) { this.x = x; } meth get() -> Int { return this.x; } }
+             let p: Point = new Point(1, 2);
+             let q: Point = new Point(1);
+             let y: Int = q.get(3);",
+        );
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                SemanticError::ArityMismatch { name: point, expected: 1, found: 2, .. },
+                SemanticError::ArityMismatch { name: get, expected: 0, found: 1, .. },
+            ] if point == "Point" && get == "get"
+        ));
+    }
+
+    #[test]
+    fn test_default_params() {
+        assert!(analyze(
+            "fun f(a: Int, b: Int = a + 1, c: Str = \"c\") -> Int { return a + b; }
+             let x: Int = f(1); let y: Int = f(1, 2); let z: Int = f(1, 2, \"z\");"
+        )
+        .is_empty());
+
+        let errors = analyze("fun f(a: Int = 1) -> Int { return a; } let x: Int = f(1, 2);");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::ArityMismatch { expected: 1, found: 2, .. }]
+        ));
+
+        let errors = analyze("fun f(a: Int = true, b: Int) -> Void {}");
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                SemanticError::TypeMismatch { expected: TypeName::Int, found: TypeName::Boolean, .. },
+                SemanticError::MissingDefault { name, .. },
+            ] if name == "b"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_definitions() {
+        let errors = analyze("fun f() -> Void {} fun g() -> Void {} fun f() -> Void {}");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::DuplicateDefinition { name, kind: "function", first_span, second_span }]
+            if name == "f" && first_span.column == 5 && second_span.column == 43
+        ));
+
+        let errors = analyze("class A { init() {} } class A { init() {} }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::DuplicateDefinition { name, kind: "class", .. }] if name == "A"
+        ));
+
+        // functions and classes share one namespace
+        let errors = analyze("class A { init() {} } fun A() -> Void {}");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::DuplicateDefinition { name, kind: "function", first_span, .. }]
+                if name == "A" && first_span.column == 7
+        ));
+    }
+
+    #[test]
+    fn test_main_has_params() {
+        let analyze_run = |input: &str| {
+            let tokens = Lexer::new(input).tokenize().unwrap();
+            let ast = Parser::new(tokens).parse().unwrap();
+            let mut analyzer = Analyzer::with_options(AnalyzerOptions { auto_run_main: true, ..AnalyzerOptions::default() });
+            analyzer.analyze(&ast);
+            analyzer.get_errors().to_vec()
+        };
+
+        assert!(analyze_run("fun main(verbose: Boolean = false) -> Void {}").is_empty());
+
+        let errors = analyze_run("fun main(args: Str) -> Void {}");
+        assert!(matches!(errors.as_slice(), [SemanticError::MainHasParams { .. }]));
+
+        // nothing calls `main` unless the generated code is asked to
+        assert!(analyze("fun main(args: Str) -> Void {}").is_empty());
+    }
+
+    #[test]
+    fn test_correct_arity() {
+        assert!(analyze("fun add(a: Int, b: Int) -> Int { return a + b; } let x: Int = add(1, 2);").is_empty());
+    }
+
+    #[test]
+    fn test_return_value_from_void() {
+        let errors = analyze("fun f() -> Void { return 5; }");
+        assert!(matches!(errors.as_slice(), [SemanticError::ReturnValueFromVoid { .. }]));
+
+        let errors = analyze("class A { init() {} meth m() -> Void { return 1; } }");
+        assert!(matches!(errors.as_slice(), [SemanticError::ReturnValueFromVoid { .. }]));
+
+        assert!(analyze("fun f() -> Void { return; }").is_empty());
+        assert!(analyze("fun g() -> Int { return 1; }").is_empty());
+    }
+
+    #[test]
+    fn test_len_of_str_is_int() {
+        assert!(analyze("let s: Str = \"abc\"; let n: Int = len(s); println(n);").is_empty());
+        assert!(matches!(
+            analyze("let n: Int = len(5); println(n);").as_slice(),
+            [SemanticError::TypeMismatch { expected: TypeName::Str, found: TypeName::Int, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_missing_return() {
+        assert!(analyze("fun g() -> Int { return 1; }").is_empty());
+        assert!(analyze("fun g(x: Int) -> Int { if (x > 0) { return 1; } else { return 2; } }").is_empty());
+
+        let errors = analyze("fun g(x: Int) -> Int { if (x > 0) { return 1; } }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::MissingReturn { name, .. }] if name == "g"
+        ));
+        let errors = analyze("class A { init() {} meth m() -> Int { println(1); } }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::MissingReturn { name, .. }] if name == "m"
+        ));
+
+        // Void bodies never need a return
+        assert!(analyze("fun g(x: Int) -> Void { if (x > 0) { return; } }").is_empty());
+
+        assert!(analyze("fun f() -> Int { do { return 1; } while (true); }").is_empty());
+        for body in ["if (x) break; return 1;", "if (x) continue; return 1;", "match (1) { _ => continue; } return 1;"] {
+            let input = format!("fun f(x: Boolean) -> Int {{ do {{ {body} }} while (x); }}");
+            assert!(
+                matches!(analyze(&input).as_slice(), [SemanticError::MissingReturn { .. }]),
+                "{body}"
+            );
+        }
+        // this `break` belongs to the inner loop
+        assert!(analyze("fun f(x: Boolean) -> Int { do { while (x) { break; } return 1; } while (x); }").is_empty());
+        assert!(matches!(
+            analyze("fun f() -> Int { while (true) { return 1; } }").as_slice(),
+            [SemanticError::MissingReturn { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        let found = warnings("fun f(unused: Int) -> Void { let x: Int = 1; let y: Int = 2; y = 3; println(x); }");
+        assert!(matches!(
+            found.as_slice(),
+            [SemanticWarning::UnusedVariable { name, span }] if name == "y" && span.column == 46
+        ));
+
+        assert!(warnings("fun f() -> Int { let x: Int = 1; { let y: Int = x; return y; } }").is_empty());
+        assert!(warnings("class A { init() { let a: A = this; a.b = 1; } }").is_empty());
+        // top-level bindings may be read by code outside the program
+        assert!(warnings("let x: Int = 1;").is_empty());
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        assert!(matches!(
+            warnings("let x: Int = 5 / 0;").as_slice(),
+            [SemanticWarning::DivisionByZero { .. }]
+        ));
+        assert!(matches!(
+            warnings("let x: Int = 5 / (0);").as_slice(),
+            [SemanticWarning::DivisionByZero { .. }]
+        ));
+        assert!(warnings("let x: Int = 2; let y: Int = 5 / x;").is_empty());
+    }
+
+    #[test]
+    fn test_new_unknown_class() {
+        let errors = analyze("let s: Shape = new Shape();");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UnknownClass { name, .. }] if name == "Shape"
+        ));
+
+        // classes may be used before they're declared
+        assert!(analyze("let s: Shape = new Shape(); class Shape { init() {} }").is_empty());
+    }
+
+    #[test]
+    fn test_extends_unknown_class() {
+        let errors = analyze("class Cat extends Animal { init() { super(); } }");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UnknownClass { name, .. }] if name == "Animal"
+        ));
+    }
+
+    #[test]
+    fn test_method_resolution() {
+        let classes = "class Shape { init() {} meth area() -> Int { return 0; } }
+            class Square extends Shape { init() { super(); } meth side() -> Int { return 1; } }";
+
+        assert!(analyze(&format!("{} let s: Square = new Square(); let a: Int = s.side();", classes)).is_empty());
+        assert!(analyze(&format!("{} let s: Square = new Square(); let a: Int = s.area();", classes)).is_empty());
+
+        let errors = analyze(&format!("{} let s: Square = new Square(); let a: Int = s.aera();", classes));
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::UnknownMethod { class, method, .. }] if class == "Square" && method == "aera"
+        ));
+    }
+
+    #[test]
+    fn test_assign_to_const() {
+        assert!(analyze("let x: Int = 1; x = 2;").is_empty());
+
+        let errors = analyze("const x: Int = 1; x = 2;");
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::AssignToConst { name, .. }] if name == "x"
+        ));
+    }
+
+    #[test]
+    fn test_assign_to_const_field() {
+        let source = "class Circle {
+            const radius: Int;
+            init(radius: Int) { this.radius = radius; }
+            meth grow() -> Void { this.radius = this.radius + 1; }
+        }";
+        let errors = analyze(source);
+        assert!(matches!(
+            errors.as_slice(),
+            [SemanticError::AssignToConst { name, span }] if name == "radius" && span.line == 4
+        ));
+    }
+}
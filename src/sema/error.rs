@@ -0,0 +1,299 @@
+use crate::error::{diagnostic_json, print_diagnostic, print_error_list, Severity};
+use crate::lexer::{Span, TypeName};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum SemanticError {
+    #[error("Undefined variable '{name}' at {span}")]
+    UndefinedVariable { name: String, span: Span },
+
+    #[error("Undefined function '{name}' at {span}")]
+    UndefinedFunction { name: String, span: Span },
+
+    #[error("'{name}' is called but has type '{found}', not a function type, at {span}")]
+    NotAFunction {
+        name: String,
+        found: TypeName,
+        span: Span,
+    },
+
+    #[error("'{name}' is already declared in this scope at {first_span}, redeclared at {second_span}")]
+    DuplicateDeclaration {
+        name: String,
+        first_span: Span,
+        second_span: Span,
+    },
+
+    #[error("Duplicate {kind} '{name}', first defined at {first_span} and again at {second_span}")]
+    DuplicateDefinition {
+        name: String,
+        /// What the second definition is: `"function"` or `"class"`.
+        kind: &'static str,
+        first_span: Span,
+        second_span: Span,
+    },
+
+    #[error("'main' is the program's entry point and can't take required parameters at {span}")]
+    MainHasParams { span: Span },
+
+    #[error("Type mismatch: expected '{expected}' but found '{found}' at {span}")]
+    TypeMismatch {
+        expected: TypeName,
+        found: TypeName,
+        span: Span,
+    },
+
+    #[error("'break' used outside of a loop at {span}")]
+    BreakOutsideLoop { span: Span },
+
+    #[error("'continue' used outside of a loop at {span}")]
+    ContinueOutsideLoop { span: Span },
+
+    #[error("'this' used outside of a class at {span}")]
+    ThisOutsideClass { span: Span },
+
+    #[error("'super' used in a class that does not extend another class at {span}")]
+    SuperOutsideSubclass { span: Span },
+
+    #[error("'super(...)' must come before any other statement in the constructor at {span}")]
+    SuperNotFirst { span: Span },
+
+    #[error("'super(...)' can only be called once, as a statement of the constructor itself, at {span}")]
+    MisplacedSuperCall { span: Span },
+
+    #[error("The constructor of '{class}' must call 'super(...)' because it extends another class, at {span}")]
+    MissingSuperCall { class: String, span: Span },
+
+    #[error("'break' inside a 'match' arm would only leave the match, not the loop, at {span}")]
+    BreakInMatch { span: Span },
+
+    #[error("'{name}' can reach the end of its body without returning a value at {span}")]
+    MissingReturn { name: String, span: Span },
+
+    #[error("'{name}' expects {expected} argument(s) but was called with {found} at {span}")]
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+
+    #[error("Cannot return a value from a function declared '-> Void' at {span}")]
+    ReturnValueFromVoid { span: Span },
+
+    #[error("Unknown class '{name}' at {span}")]
+    UnknownClass { name: String, span: Span },
+
+    #[error("Parameter '{name}' needs a default because an earlier parameter has one at {span}")]
+    MissingDefault { name: String, span: Span },
+
+    #[error("Class '{class}' has no method '{method}' at {span}")]
+    UnknownMethod {
+        class: String,
+        method: String,
+        span: Span,
+    },
+
+    #[error("Cannot assign to '{name}' because it is declared 'const' at {span}")]
+    AssignToConst { name: String, span: Span },
+}
+
+impl SemanticError {
+    pub fn get_span(&self) -> Option<&Span> {
+        match self {
+            Self::UndefinedVariable { span, .. }
+            | Self::UndefinedFunction { span, .. }
+            | Self::MissingSuperCall { span, .. }
+            | Self::NotAFunction { span, .. }
+            | Self::UnknownClass { span, .. }
+            | Self::AssignToConst { span, .. }
+            | Self::MissingDefault { span, .. }
+            | Self::MissingReturn { span, .. } => Some(span),
+            Self::DuplicateDeclaration { second_span, .. }
+            | Self::DuplicateDefinition { second_span, .. } => Some(second_span),
+            Self::TypeMismatch { span, .. }
+            | Self::BreakOutsideLoop { span }
+            | Self::ContinueOutsideLoop { span }
+            | Self::ThisOutsideClass { span }
+            | Self::SuperOutsideSubclass { span }
+            | Self::SuperNotFirst { span }
+            | Self::MisplacedSuperCall { span }
+            | Self::BreakInMatch { span }
+            | Self::ArityMismatch { span, .. }
+            | Self::ReturnValueFromVoid { span }
+            | Self::MainHasParams { span }
+            | Self::UnknownMethod { span, .. } => Some(span),
+        }
+    }
+
+    pub fn get_code(&self) -> &str {
+        match self {
+            Self::UndefinedVariable { .. } => "E100",
+            Self::DuplicateDeclaration { .. } => "E101",
+            Self::TypeMismatch { .. } => "E102",
+            Self::BreakOutsideLoop { .. } => "E103",
+            Self::ContinueOutsideLoop { .. } => "E104",
+            Self::ThisOutsideClass { .. } => "E105",
+            Self::SuperOutsideSubclass { .. } => "E106",
+            Self::ArityMismatch { .. } => "E107",
+            Self::ReturnValueFromVoid { .. } => "E108",
+            Self::UnknownClass { .. } => "E109",
+            Self::UnknownMethod { .. } => "E110",
+            Self::AssignToConst { .. } => "E111",
+            Self::MissingDefault { .. } => "E112",
+            Self::DuplicateDefinition { .. } => "E113",
+            Self::MainHasParams { .. } => "E114",
+            Self::SuperNotFirst { .. } => "E115",
+            Self::BreakInMatch { .. } => "E116",
+            Self::MissingReturn { .. } => "E117",
+            Self::UndefinedFunction { .. } => "E118",
+            Self::NotAFunction { .. } => "E119",
+            Self::MisplacedSuperCall { .. } => "E120",
+            Self::MissingSuperCall { .. } => "E121",
+        }
+    }
+
+    pub fn print_with_context(&self, source: &str) {
+        print_diagnostic(Severity::Error, self.get_code(), &self.to_string(), self.get_span(), source);
+    }
+
+    pub fn to_json(&self) -> String {
+        diagnostic_json(Severity::Error, self.get_code(), &self.to_string(), self.get_span())
+    }
+}
+
+/// Problems that don't stop compilation but are probably mistakes.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum SemanticWarning {
+    #[error("Unreachable code at {span}")]
+    UnreachableCode { span: Span },
+
+    #[error("Variable '{name}' is declared but never used at {span}")]
+    UnusedVariable { name: String, span: Span },
+
+    #[error("Division by zero at {span}")]
+    DivisionByZero { span: Span },
+}
+
+impl SemanticWarning {
+    pub fn get_span(&self) -> Option<&Span> {
+        match self {
+            Self::UnreachableCode { span }
+            | Self::UnusedVariable { span, .. }
+            | Self::DivisionByZero { span } => Some(span),
+        }
+    }
+
+    pub fn get_code(&self) -> &str {
+        match self {
+            Self::UnreachableCode { .. } => "W001",
+            Self::UnusedVariable { .. } => "W003",
+            Self::DivisionByZero { .. } => "W004",
+        }
+    }
+
+    pub fn print_with_context(&self, source: &str) {
+        print_diagnostic(Severity::Warning, self.get_code(), &self.to_string(), self.get_span(), source);
+    }
+
+    pub fn to_json(&self) -> String {
+        diagnostic_json(Severity::Warning, self.get_code(), &self.to_string(), self.get_span())
+    }
+}
+
+/// Either kind of semantic finding, for callers that report them together.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Diagnostic {
+    Error(SemanticError),
+    Warning(SemanticWarning),
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Error(_) => Severity::Error,
+            Self::Warning(_) => Severity::Warning,
+        }
+    }
+
+    pub fn get_span(&self) -> Option<&Span> {
+        match self {
+            Self::Error(error) => error.get_span(),
+            Self::Warning(warning) => warning.get_span(),
+        }
+    }
+
+    pub fn get_code(&self) -> &str {
+        match self {
+            Self::Error(error) => error.get_code(),
+            Self::Warning(warning) => warning.get_code(),
+        }
+    }
+
+    pub fn print_with_context(&self, source: &str) {
+        match self {
+            Self::Error(error) => error.print_with_context(source),
+            Self::Warning(warning) => warning.print_with_context(source),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            Self::Error(error) => error.to_json(),
+            Self::Warning(warning) => warning.to_json(),
+        }
+    }
+}
+
+pub fn print_errors(errors: &[SemanticError], source: &str) {
+    print_error_list(errors, source, SemanticError::print_with_context);
+}
+
+pub fn print_warnings(warnings: &[SemanticWarning], source: &str) {
+    for warning in warnings {
+        warning.print_with_context(source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        let error = SemanticError::UndefinedVariable { name: "x".to_string(), span: Span::new(0, 0, 1, 2) };
+        assert_eq!(error.get_code(), "E100");
+        assert_eq!(error.get_span(), Some(&Span::new(0, 0, 1, 2)));
+        assert_eq!(error.to_string(), "Undefined variable 'x' at line 1, column 2");
+
+        let error = SemanticError::DuplicateDeclaration {
+            name: "x".to_string(),
+            first_span: Span::new(0, 0, 1, 2),
+            second_span: Span::new(0, 0, 3, 4),
+        };
+        assert_eq!(error.get_code(), "E101");
+        assert_eq!(error.get_span(), Some(&Span::new(0, 0, 3, 4)));
+
+        let error = SemanticError::TypeMismatch {
+            expected: TypeName::Int,
+            found: TypeName::Str,
+            span: Span::new(0, 0, 5, 6),
+        };
+        assert_eq!(error.get_code(), "E102");
+        assert_eq!(error.to_string(), "Type mismatch: expected 'Int' but found 'Str' at line 5, column 6");
+    }
+
+    #[test]
+    fn test_diagnostic_severity() {
+        let warning = SemanticWarning::UnreachableCode { span: Span::new(0, 0, 2, 1) };
+        assert_eq!(warning.get_code(), "W001");
+
+        let diagnostic = Diagnostic::Warning(warning);
+        assert_eq!(diagnostic.severity(), Severity::Warning);
+        assert_eq!(diagnostic.get_span(), Some(&Span::new(0, 0, 2, 1)));
+
+        let diagnostic = Diagnostic::Error(SemanticError::BreakOutsideLoop { span: Span::new(0, 0, 1, 1) });
+        assert_eq!(diagnostic.severity(), Severity::Error);
+        assert_eq!(diagnostic.get_code(), "E103");
+    }
+}
@@ -0,0 +1,9 @@
+mod analyzer;
+mod error;
+mod scope;
+mod symbols;
+
+pub use analyzer::{Analyzer, AnalyzerOptions};
+pub use error::{print_errors, print_warnings, Diagnostic, SemanticError, SemanticWarning};
+use scope::{ScopeStack, Symbol};
+use symbols::SymbolTable;
@@ -0,0 +1,101 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::lexer::{Span, TypeName};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub span: Span,
+    /// `None` when the type couldn't be worked out, e.g. an untyped `let`
+    /// whose initializer failed to check.
+    pub var_type: Option<TypeName>,
+    /// `false` for `const` bindings, which may not be reassigned.
+    pub mutable: bool,
+    /// Whether the binding has been read. Bindings that are never reported
+    /// as unused, such as parameters, start out `true`.
+    pub used: bool,
+}
+
+/// Stack of lexical scopes, innermost last.
+#[derive(Debug, Default)]
+pub struct ScopeStack {
+    scopes: Vec<HashMap<String, Symbol>>,
+}
+
+impl ScopeStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Leaves the innermost scope and hands back its bindings.
+    pub fn pop(&mut self) -> Option<HashMap<String, Symbol>> {
+        self.scopes.pop()
+    }
+
+    /// Declares `name` in the innermost scope. If the name was already declared
+    /// in that same scope the original symbol is kept and returned.
+    pub fn declare(&mut self, name: &str, symbol: Symbol) -> Option<Symbol> {
+        let scope = self.scopes.last_mut()?;
+        match scope.entry(name.to_string()) {
+            Entry::Occupied(existing) => Some(existing.get().clone()),
+            Entry::Vacant(slot) => {
+                slot.insert(symbol);
+                None
+            }
+        }
+    }
+
+    /// Looks `name` up from the innermost scope outwards.
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Like [`ScopeStack::lookup`], but also records that the binding was read.
+    pub fn lookup_used(&mut self, name: &str) -> Option<&Symbol> {
+        let symbol = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))?;
+        symbol.used = true;
+        Some(symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(line: usize) -> Symbol {
+        Symbol { span: Span::new(0, 0, line, 1), var_type: None, mutable: true, used: false }
+    }
+
+    #[test]
+    fn test_lookup_walks_outwards() {
+        let mut scopes = ScopeStack::new();
+        scopes.push();
+        scopes.declare("x", symbol(1));
+        scopes.push();
+        scopes.declare("y", symbol(2));
+
+        assert_eq!(scopes.lookup("x"), Some(&symbol(1)));
+        assert_eq!(scopes.lookup("y"), Some(&symbol(2)));
+
+        scopes.lookup_used("x");
+        let inner = scopes.pop().unwrap();
+        assert!(!inner["y"].used);
+        assert_eq!(scopes.lookup("y"), None);
+        assert!(scopes.lookup("x").unwrap().used);
+    }
+
+    #[test]
+    fn test_declare_reports_same_scope_redeclaration() {
+        let mut scopes = ScopeStack::new();
+        scopes.push();
+        assert_eq!(scopes.declare("x", symbol(1)), None);
+        scopes.push();
+        assert_eq!(scopes.declare("x", symbol(2)), None);
+        assert_eq!(scopes.declare("x", symbol(3)), Some(symbol(2)));
+        assert_eq!(scopes.declare("x", symbol(4)), Some(symbol(2)));
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{Entry, ParamDecl};
+use crate::lexer::TypeName;
+
+/// Parameter and return types of a function, method or constructor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signature {
+    pub params: Vec<TypeName>,
+    /// How many leading parameters have no default and must be passed.
+    pub required: usize,
+    pub return_type: TypeName,
+}
+
+impl Signature {
+    fn new(params: &[ParamDecl], return_type: &TypeName) -> Self {
+        Self {
+            params: params.iter().map(|param| param.param_type.clone()).collect(),
+            required: params.iter().take_while(|param| param.default.is_none()).count(),
+            return_type: return_type.clone(),
+        }
+    }
+}
+
+/// What the analyzer knows about a class before walking any bodies.
+#[derive(Debug, Clone, Default)]
+pub struct ClassInfo {
+    pub extends: Option<String>,
+    pub fields: HashMap<String, TypeName>,
+    /// Fields declared `const`, which only the constructor may assign.
+    pub const_fields: HashSet<String>,
+    pub methods: HashMap<String, Signature>,
+    pub constructor: Signature,
+}
+
+/// Program-wide declarations, collected up front so bodies can refer to
+/// functions and classes declared after them.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    pub functions: HashMap<String, Signature>,
+    pub classes: HashMap<String, ClassInfo>,
+}
+
+impl SymbolTable {
+    pub fn collect(entry: &Entry) -> Self {
+        let functions = entry
+            .fun_defs
+            .iter()
+            .map(|fun_def| {
+                let signature = Signature::new(&fun_def.params, &fun_def.return_type);
+                (fun_def.name.clone(), signature)
+            })
+            .collect();
+
+        let classes = entry
+            .class_defs
+            .iter()
+            .map(|class_def| {
+                let info = ClassInfo {
+                    extends: class_def.extends.clone(),
+                    fields: class_def
+                        .vars
+                        .iter()
+                        .map(|var| (var.name.clone(), var.var_type.clone()))
+                        .collect(),
+                    const_fields: class_def
+                        .vars
+                        .iter()
+                        .filter(|var| !var.mutable)
+                        .map(|var| var.name.clone())
+                        .collect(),
+                    methods: class_def
+                        .methods
+                        .iter()
+                        .map(|method| {
+                            let signature = Signature::new(&method.params, &method.return_type);
+                            (method.name.clone(), signature)
+                        })
+                        .collect(),
+                    constructor: Signature::new(
+                        &class_def.constructor.params,
+                        &TypeName::Class(class_def.name.clone()),
+                    ),
+                };
+                (class_def.name.clone(), info)
+            })
+            .collect();
+
+        Self { functions, classes }
+    }
+
+    /// Returns `class` followed by each of its known ancestors.
+    fn ancestors<'a>(&'a self, class: &'a str) -> Vec<&'a str> {
+        let mut chain = Vec::new();
+        let mut seen = HashSet::new();
+        let mut current = Some(class);
+        // `seen` guards against `class A extends B` / `class B extends A` cycles
+        while let Some(name) = current {
+            if !seen.insert(name) {
+                break;
+            }
+            chain.push(name);
+            current = self
+                .classes
+                .get(name)
+                .and_then(|info| info.extends.as_deref());
+        }
+        chain
+    }
+
+    pub fn field_type(&self, class: &str, field: &str) -> Option<&TypeName> {
+        self.ancestors(class)
+            .into_iter()
+            .find_map(|name| self.classes.get(name)?.fields.get(field))
+    }
+
+    /// Whether `field` is declared `const` on `class` or one of its ancestors.
+    pub fn is_const_field(&self, class: &str, field: &str) -> bool {
+        self.ancestors(class)
+            .into_iter()
+            .filter_map(|name| self.classes.get(name))
+            .any(|info| info.const_fields.contains(field))
+    }
+
+    pub fn method(&self, class: &str, method: &str) -> Option<&Signature> {
+        self.ancestors(class)
+            .into_iter()
+            .find_map(|name| self.classes.get(name)?.methods.get(method))
+    }
+
+    pub fn method_return_type(&self, class: &str, method: &str) -> Option<&TypeName> {
+        self.method(class, method).map(|signature| &signature.return_type)
+    }
+
+    /// Whether a value of type `found` may be used where `expected` is declared.
+    /// Instances of a subclass are accepted wherever a parent class is expected.
+    pub fn is_assignable(&self, expected: &TypeName, found: &TypeName) -> bool {
+        match (expected, found) {
+            (TypeName::Class(expected), TypeName::Class(found)) => {
+                self.ancestors(found).contains(&expected.as_str())
+            }
+            _ => expected == found,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn collect(input: &str) -> SymbolTable {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        SymbolTable::collect(&ast)
+    }
+
+    #[test]
+    fn test_inherited_members() {
+        let symbols = collect(
+            "class Animal { let name: Str; init() {} meth speak() -> Str { return this.name; } }
+             class Cat extends Animal { init() { super(); } }",
+        );
+        assert_eq!(symbols.field_type("Cat", "name"), Some(&TypeName::Str));
+        assert_eq!(symbols.method_return_type("Cat", "speak"), Some(&TypeName::Str));
+        assert_eq!(symbols.method_return_type("Animal", "purr"), None);
+    }
+
+    #[test]
+    fn test_is_assignable() {
+        let symbols = collect(
+            "class Animal { init() {} }
+             class Cat extends Animal { init() { super(); } }",
+        );
+        let animal = TypeName::Class("Animal".to_string());
+        let cat = TypeName::Class("Cat".to_string());
+        assert!(symbols.is_assignable(&animal, &cat));
+        assert!(!symbols.is_assignable(&cat, &animal));
+        assert!(symbols.is_assignable(&TypeName::Int, &TypeName::Int));
+        assert!(!symbols.is_assignable(&TypeName::Int, &TypeName::Str));
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Runs the compiler with `args`, piping `stdin` in, and returns its stdout
+/// and stderr, with colors off.
+fn run(args: &[&str], stdin: &str) -> (String, String) {
+    run_with_env(args, &[("NO_COLOR", "1")], stdin)
+}
+
+fn run_with_env(args: &[&str], vars: &[(&str, &str)], stdin: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lavascript"))
+        .args(args)
+        .env_remove("NO_COLOR")
+        .envs(vars.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Runs the compiler on `source` piped through stdin, returning its stdout
+/// and stderr.
+fn run_stdin(source: &str) -> (String, String) {
+    run(&["-"], source)
+}
+
+#[test]
+fn test_stdin_reports_warnings_on_stderr() {
+    let (stdout, stderr) = run_stdin("fun f() -> Void { let unused: Int = 1; }");
+    assert!(stderr.contains("W003"), "{}", stderr);
+    assert!(!stdout.contains("W003"));
+    assert!(stdout.contains("function f(){"), "{}", stdout);
+}
+
+#[test]
+fn test_unwritable_source_map_is_not_linked() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("unwritable_source_map");
+    let _ = fs::remove_dir_all(&dir);
+    // a directory where the map should go makes writing it fail
+    fs::create_dir_all(dir.join("out.js.map")).unwrap();
+    let source = dir.join("main.lava");
+    fs::write(&source, "let x: Int = 1;").unwrap();
+    let output = dir.join("out.js");
+
+    let (stdout, stderr) = run(
+        &[source.to_str().unwrap(), output.to_str().unwrap(), "--sourcemap"],
+        "",
+    );
+    assert!(stderr.contains("Error writing source map"), "{}", stderr);
+    assert!(!stdout.contains("Error writing source map"));
+    assert!(!fs::read_to_string(&output).unwrap().contains("sourceMappingURL"));
+}
+
+#[test]
+fn test_no_color() {
+    // colors are forced on, so only the compiler's own switches turn them off
+    let forced = ("CLICOLOR_FORCE", "1");
+    let (stdout, _) = run_with_env(&["-", "--emit=ast"], &[forced], "let x: Int = 1;");
+    assert!(stdout.contains('\x1b'), "{}", stdout);
+
+    for (flags, vars) in [(&["--no-color"][..], &[forced][..]), (&[][..], &[forced, ("NO_COLOR", "1")][..])] {
+        let (stdout, _) = run_with_env(&[&["-", "--emit=ast"], flags].concat(), vars, "let x: Int = 1;");
+        assert!(!stdout.is_empty() && !stdout.contains('\x1b'), "{:?} {:?}", flags, vars);
+
+        let (_, stderr) = run_with_env(&[&["-"], flags].concat(), vars, "let x: Int = ;");
+        assert!(stderr.contains("E012") && !stderr.contains('\x1b'), "{:?} {:?}", flags, vars);
+    }
+}